@@ -4,16 +4,21 @@ use std::sync::OnceLock;
 use async_trait::async_trait;
 use regex::Regex;
 use ricq::handler::QEvent;
+use ricq::structs::{FriendMessage, GroupMessage};
 use tokio::sync::broadcast::{channel, Receiver, Sender};
 use tracing::{error, info, warn};
 
+use crate::config::coordination::Policy;
 use crate::contact::friend::Friend;
+use crate::contact::group::files;
 use crate::contact::member::{AnonymousMember, Member, NamedMember};
 use crate::event::{
     ClientLoginEvent, DeleteFriendEvent, Event, FriendMessageEvent, FriendPokeEvent,
-    GroupMessageEvent, GroupPokeEvent, NewFriendEvent,
+    GroupFileUploadEvent, GroupMessageEvent, GroupPokeEvent, NewFriendEvent,
 };
 use crate::global_listener_worker;
+use crate::message::voice::Voice;
+use crate::service::coordinator::coordinator;
 use crate::{global_listener_runtime, global_status, Client};
 
 static GLOBAL_EVENT_CHANNEL: OnceLock<Sender<Event>> = OnceLock::<Sender<Event>>::new();
@@ -55,6 +60,48 @@ impl ricq::handler::Handler for GlobalEventBroadcastHandler {
             };
         }
 
+        // 语音不在普通消息中, 将其转换为仅包含语音的消息
+        let (event, voice) = match event {
+            QEvent::GroupAudioMessage(e) => {
+                let voice = Voice::group(e.inner.group_code, e.inner.audio);
+                let msg = GroupMessage {
+                    seqs: e.inner.seqs,
+                    rands: e.inner.rands,
+                    group_code: e.inner.group_code,
+                    group_name: e.inner.group_name,
+                    group_card: e.inner.group_card,
+                    from_uin: e.inner.from_uin,
+                    time: e.inner.time,
+                    elements: Default::default(),
+                };
+
+                let e = ricq::client::event::GroupMessageEvent {
+                    client: e.client,
+                    inner: msg,
+                };
+                (QEvent::GroupMessage(e), Some(voice))
+            }
+            QEvent::FriendAudioMessage(e) => {
+                let voice = Voice::friend(e.inner.from_uin, e.inner.audio);
+                let msg = FriendMessage {
+                    seqs: e.inner.seqs,
+                    rands: e.inner.rands,
+                    target: e.inner.target,
+                    time: e.inner.time,
+                    from_uin: e.inner.from_uin,
+                    from_nick: e.inner.from_nick,
+                    elements: Default::default(),
+                };
+
+                let e = ricq::client::event::FriendMessageEvent {
+                    client: e.client,
+                    inner: msg,
+                };
+                (QEvent::FriendMessage(e), Some(voice))
+            }
+            or => (or, None),
+        };
+
         let self_event = match event {
             QEvent::Login(id) => {
                 client = if let Some(b) = get_client(id) {
@@ -77,7 +124,10 @@ impl ricq::handler::Handler for GlobalEventBroadcastHandler {
 
                 let group_name = || get_filter_regex().replace_all(&e.inner.group_name, "");
 
-                let message = || e.inner.elements.to_string().replace('\n', "\\n");
+                let message = || match &voice {
+                    Some(v) => format!("$[Voice:{}]", v.name()),
+                    None => e.inner.elements.to_string().replace('\n', "\\n"),
+                };
 
                 if client.id() == e.inner.from_uin {
                     info!(
@@ -89,12 +139,30 @@ impl ricq::handler::Handler for GlobalEventBroadcastHandler {
                     return;
                 }
 
-                let Some(group) = client.find_or_refresh_group(group_id).await else {
+                // 其他已登录的客户端发送的消息, 协调时由发送者自身记录
+                if coordinator().policy(group_id) != Policy::Broadcast
+                    && global_status().clients.contains_key(&e.inner.from_uin)
+                {
+                    return;
+                }
+
+                if client.find_or_refresh_group(group_id).await.is_none() {
                     cannot_find_group(group_id);
                     error_more_info(&e);
 
+                    return;
+                }
+
+                let key = e.inner.seqs.first().map(|&seq| {
+                    let rand = e.inner.rands.first().copied().unwrap_or_default();
+                    (group_id, seq, rand)
+                });
+
+                // 多个客户端处于同一群时, 仅由协调器选出的客户端处理
+                let Some(group) = coordinator().route(&client, group_id, key) else {
                     return;
                 };
+                let client = group.client();
 
                 let sender = e.inner.from_uin;
 
@@ -141,8 +209,23 @@ impl ricq::handler::Handler for GlobalEventBroadcastHandler {
                     message(),
                 );
 
-                let base = GroupMessageEvent::from(group, member, e);
-                Event::GroupMessage(base)
+                let upload = match &member {
+                    Member::Named(m) => {
+                        files::parse_upload(&e.inner.elements, sender, e.inner.time as i64)
+                            .map(|f| (m.clone(), f))
+                    }
+                    Member::Anonymous(_) => None,
+                };
+
+                if let Some((uploader, file)) = upload {
+                    info!("{uploader}上传了群文件: {} >> {group}", file.name);
+
+                    let base = GroupFileUploadEvent::from(group, uploader, file);
+                    Event::GroupFileUpload(base)
+                } else {
+                    let base = GroupMessageEvent::from(group, member, e, voice);
+                    Event::GroupMessage(base)
+                }
             }
             QEvent::FriendMessage(e) => {
                 client = get_client!(e.client);
@@ -154,9 +237,12 @@ impl ricq::handler::Handler for GlobalEventBroadcastHandler {
                     return;
                 };
 
-                info!("{friend} >> {client}: {}", e.inner.elements,);
+                match &voice {
+                    Some(v) => info!("{friend} >> {client}: $[Voice:{}]", v.name()),
+                    None => info!("{friend} >> {client}: {}", e.inner.elements),
+                }
 
-                let base = FriendMessageEvent::from(friend, e);
+                let base = FriendMessageEvent::from(friend, e, voice);
 
                 Event::FriendMessage(base)
             }
@@ -198,12 +284,18 @@ impl ricq::handler::Handler for GlobalEventBroadcastHandler {
             QEvent::GroupPoke(e) => {
                 client = get_client!(e.client);
                 let group_id = e.inner.group_code;
-                let Some(group) = client.find_or_refresh_group(group_id).await else {
+                if client.find_or_refresh_group(group_id).await.is_none() {
                     cannot_find_group(group_id);
                     error_more_info(&e);
 
+                    return;
+                }
+
+                let key = ("poke", group_id, e.inner.sender, e.inner.receiver);
+                let Some(group) = coordinator().route_event(&client, group_id, key) else {
                     return;
                 };
+                let client = group.client();
 
                 let sender = e.inner.sender;
                 let Some(sender) = group.find_member(sender).await else {
@@ -211,7 +303,7 @@ impl ricq::handler::Handler for GlobalEventBroadcastHandler {
                     return;
                 };
 
-                let target = e.inner.sender;
+                let target = e.inner.receiver;
                 let Some(target) = group.find_member(target).await else {
                     error!("无法找到群成员{target}, Raw event: {:?}", e);
                     return;
@@ -228,7 +320,7 @@ impl ricq::handler::Handler for GlobalEventBroadcastHandler {
                 let op_id = e.inner.operator_uin;
 
                 if let Some(g) = client.find_or_refresh_group(group_id).await {
-                    let member = g.members_cache().get(&op_id).and_then(|r| r.to_owned());
+                    let member = g.members_cache().get(op_id);
 
                     let name = member
                         .map(|n| n.card_name().to_owned())
@@ -240,6 +332,11 @@ impl ricq::handler::Handler for GlobalEventBroadcastHandler {
 
                 client.remove_group_cache(e.inner.group_code);
 
+                let key = ("disband", group_id, op_id, 0);
+                if !coordinator().claim_event(client.id(), group_id, key) {
+                    return;
+                }
+
                 Event::Unknown(QEvent::GroupDisband(e).into())
             }
             QEvent::NewMember(e) => {
@@ -258,6 +355,14 @@ impl ricq::handler::Handler for GlobalEventBroadcastHandler {
                     let _member = group.try_refresh_member(member_id).await;
                 }
 
+                if !coordinator().claim_event(
+                    client.id(),
+                    group_id,
+                    ("new_member", group_id, member_id, 0),
+                ) {
+                    return;
+                }
+
                 Event::Unknown(QEvent::NewMember(e).into())
             }
             QEvent::GroupLeave(e) => {
@@ -274,6 +379,11 @@ impl ricq::handler::Handler for GlobalEventBroadcastHandler {
                     group.remove_member_cache(member);
                 }
 
+                if !coordinator().claim_event(client.id(), group_id, ("leave", group_id, member, 0))
+                {
+                    return;
+                }
+
                 Event::Unknown(QEvent::GroupLeave(e).into())
             }
             QEvent::KickedOffline(e) => {
@@ -302,6 +412,8 @@ impl ricq::handler::Handler for GlobalEventBroadcastHandler {
         };
 
         global_listener_runtime().spawn(async move {
+            crate::message::reply::record_event(&self_event);
+            crate::service::history::record_event(&self_event);
             global_listener_worker().handle(&self_event).await;
 
             let _ = global_sender().send(self_event);
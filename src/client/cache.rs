@@ -0,0 +1,394 @@
+use std::io;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use ricq::structs::{FriendInfo, GroupInfo};
+use serde::{Deserialize, Serialize};
+
+use crate::client::crypto;
+use crate::contact::friend::Friend;
+use crate::contact::group::Group;
+use crate::contact::member::{MemberPermission, NamedMember};
+use crate::{Client, GroupMemberInfo};
+
+/// 联系人缓存文件名
+pub const SNAPSHOT_FILE: &str = "contacts.json";
+
+/// 客户端的联系人缓存快照, 包含好友, 群及已缓存的群成员
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ContactSnapshot {
+    /// 保存时间(Unix秒)
+    pub saved_at: u64,
+    pub friends: Vec<FriendSnapshot>,
+    pub groups: Vec<GroupSnapshot>,
+}
+
+impl ContactSnapshot {
+    /// 生成客户端当前缓存的快照
+    pub fn capture(client: &Client) -> Self {
+        let friends = client.friends().iter().map(|f| f.info().into()).collect();
+
+        let groups = client
+            .groups()
+            .iter()
+            .map(|g| GroupSnapshot {
+                info: g.info().into(),
+                members: g
+                    .members_cache()
+                    .members()
+                    .iter()
+                    .map(|named| named.info().into())
+                    .collect(),
+                member_list_complete: g.members_cache().is_list_complete(),
+            })
+            .collect();
+
+        Self {
+            saved_at: unix_now(),
+            friends,
+            groups,
+        }
+    }
+
+    /// 快照是否为空
+    pub fn is_empty(&self) -> bool {
+        self.friends.is_empty() && self.groups.is_empty()
+    }
+
+    /// 距保存时经过的时间
+    pub fn age(&self) -> Duration {
+        Duration::from_secs(unix_now().saturating_sub(self.saved_at))
+    }
+
+    /// 将快照中的联系人加载至客户端缓存
+    ///
+    /// 群成员的缓存时间为快照的保存时间, 以便按缓存策略判断是否过期
+    pub fn restore(self, client: &Client) {
+        let cached_at = Instant::now()
+            .checked_sub(self.age())
+            .unwrap_or_else(Instant::now);
+
+        for f in self.friends {
+            client.cache_friend(Friend::from(client, f.into()));
+        }
+
+        for g in self.groups {
+            let group = Group::from(client, g.info.into());
+            let cache = group.members_cache();
+            for m in g.members {
+                let named = NamedMember::from(&group, m.into());
+                cache.insert_at(named.id(), Some(named), cached_at);
+            }
+            if g.member_list_complete {
+                cache.set_list_refreshed_at(Some(cached_at));
+            }
+
+            client.cache_group(group);
+        }
+    }
+
+    /// 从工作目录读取快照, 文件不存在时返回`None`
+    pub fn read<P: AsRef<Path>>(dir: P) -> io::Result<Option<Self>> {
+        let bytes = match std::fs::read(dir.as_ref().join(SNAPSHOT_FILE)) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let bytes =
+            crypto::open_bytes(bytes).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+
+        serde_json::from_slice(&bytes)
+            .map(Some)
+            .map_err(io::Error::from)
+    }
+
+    /// 将快照写入工作目录, 若启用了登录信息加密则同样加密
+    pub fn write<P: AsRef<Path>>(&self, dir: P) -> io::Result<()> {
+        let json = serde_json::to_vec(self)?;
+        std::fs::write(
+            dir.as_ref().join(SNAPSHOT_FILE),
+            crypto::seal_bytes_if_enabled(json),
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct FriendSnapshot {
+    pub uin: i64,
+    pub nick: String,
+    pub remark: String,
+    pub face_id: i16,
+    pub group_id: u8,
+}
+
+impl From<&FriendInfo> for FriendSnapshot {
+    fn from(
+        FriendInfo {
+            uin,
+            nick,
+            remark,
+            face_id,
+            group_id,
+        }: &FriendInfo,
+    ) -> Self {
+        Self {
+            uin: *uin,
+            nick: nick.clone(),
+            remark: remark.clone(),
+            face_id: *face_id,
+            group_id: *group_id,
+        }
+    }
+}
+
+impl From<FriendSnapshot> for FriendInfo {
+    fn from(
+        FriendSnapshot {
+            uin,
+            nick,
+            remark,
+            face_id,
+            group_id,
+        }: FriendSnapshot,
+    ) -> Self {
+        Self {
+            uin,
+            nick,
+            remark,
+            face_id,
+            group_id,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct GroupSnapshot {
+    #[serde(flatten)]
+    pub info: GroupInfoSnapshot,
+    /// 已缓存的群成员
+    #[serde(default)]
+    pub members: Vec<MemberSnapshot>,
+    /// 群成员列表是否完整
+    #[serde(default)]
+    pub member_list_complete: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct GroupInfoSnapshot {
+    pub uin: i64,
+    pub code: i64,
+    pub name: String,
+    pub memo: String,
+    pub owner_uin: i64,
+    pub group_create_time: u32,
+    pub group_level: u32,
+    pub member_count: u16,
+    pub max_member_count: u16,
+    pub shut_up_timestamp: i64,
+    pub my_shut_up_timestamp: i64,
+    pub last_msg_seq: i64,
+}
+
+impl From<&GroupInfo> for GroupInfoSnapshot {
+    fn from(
+        GroupInfo {
+            uin,
+            code,
+            name,
+            memo,
+            owner_uin,
+            group_create_time,
+            group_level,
+            member_count,
+            max_member_count,
+            shut_up_timestamp,
+            my_shut_up_timestamp,
+            last_msg_seq,
+        }: &GroupInfo,
+    ) -> Self {
+        Self {
+            uin: *uin,
+            code: *code,
+            name: name.clone(),
+            memo: memo.clone(),
+            owner_uin: *owner_uin,
+            group_create_time: *group_create_time,
+            group_level: *group_level,
+            member_count: *member_count,
+            max_member_count: *max_member_count,
+            shut_up_timestamp: *shut_up_timestamp,
+            my_shut_up_timestamp: *my_shut_up_timestamp,
+            last_msg_seq: *last_msg_seq,
+        }
+    }
+}
+
+impl From<GroupInfoSnapshot> for GroupInfo {
+    fn from(
+        GroupInfoSnapshot {
+            uin,
+            code,
+            name,
+            memo,
+            owner_uin,
+            group_create_time,
+            group_level,
+            member_count,
+            max_member_count,
+            shut_up_timestamp,
+            my_shut_up_timestamp,
+            last_msg_seq,
+        }: GroupInfoSnapshot,
+    ) -> Self {
+        Self {
+            uin,
+            code,
+            name,
+            memo,
+            owner_uin,
+            group_create_time,
+            group_level,
+            member_count,
+            max_member_count,
+            shut_up_timestamp,
+            my_shut_up_timestamp,
+            last_msg_seq,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct MemberSnapshot {
+    pub group_code: i64,
+    pub uin: i64,
+    pub gender: u8,
+    pub nickname: String,
+    pub card_name: String,
+    pub level: u16,
+    pub join_time: i64,
+    pub last_speak_time: i64,
+    pub special_title: String,
+    pub special_title_expire_time: i64,
+    pub shut_up_timestamp: i64,
+    /// 同[`MemberPermission`]: 0: 成员, 1: 管理员, 2: 群主
+    pub permission: u8,
+}
+
+impl From<&GroupMemberInfo> for MemberSnapshot {
+    fn from(
+        GroupMemberInfo {
+            group_code,
+            uin,
+            gender,
+            nickname,
+            card_name,
+            level,
+            join_time,
+            last_speak_time,
+            special_title,
+            special_title_expire_time,
+            shut_up_timestamp,
+            permission,
+        }: &GroupMemberInfo,
+    ) -> Self {
+        Self {
+            group_code: *group_code,
+            uin: *uin,
+            gender: *gender,
+            nickname: nickname.clone(),
+            card_name: card_name.clone(),
+            level: *level,
+            join_time: *join_time,
+            last_speak_time: *last_speak_time,
+            special_title: special_title.clone(),
+            special_title_expire_time: *special_title_expire_time,
+            shut_up_timestamp: *shut_up_timestamp,
+            permission: MemberPermission::from(permission) as u8,
+        }
+    }
+}
+
+impl From<MemberSnapshot> for GroupMemberInfo {
+    fn from(
+        MemberSnapshot {
+            group_code,
+            uin,
+            gender,
+            nickname,
+            card_name,
+            level,
+            join_time,
+            last_speak_time,
+            special_title,
+            special_title_expire_time,
+            shut_up_timestamp,
+            permission,
+        }: MemberSnapshot,
+    ) -> Self {
+        Self {
+            group_code,
+            uin,
+            gender,
+            nickname,
+            card_name,
+            level,
+            join_time,
+            last_speak_time,
+            special_title,
+            special_title_expire_time,
+            shut_up_timestamp,
+            permission: MemberPermission::from(permission).into(),
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use ricq::structs::GroupMemberPermission;
+
+    use super::{ContactSnapshot, GroupSnapshot, MemberSnapshot};
+    use crate::contact::member::MemberPermission;
+    use crate::GroupMemberInfo;
+
+    #[test]
+    fn member_round_trip() {
+        let snapshot = MemberSnapshot {
+            group_code: 114514,
+            uin: 1919810,
+            card_name: "atri".into(),
+            permission: MemberPermission::Owner as u8,
+            ..Default::default()
+        };
+
+        let info: GroupMemberInfo = snapshot.clone().into();
+        assert!(matches!(info.permission, GroupMemberPermission::Owner));
+        assert_eq!(MemberSnapshot::from(&info), snapshot);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let snapshot = ContactSnapshot {
+            saved_at: 1,
+            friends: vec![],
+            groups: vec![GroupSnapshot {
+                member_list_complete: true,
+                members: vec![MemberSnapshot::default()],
+                ..Default::default()
+            }],
+        };
+
+        let json = serde_json::to_vec(&snapshot).unwrap();
+        let de: ContactSnapshot = serde_json::from_slice(&json).unwrap();
+        assert_eq!(de.groups, snapshot.groups);
+        assert!(de.age().as_secs() > 0);
+    }
+}
@@ -0,0 +1,257 @@
+use std::path::Path;
+use std::sync::OnceLock;
+use std::{fs, io};
+
+use chacha20poly1305::aead::Aead;
+use chacha20poly1305::{ChaCha20Poly1305, Key, KeyInit, Nonce};
+use rand::{thread_rng, RngCore};
+use sha2::{Digest, Sha256};
+
+use crate::config::login::EncryptionConfig;
+use crate::error::{AtriResult, LoginError};
+
+/// 加密文件的文件头
+pub const SEALED_MAGIC: &[u8] = b"ATRIENC1";
+
+/// 加密后的密码前缀
+pub const SEALED_PASSWORD_PREFIX: &str = "enc:";
+
+const NONCE_SIZE: usize = 12;
+
+/// 用于加密登录信息(Token, 设备信息, 密码)的密钥
+pub struct StorageKey(ChaCha20Poly1305);
+
+impl StorageKey {
+    /// 从任意密钥材料派生密钥
+    pub fn from_material(material: &[u8]) -> Self {
+        let digest = Sha256::digest(material);
+        Self(ChaCha20Poly1305::new(Key::from_slice(&digest)))
+    }
+
+    /// 加密, 返回(nonce, 密文)
+    pub fn seal(&self, plain: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        let mut nonce = [0u8; NONCE_SIZE];
+        thread_rng().fill_bytes(&mut nonce);
+
+        let sealed = self
+            .0
+            .encrypt(Nonce::from_slice(&nonce), plain)
+            .expect("Cannot encrypt data");
+
+        (nonce.to_vec(), sealed)
+    }
+
+    /// 解密, 密钥错误或数据被篡改时返回[`LoginError::WrongStorageKey`]
+    pub fn open(&self, nonce: &[u8], sealed: &[u8]) -> Result<Vec<u8>, LoginError> {
+        if nonce.len() != NONCE_SIZE {
+            return Err(LoginError::WrongStorageKey);
+        }
+
+        self.0
+            .decrypt(Nonce::from_slice(nonce), sealed)
+            .map_err(|_| LoginError::WrongStorageKey)
+    }
+
+    /// 加密为带有文件头的字节
+    pub fn seal_bytes(&self, plain: &[u8]) -> Vec<u8> {
+        let (nonce, sealed) = self.seal(plain);
+
+        let mut bytes = Vec::with_capacity(SEALED_MAGIC.len() + nonce.len() + sealed.len());
+        bytes.extend_from_slice(SEALED_MAGIC);
+        bytes.extend_from_slice(&nonce);
+        bytes.extend_from_slice(&sealed);
+        bytes
+    }
+
+    /// 加密密码, 结果形如`enc:<base64>`
+    pub fn seal_password(&self, password: &str) -> String {
+        let (mut nonce, sealed) = self.seal(password.as_bytes());
+        nonce.extend_from_slice(&sealed);
+
+        let mut s = String::from(SEALED_PASSWORD_PREFIX);
+        s.push_str(&base64::encode(nonce));
+        s
+    }
+}
+
+/// 文件是否为加密文件
+pub fn is_sealed(bytes: &[u8]) -> bool {
+    bytes.starts_with(SEALED_MAGIC)
+}
+
+/// 密码是否已加密
+pub fn is_sealed_password(password: &str) -> bool {
+    password.starts_with(SEALED_PASSWORD_PREFIX)
+}
+
+/// 若数据已加密则使用全局密钥解密, 否则原样返回
+pub fn open_bytes(bytes: Vec<u8>) -> Result<Vec<u8>, LoginError> {
+    if !is_sealed(&bytes) {
+        return Ok(bytes);
+    }
+
+    let key = storage_key().ok_or(LoginError::StorageKeyNotProvided)?;
+    let body = &bytes[SEALED_MAGIC.len()..];
+    if body.len() < NONCE_SIZE {
+        return Err(LoginError::WrongStorageKey);
+    }
+
+    let (nonce, sealed) = body.split_at(NONCE_SIZE);
+    key.open(nonce, sealed)
+}
+
+/// 若密码已加密则使用全局密钥解密, 否则原样返回
+pub fn open_password(password: &str) -> Result<String, LoginError> {
+    let Some(encoded) = password.strip_prefix(SEALED_PASSWORD_PREFIX) else {
+        return Ok(password.to_owned());
+    };
+
+    let key = storage_key().ok_or(LoginError::StorageKeyNotProvided)?;
+    let bytes = base64::decode(encoded).map_err(|_| LoginError::WrongStorageKey)?;
+    if bytes.len() < NONCE_SIZE {
+        return Err(LoginError::WrongStorageKey);
+    }
+
+    let (nonce, sealed) = bytes.split_at(NONCE_SIZE);
+    let plain = key.open(nonce, sealed)?;
+
+    String::from_utf8(plain).map_err(|_| LoginError::WrongStorageKey)
+}
+
+/// 若配置了密钥则加密, 否则原样返回
+pub fn seal_bytes_if_enabled(bytes: Vec<u8>) -> Vec<u8> {
+    match storage_key() {
+        Some(key) => key.seal_bytes(&bytes),
+        None => bytes,
+    }
+}
+
+static STORAGE_KEY: OnceLock<Option<StorageKey>> = OnceLock::new();
+
+/// 根据配置初始化全局密钥, 仅第一次调用有效
+///
+/// 优先读取环境变量, 其次读取密钥文件, 均未配置则不启用加密.
+/// 配置了环境变量但未设置(或为空), 且未配置密钥文件时返回[`LoginError::StorageKeyNotProvided`],
+/// 不会退回明文保存
+pub fn init_storage_key(conf: &EncryptionConfig) -> AtriResult<()> {
+    let env = conf
+        .key_env
+        .as_ref()
+        .and_then(|name| std::env::var(name).ok())
+        .filter(|env| !env.is_empty());
+
+    let material = if let Some(env) = env {
+        Some(env.into_bytes())
+    } else if let Some(path) = &conf.key_file {
+        Some(fs::read(path)?)
+    } else if conf.key_env.is_some() {
+        return Err(LoginError::StorageKeyNotProvided.into());
+    } else {
+        None
+    };
+
+    let _ = STORAGE_KEY.set(material.map(|m| StorageKey::from_material(&m)));
+
+    Ok(())
+}
+
+/// 全局密钥, 未启用加密时返回`None`
+pub fn storage_key() -> Option<&'static StorageKey> {
+    STORAGE_KEY.get().and_then(Option::as_ref)
+}
+
+/// 加密客户端目录下的登录信息, 返回转换的文件数
+pub fn migrate_client_dir<P: AsRef<Path>>(dir: P, key: &StorageKey) -> io::Result<usize> {
+    use crate::client::token::Token;
+
+    let dir = dir.as_ref();
+    let mut migrated = 0;
+
+    let bin = dir.join("token.bin");
+    if let Ok(bytes) = fs::read(&bin) {
+        let token: Token = prost::Message::decode(&*bytes)?;
+        if !token.is_encrypted() {
+            fs::write(&bin, prost::Message::encode_to_vec(&token.encrypt(key)))?;
+            migrated += 1;
+        }
+    }
+
+    let json = dir.join("token.json");
+    if let Ok(bytes) = fs::read(&json) {
+        let token: Token = serde_json::from_slice(&bytes)?;
+        if !token.is_encrypted() {
+            fs::write(&json, serde_json::to_vec_pretty(&token.encrypt(key))?)?;
+            migrated += 1;
+        }
+    }
+
+    let device = dir.join("device.json");
+    if let Ok(bytes) = fs::read(&device) {
+        if !is_sealed(&bytes) {
+            fs::write(&device, key.seal_bytes(&bytes))?;
+            migrated += 1;
+        }
+    }
+
+    Ok(migrated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StorageKey;
+    use crate::client::token::Token;
+    use crate::config::login::EncryptionConfig;
+    use crate::error::{AtriError, LoginError};
+
+    #[test]
+    fn missing_key_env() {
+        let conf = EncryptionConfig {
+            key_env: Some("ATRI_TEST_UNSET_STORAGE_KEY".into()),
+            key_file: None,
+        };
+
+        assert!(matches!(
+            super::init_storage_key(&conf),
+            Err(AtriError::Login(LoginError::StorageKeyNotProvided))
+        ));
+    }
+
+    #[test]
+    fn token_round_trip() {
+        let key = StorageKey::from_material(b"atri");
+        let token = Token {
+            uin: 114514,
+            d2: vec![1, 9, 1, 9],
+            ..Default::default()
+        };
+
+        let sealed = token.clone().encrypt(&key);
+        assert!(sealed.is_encrypted());
+        assert_eq!(sealed.uin, 114514);
+        assert!(sealed.d2.is_empty());
+
+        let opened = sealed.clone().decrypt(Some(&key)).unwrap();
+        assert_eq!(opened.d2, token.d2);
+
+        let wrong = StorageKey::from_material(b"not atri");
+        assert!(matches!(
+            sealed.clone().decrypt(Some(&wrong)),
+            Err(LoginError::WrongStorageKey)
+        ));
+        assert!(matches!(
+            sealed.decrypt(None),
+            Err(LoginError::StorageKeyNotProvided)
+        ));
+    }
+
+    #[test]
+    fn password_round_trip() {
+        let key = StorageKey::from_material(b"atri");
+        let sealed = key.seal_password("1919810");
+        assert!(super::is_sealed_password(&sealed));
+
+        let bytes = base64::decode(&sealed[super::SEALED_PASSWORD_PREFIX.len()..]).unwrap();
+        let (nonce, body) = bytes.split_at(super::NONCE_SIZE);
+        assert_eq!(key.open(nonce, body).unwrap(), b"1919810");
+    }
+}
@@ -0,0 +1,215 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use dashmap::DashMap;
+use ricq::msg::elem::{FriendImage, GroupImage};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::sync::Mutex;
+
+use crate::message::hex;
+
+/// 图片缓存文件名
+pub const IMAGE_CACHE_FILE: &str = "images.json";
+
+/// 缓存的有效时间, 服务器上的图片可能被清理, 过期后将重新上传
+pub const IMAGE_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// 已上传图片的缓存, 以图片的md5为键, 群图片与好友图片分开保存
+///
+/// 再次上传相同的图片时直接使用缓存, 不再请求服务器. 缓存在[`IMAGE_TTL`]后过期
+pub struct ImageCache {
+    path: PathBuf,
+    group: DashMap<String, Cached<GroupImage>>,
+    friend: DashMap<String, Cached<FriendImage>>,
+    /// 保证文件的写入按顺序进行
+    write: Mutex<()>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Cached<I> {
+    image: I,
+    /// 上传时间(Unix秒)
+    cached_at: u64,
+}
+
+impl<I: Clone> Cached<I> {
+    fn new(image: I) -> Self {
+        Self {
+            image,
+            cached_at: unix_now(),
+        }
+    }
+
+    fn is_expired(&self, now: u64) -> bool {
+        now.saturating_sub(self.cached_at) >= IMAGE_TTL.as_secs()
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct ImageCacheFile {
+    group: Vec<(String, Cached<GroupImage>)>,
+    friend: Vec<(String, Cached<FriendImage>)>,
+}
+
+impl ImageCache {
+    /// 从工作目录加载缓存, 文件不存在或无法解析时为空, 已过期的条目将被忽略
+    pub fn load(work_dir: &Path) -> Self {
+        let path = work_dir.join(IMAGE_CACHE_FILE);
+        let file: ImageCacheFile = std::fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+
+        let now = unix_now();
+        Self {
+            path,
+            group: file
+                .group
+                .into_iter()
+                .filter(|(_, c)| !c.is_expired(now))
+                .collect(),
+            friend: file
+                .friend
+                .into_iter()
+                .filter(|(_, c)| !c.is_expired(now))
+                .collect(),
+            write: Mutex::new(()),
+        }
+    }
+
+    /// 获取未过期的群图片, 过期的条目将被移除
+    pub fn group(&self, md5: &str) -> Option<GroupImage> {
+        get(&self.group, md5)
+    }
+
+    /// 获取未过期的好友图片, 过期的条目将被移除
+    pub fn friend(&self, md5: &str) -> Option<FriendImage> {
+        get(&self.friend, md5)
+    }
+
+    pub async fn insert_group(&self, md5: String, image: GroupImage) -> io::Result<()> {
+        self.group.insert(md5, Cached::new(image));
+        self.save().await
+    }
+
+    pub async fn insert_friend(&self, md5: String, image: FriendImage) -> io::Result<()> {
+        self.friend.insert(md5, Cached::new(image));
+        self.save().await
+    }
+
+    /// 移除图片, 如使用缓存的图片发送失败时
+    pub async fn remove(&self, md5: &str) -> io::Result<()> {
+        let group = self.group.remove(md5).is_some();
+        let friend = self.friend.remove(md5).is_some();
+        if group || friend {
+            self.save().await?;
+        }
+
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.group.len() + self.friend.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 清空缓存, 如缓存的图片已在服务器过期
+    pub async fn clear(&self) -> io::Result<()> {
+        self.group.clear();
+        self.friend.clear();
+        self.save().await
+    }
+
+    /// 先写入临时文件再替换, 避免写入中断时损坏缓存文件
+    async fn save(&self) -> io::Result<()> {
+        let _write = self.write.lock().await;
+
+        // 获取写锁后再读取, 保证最后写入的是最新的内容
+        let file = ImageCacheFile {
+            group: self
+                .group
+                .iter()
+                .map(|e| (e.key().clone(), e.value().clone()))
+                .collect(),
+            friend: self
+                .friend
+                .iter()
+                .map(|e| (e.key().clone(), e.value().clone()))
+                .collect(),
+        };
+
+        let json = serde_json::to_vec(&file)?;
+        let tmp = self.path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, json).await?;
+        tokio::fs::rename(&tmp, &self.path).await
+    }
+}
+
+fn get<I: Clone>(map: &DashMap<String, Cached<I>>, md5: &str) -> Option<I> {
+    let now = unix_now();
+    let cached = map.get(md5)?;
+    if !cached.is_expired(now) {
+        return Some(cached.image.clone());
+    }
+
+    drop(cached);
+    map.remove_if(md5, |_, c| c.is_expired(now));
+    None
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// 十六进制编码的md5, 作为缓存的键
+pub fn image_md5(image: &[u8]) -> String {
+    hex(&md5::compute(image).0)
+}
+
+/// 读取全部内容
+///
+/// 上传前需以图片的md5与大小向服务器申请上传, 且服务器已存在相同图片时无需传输,
+/// 因此无法边读取边上传, 需读取完整的图片数据
+pub async fn read_image<R: AsyncRead + Unpin>(mut reader: R) -> io::Result<Vec<u8>> {
+    let mut buf = vec![];
+    reader.read_to_end(&mut buf).await?;
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use dashmap::DashMap;
+
+    use super::{get, image_md5, unix_now, Cached, IMAGE_TTL};
+
+    #[test]
+    fn md5() {
+        assert_eq!(image_md5(b""), "d41d8cd98f00b204e9800998ecf8427e");
+    }
+
+    #[test]
+    fn expire() {
+        let map = DashMap::new();
+        map.insert("fresh".to_owned(), Cached::new(1));
+        map.insert(
+            "expired".to_owned(),
+            Cached {
+                image: 2,
+                cached_at: unix_now() - IMAGE_TTL.as_secs(),
+            },
+        );
+
+        assert_eq!(get(&map, "fresh"), Some(1));
+        assert_eq!(get(&map, "expired"), None);
+        assert!(!map.contains_key("expired"));
+        assert_eq!(get(&map, "missing"), None);
+    }
+}
@@ -1,5 +1,10 @@
+pub mod cache;
+pub mod crypto;
+pub mod image_cache;
 pub mod info;
+pub mod proxy;
 pub mod token;
+pub mod web;
 
 use dashmap::DashMap;
 use std::fmt::{Debug, Display, Formatter};
@@ -9,18 +14,22 @@ use std::sync::atomic::Ordering;
 use std::sync::{Arc, Weak};
 use std::time::Duration;
 
+use crate::client::cache::ContactSnapshot;
+use crate::client::crypto;
+use crate::client::image_cache::ImageCache;
 use crate::client::info::AccountInfo;
+use crate::client::proxy::Proxy;
 use crate::client::token::Token;
-use crate::contact::friend::Friend;
-use ricq::client::DefaultConnector;
+use crate::config::login::AddressFamily;
+use crate::contact::friend::{Friend, FriendCategory};
 use ricq::ext::common::after_login;
-use ricq::ext::reconnect::{auto_reconnect, Credential};
 use ricq::{Client as RQClient, LoginResponse};
 use tokio::io;
 use tracing::{error, info, warn};
 
 use crate::contact::group::Group;
 use crate::error::{AtriError, AtriResult, LoginError};
+use crate::service::cache::cache_config;
 use crate::{config, global_status};
 
 /// 一个`客户端`
@@ -28,12 +37,12 @@ use crate::{config, global_status};
 pub struct Client(Arc<imp::Client>);
 
 impl Client {
-    pub async fn new(id: i64, conf: ClientConfiguration) -> Self {
-        let inner = imp::ClientInner::new(id, conf).await;
-        Self(Arc::new_cyclic(|weak| imp::Client {
+    pub async fn new(id: i64, conf: ClientConfiguration) -> AtriResult<Self> {
+        let inner = imp::ClientInner::new(id, conf).await?;
+        Ok(Self(Arc::new_cyclic(|weak| imp::Client {
             inner,
             weak: weak.clone(),
-        }))
+        })))
     }
 
     pub async fn try_login(&self) -> AtriResult<()> {
@@ -48,11 +57,14 @@ impl Client {
             };
 
             token
-        } else if let Ok(f) = tokio::fs::File::open(self.work_dir().join("token.json")).await {
-            let std_file = f.into_std().await;
-            tokio::task::block_in_place(|| {
-                serde_json::from_reader(&std_file).expect("Cannot read token from file")
-            })
+        } else if let Ok(bytes) = tokio::fs::read(self.work_dir().join("token.json")).await {
+            let Ok(token) = serde_json::from_slice(&bytes) else {
+                error!("{}登录失败: Token不合法", self);
+
+                return Err(AtriError::Login(LoginError::WrongToken));
+            };
+
+            token
         } else {
             error!("{}登陆失败: 无法读取Token", self);
 
@@ -63,6 +75,12 @@ impl Client {
             return Err(AtriError::Login(LoginError::WrongToken));
         }
 
+        let token = token.decrypt(crypto::storage_key()).map_err(|e| {
+            error!("{}登录失败: 无法解密Token: {}", self, e);
+
+            AtriError::Login(e)
+        })?;
+
         let rq_token: ricq::client::Token = token.into();
 
         let resp = self.0.client.token_login(rq_token).await?;
@@ -79,6 +97,11 @@ impl Client {
 
             let token = self.gen_token().await;
 
+            let token = match crypto::storage_key() {
+                Some(key) => token.encrypt(key),
+                None => token,
+            };
+
             tokio::task::spawn_blocking(move || {
                 if let Ok(mut file) = std::fs::File::create(&binp) {
                     let proto = prost::Message::encode_to_vec(&token);
@@ -222,15 +245,42 @@ impl Client {
     }
 
     /// 重连此客户端, 断线时使用
+    ///
+    /// 与首次连接相同, 使用配置的代理, 地址族与服务器列表
     pub async fn reconnect(&self) {
-        auto_reconnect(
-            self.0.client.clone(),
-            Credential::Token(self.request_client().gen_token().await),
-            Duration::from_secs(12),
-            5,
-            DefaultConnector,
-        )
-        .await;
+        const RECONNECT_INTERVAL: Duration = Duration::from_secs(12);
+        const MAX_TIMES: usize = 5;
+
+        let token = self.request_client().gen_token().await;
+        for times in 1..=MAX_TIMES {
+            self.request_client()
+                .stop(ricq::client::NetworkStatus::Drop);
+            tokio::time::sleep(RECONNECT_INTERVAL).await;
+
+            let stream = match self.0.connect().await {
+                Ok(s) => s,
+                Err(e) => {
+                    error!("{}重连失败({}/{}): {}", self, times, MAX_TIMES, e);
+                    continue;
+                }
+            };
+
+            // 网络循环在此处已启动, 无需等待其结束
+            let _handle = self.0.start(stream);
+            tokio::task::yield_now().await;
+
+            match self.request_client().token_login(token.clone()).await {
+                Ok(LoginResponse::Success(..)) => {
+                    after_login(&self.0.client).await;
+                    info!("{}重连成功", self);
+                    return;
+                }
+                Ok(resp) => error!("{}重连登录失败({}/{}): {:?}", self, times, MAX_TIMES, resp),
+                Err(e) => error!("{}重连登录失败({}/{}): {}", self, times, MAX_TIMES, e),
+            }
+        }
+
+        error!("{}重连失败, 已达最大重试次数", self);
     }
 
     /// 生成登录凭证
@@ -247,15 +297,44 @@ impl Client {
             .collect()
     }
 
+    /// 所有已登录客户端加入的群, 以(客户端, 群)的形式迭代
+    pub fn all_groups() -> impl Iterator<Item = (Client, Group)> {
+        Self::sorted_list().into_iter().flat_map(|client| {
+            client
+                .groups()
+                .into_iter()
+                .map(move |g| (client.clone(), g))
+        })
+    }
+
+    /// 所有已登录客户端添加的好友, 以(客户端, 好友)的形式迭代
+    pub fn all_friends() -> impl Iterator<Item = (Client, Friend)> {
+        Self::sorted_list().into_iter().flat_map(|client| {
+            client
+                .friends()
+                .into_iter()
+                .map(move |f| (client.clone(), f))
+        })
+    }
+
     /// 刷新好友列表
     pub async fn refresh_friend_list(&self) -> AtriResult<()> {
         let list = self.request_client().get_friend_list().await?;
 
+        // 移除已不存在的好友, 缓存可能来自持久化的快照
+        self.friend_caches()
+            .retain(|id, _| list.friends.iter().any(|info| info.uin == *id));
+
         for info in list.friends {
             self.friend_caches()
                 .insert(info.uin, Friend::from(self, info));
         }
 
+        self.0.friend_categories.clear();
+        for (id, info) in list.friend_groups {
+            self.0.friend_categories.insert(id, info.into());
+        }
+
         Ok(())
     }
 
@@ -263,6 +342,9 @@ impl Client {
     pub async fn refresh_group_list(&self) -> AtriResult<()> {
         let infos = self.request_client().get_group_list().await?;
 
+        self.group_caches()
+            .retain(|id, _| infos.iter().any(|info| info.code == *id));
+
         for info in infos {
             let group = Group::from(self, info);
 
@@ -320,11 +402,64 @@ impl Client {
         self.0.friends.iter().map(|f| f.clone()).collect()
     }
 
+    /// 好友分组的列表, 在刷新好友列表时更新
+    pub fn friend_categories(&self) -> Vec<FriendCategory> {
+        let mut categories: Vec<FriendCategory> =
+            self.0.friend_categories.iter().map(|c| c.clone()).collect();
+        categories.sort_by_key(|c| c.id);
+
+        categories
+    }
+
+    /// 寻找一个好友分组
+    pub fn find_friend_category(&self, id: u8) -> Option<FriendCategory> {
+        self.0.friend_categories.get(&id).map(|c| c.clone())
+    }
+
+    /// 已上传图片的缓存
+    pub fn image_cache(&self) -> &ImageCache {
+        &self.0.image_cache
+    }
+
     /*pub async fn guild_client(&self) -> GuildClient {
         GuildClient::new(&self.0.client).await
     }*/
 
+    /// 从工作目录加载联系人缓存, 返回缓存距保存时经过的时间
+    pub async fn load_contact_snapshot(&self) -> Option<Duration> {
+        let dir = self.work_dir().to_owned();
+        let snapshot = match tokio::task::spawn_blocking(move || ContactSnapshot::read(dir)).await {
+            Ok(Ok(Some(s))) => s,
+            Ok(Ok(None)) | Err(_) => return None,
+            Ok(Err(e)) => {
+                warn!("{}读取联系人缓存失败: {}", self, e);
+                return None;
+            }
+        };
+
+        let age = snapshot.age();
+        snapshot.restore(self);
+
+        Some(age)
+    }
+
+    /// 将联系人缓存保存至工作目录, 缓存为空时不保存
+    pub fn save_contact_snapshot(&self) -> io::Result<()> {
+        let snapshot = ContactSnapshot::capture(self);
+        if snapshot.is_empty() {
+            return Ok(());
+        }
+
+        snapshot.write(self.work_dir())
+    }
+
     pub fn close(&self) {
+        if cache_config().persist {
+            if let Err(e) = self.save_contact_snapshot() {
+                warn!("{}保存联系人缓存失败: {}", self, e);
+            }
+        }
+
         self.0.close();
     }
 }
@@ -348,10 +483,11 @@ impl Client {
         self.friend_caches().insert(friend.id(), friend);
     }
 
-    /// 缓存一个群, 将其直接添加(或替换)到群信息缓存中
-    #[inline]
+    /// 缓存一个群, 将其直接添加(或替换)到群信息缓存中, 替换时保留已缓存的群成员
     pub(crate) fn cache_group(&self, group: Group) {
-        self.group_caches().insert(group.id(), group);
+        if let Some(old) = self.group_caches().insert(group.id(), group.clone()) {
+            group.inherit_members(&old);
+        }
     }
 
     /// 从缓存移除一个好友, 用于删除(被删除)场景
@@ -395,6 +531,13 @@ impl Client {
         self.find_friend(id)
     }
 
+    /// 按账号排序的已登录客户端列表, 保证跨客户端查找的结果稳定
+    pub(crate) fn sorted_list() -> Vec<Client> {
+        let mut list = Self::list();
+        list.sort_unstable_by_key(Client::id);
+        list
+    }
+
     /// 内部使用的客户端, 详见[`ricq::Client`]
     #[inline]
     pub(crate) fn request_client(&self) -> &RQClient {
@@ -453,17 +596,22 @@ mod imp {
     use dashmap::DashMap;
     use ricq::device::Device;
     use ricq::Client as RQClient;
-    use tokio::io::AsyncReadExt;
-    use tokio::net::{TcpSocket, TcpStream};
+    use tokio::net::TcpStream;
     use tokio::task::yield_now;
     use tokio::{fs, io};
     use tracing::{error, warn};
 
     use crate::channel::GlobalEventBroadcastHandler;
+    use crate::client::crypto;
+    use crate::client::image_cache::ImageCache;
     use crate::client::info::AccountInfo;
+    use crate::client::proxy;
+    use crate::client::proxy::Proxy;
     use crate::client::ClientConfiguration;
-    use crate::contact::friend::Friend;
+    use crate::config::login::AddressFamily;
+    use crate::contact::friend::{Friend, FriendCategory};
     use crate::contact::group::Group;
+    use crate::error::AtriResult;
 
     pub struct Client {
         pub inner: ClientInner,
@@ -485,27 +633,40 @@ mod imp {
         pub enable: AtomicBool,
         pub client: Arc<RQClient>,
         pub friends: DashMap<i64, Friend>,
+        pub friend_categories: DashMap<u8, FriendCategory>,
         pub groups: DashMap<i64, Group>,
+        pub image_cache: ImageCache,
         pub work_dir: PathBuf,
+        pub proxy: Option<Proxy>,
+        pub address_family: AddressFamily,
+        pub server_override: Vec<String>,
     }
 
     impl ClientInner {
-        pub async fn new(id: i64, conf: ClientConfiguration) -> Self {
+        pub async fn new(id: i64, conf: ClientConfiguration) -> AtriResult<Self> {
             let work_dir = conf.work_dir(id);
 
             if !work_dir.is_dir() {
-                fs::create_dir_all(&work_dir)
-                    .await
-                    .expect("Cannot create work dir");
+                fs::create_dir_all(&work_dir).await?;
             }
 
             let file_p = work_dir.join("device.json");
+
+            async fn write_device(path: &Path, device: &Device) -> io::Result<()> {
+                let json = serde_json::to_vec_pretty(device)?;
+                let bytes = crypto::seal_bytes_if_enabled(json);
+
+                fs::write(path, bytes).await
+            }
+
             let device: Device = if file_p.is_file() {
-                if let Ok(fu) = fs::File::open(&file_p).await.map(|mut f| async move {
-                    let mut str = String::new();
-                    f.read_to_string(&mut str).await.expect("Cannot read file");
+                if let Ok(bytes) = fs::read(&file_p).await {
+                    let bytes = crypto::open_bytes(bytes).map_err(|e| {
+                        error!("Client({})无法解密设备信息: {}", id, e);
+                        e
+                    })?;
 
-                    match serde_json::from_str(&str) {
+                    match serde_json::from_slice(&bytes) {
                         Ok(d) => d,
                         Err(e) => {
                             error!("{:?}", e);
@@ -514,37 +675,19 @@ mod imp {
                             let mut bak = file_p.as_os_str().to_owned();
                             bak.push(".bak");
                             let bak = Path::new(&bak);
-                            if fs::copy(&file_p, &bak).await.is_err() {
-                                return device;
+                            if fs::copy(&file_p, &bak).await.is_ok() {
+                                write_device(&file_p, &device).await?;
                             };
 
-                            drop(f); // make sure the file is closed
-
-                            tokio::task::block_in_place(|| {
-                                if let Ok(f) = std::fs::File::create(file_p) {
-                                    serde_json::to_writer_pretty(f, &device).expect(
-                                        "Cannot serialize device info Or write device info to file",
-                                    );
-                                };
-                            });
-
                             device
                         }
                     }
-                }) {
-                    fu.await
                 } else {
                     Device::random()
                 }
             } else {
                 let device = Device::random();
-
-                tokio::task::block_in_place(|| {
-                    if let Ok(f) = std::fs::File::create(&file_p) {
-                        serde_json::to_writer_pretty(f, &device)
-                            .expect("Cannot serialize device info Or write device info to file");
-                    }
-                });
+                write_device(&file_p, &device).await?;
 
                 device
             };
@@ -552,35 +695,60 @@ mod imp {
             let client = RQClient::new(device, conf.version, GlobalEventBroadcastHandler);
             let client = Arc::new(client);
 
-            Self {
+            Ok(Self {
                 id,
                 info: OnceLock::new(),
                 enable: AtomicBool::new(false),
                 friends: DashMap::new(),
+                friend_categories: DashMap::new(),
                 groups: DashMap::new(),
+                image_cache: ImageCache::load(&work_dir),
                 client,
                 work_dir,
-            }
+                proxy: conf.proxy,
+                address_family: conf.address_family,
+                server_override: conf.server_override,
+            })
         }
 
         pub async fn connect(&self) -> io::Result<TcpStream> {
-            let mut servers = self.client.get_address_list().await;
+            const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+            let mut servers: Vec<String> = if self.server_override.is_empty() {
+                self.client
+                    .get_address_list()
+                    .await
+                    .into_iter()
+                    .filter(|addr| self.address_family.matches(addr))
+                    .map(|addr| addr.to_string())
+                    .collect()
+            } else {
+                self.server_override.iter().rev().cloned().collect()
+            };
 
             let total = servers.len();
             let mut times = 1;
             Ok(loop {
-                let socket = TcpSocket::new_v4()?;
-                let addr = servers
+                let server = servers
                     .pop()
                     .ok_or_else(|| io::Error::new(ErrorKind::AddrNotAvailable, "重连失败"))?;
 
-                match tokio::time::timeout(Duration::from_secs(2), socket.connect(addr)).await {
+                let result = tokio::time::timeout(CONNECT_TIMEOUT, async {
+                    if let Some(proxy) = &self.proxy {
+                        proxy.connect(&server, self.address_family).await
+                    } else {
+                        proxy::connect_to(&server, self.address_family).await
+                    }
+                })
+                .await;
+
+                match result {
                     Ok(Ok(s)) => break s,
                     Ok(Err(e)) => warn!(
                         "连接服务器{}失败, 尝试重连({}/{}): {}",
-                        addr, times, total, e
+                        server, times, total, e
                     ),
-                    Err(_) => warn!("连接服务器{}超时, 尝试重连({}/{})", addr, times, total),
+                    Err(_) => warn!("连接服务器{}超时, 尝试重连({}/{})", server, times, total),
                 }
 
                 times += 1;
@@ -610,6 +778,12 @@ mod imp {
 pub struct ClientConfiguration {
     pub work_dir: Option<PathBuf>,
     pub version: ricq::version::Version,
+    /// 连接服务器使用的代理
+    pub proxy: Option<Proxy>,
+    /// 连接服务器使用的地址族
+    pub address_family: AddressFamily,
+    /// 指定连接的服务器列表, 为空则使用服务器下发的列表
+    pub server_override: Vec<String>,
 }
 
 impl ClientConfiguration {
@@ -1,3 +1,5 @@
+use crate::client::crypto::StorageKey;
+use crate::error::LoginError;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Serialize, Deserialize, prost::Message)]
@@ -22,6 +24,52 @@ pub struct Token {
     pub tgtgt_key: Vec<u8>,
     #[prost(bytes = "vec", tag = "10")]
     pub wt_session_ticket_key: Vec<u8>,
+    /// 加密时使用的nonce, 未加密时为空
+    #[prost(bytes = "vec", tag = "100")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub nonce: Vec<u8>,
+    /// 加密后的完整Token, 未加密时为空
+    #[prost(bytes = "vec", tag = "101")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sealed: Vec<u8>,
+}
+
+impl Token {
+    /// Token是否已加密
+    pub fn is_encrypted(&self) -> bool {
+        !self.sealed.is_empty()
+    }
+
+    /// 加密Token, 加密后仅保留`uin`明文
+    pub fn encrypt(self, key: &StorageKey) -> Self {
+        let uin = self.uin;
+        let plain = prost::Message::encode_to_vec(&self);
+        let (nonce, sealed) = key.seal(&plain);
+
+        Self {
+            uin,
+            nonce,
+            sealed,
+            ..Default::default()
+        }
+    }
+
+    /// 解密Token, 若Token未加密则原样返回
+    pub fn decrypt(self, key: Option<&StorageKey>) -> Result<Self, LoginError> {
+        if !self.is_encrypted() {
+            return Ok(self);
+        }
+
+        let key = key.ok_or(LoginError::StorageKeyNotProvided)?;
+        let plain = key.open(&self.nonce, &self.sealed)?;
+        let token: Self = prost::Message::decode(&*plain).map_err(|_| LoginError::WrongToken)?;
+
+        if token.uin != self.uin {
+            return Err(LoginError::WrongToken);
+        }
+
+        Ok(token)
+    }
 }
 
 impl From<ricq::client::Token> for Token {
@@ -50,6 +98,8 @@ impl From<ricq::client::Token> for Token {
             out_packet_session_id,
             tgtgt_key,
             wt_session_ticket_key,
+            nonce: vec![],
+            sealed: vec![],
         }
     }
 }
@@ -67,6 +117,7 @@ impl From<Token> for ricq::client::Token {
             out_packet_session_id,
             tgtgt_key,
             wt_session_ticket_key,
+            ..
         }: Token,
     ) -> Self {
         Self {
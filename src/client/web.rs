@@ -0,0 +1,112 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+
+use crate::error::{AtriResult, WebApiError};
+use crate::Client;
+
+/// 群相关网页接口使用的域名
+pub const QUN_DOMAIN: &str = "qun.qq.com";
+
+/// 访问QQ网页接口所需的凭证
+#[derive(Debug, Clone)]
+pub struct WebCredential {
+    /// 请求时携带的Cookie
+    pub cookie: String,
+    /// 由`skey`计算的CSRF令牌, 即接口参数中的`bkn`
+    pub bkn: i64,
+}
+
+impl Client {
+    /// 获取访问某个域名下网页接口的凭证
+    pub async fn web_credential(&self, domain: &str) -> WebCredential {
+        let cookie = self.request_client().get_cookies(domain).await;
+        let bkn = cookie_value(&cookie, "skey")
+            .map(csrf_token)
+            .unwrap_or_default();
+
+        WebCredential { cookie, bkn }
+    }
+}
+
+/// 由`skey`计算CSRF令牌
+pub fn csrf_token(skey: &str) -> i64 {
+    let mut hash: i64 = 5381;
+    for b in skey.bytes() {
+        hash += (hash << 5) + b as i64;
+        hash &= 0x7fffffff;
+    }
+
+    hash
+}
+
+fn cookie_value<'a>(cookie: &'a str, name: &str) -> Option<&'a str> {
+    cookie.split(';').find_map(|pair| {
+        let (k, v) = pair.trim().split_once('=')?;
+        (k == name).then_some(v)
+    })
+}
+
+/// 网页接口共用的HTTP客户端
+pub(crate) fn http_client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .user_agent("Mozilla/5.0 (compatible; AtriBot)")
+            .build()
+            .expect("Cannot build http client")
+    })
+}
+
+/// 解析网页接口的响应, 错误码(`ec`或`retcode`)不为0时返回[`WebApiError::Response`]
+pub(crate) fn decode_response<T: DeserializeOwned>(bytes: &[u8]) -> AtriResult<T> {
+    let value: serde_json::Value = serde_json::from_slice(bytes).map_err(WebApiError::Decode)?;
+
+    let code = value
+        .get("ec")
+        .or_else(|| value.get("retcode"))
+        .and_then(serde_json::Value::as_i64)
+        .unwrap_or(0);
+
+    if code != 0 {
+        let message = value
+            .get("em")
+            .or_else(|| value.get("msg"))
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or_default()
+            .to_owned();
+
+        return Err(WebApiError::Response { code, message }.into());
+    }
+
+    serde_json::from_value(value).map_err(|e| WebApiError::Decode(e).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cookie_value, csrf_token, decode_response};
+    use crate::error::{AtriError, WebApiError};
+
+    #[test]
+    fn bkn() {
+        let cookie = "uin=o114514; skey=@abcdefgh; p_uin=o114514";
+        assert_eq!(cookie_value(cookie, "skey"), Some("@abcdefgh"));
+        assert_eq!(cookie_value(cookie, "p_skey"), None);
+        assert_eq!(csrf_token(""), 5381);
+        assert!(csrf_token("@abcdefgh") <= 0x7fffffff);
+    }
+
+    #[test]
+    fn response_code() {
+        let ok: serde_json::Value = decode_response(br#"{"ec":0,"data":1}"#).unwrap();
+        assert_eq!(ok["data"], 1);
+
+        let err = decode_response::<serde_json::Value>(br#"{"ec":1,"em":"no permission"}"#);
+        assert!(matches!(
+            err,
+            Err(AtriError::WebApi(WebApiError::Response { code: 1, .. }))
+        ));
+    }
+}
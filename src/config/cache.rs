@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+pub const DEFAULT_CONFIG: &[u8] = include_bytes!("../../default_config/cache.toml");
+
+/// 缓存最长有效时间的下限(秒), 避免后台刷新过于频繁
+pub const MIN_MAX_STALENESS: u64 = 60;
+
+/// 联系人缓存配置
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CacheConfig {
+    /// 是否将好友, 群及群成员缓存保存至客户端工作目录
+    #[serde(default = "true_bool")]
+    pub persist: bool,
+    /// 缓存的最长有效时间(秒), 超过此时间的缓存将在登录时同步刷新,
+    /// 未超过则先使用缓存, 并在后台刷新. 小于[`MIN_MAX_STALENESS`]时按其计算
+    #[serde(default = "default_max_staleness")]
+    pub max_staleness: u64,
+    /// 群成员缓存策略
+    #[serde(default)]
+    pub member: MemberCacheConfig,
+}
+
+impl CacheConfig {
+    pub fn max_staleness(&self) -> Duration {
+        Duration::from_secs(self.max_staleness.max(MIN_MAX_STALENESS))
+    }
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            persist: true,
+            max_staleness: default_max_staleness(),
+            member: MemberCacheConfig::default(),
+        }
+    }
+}
+
+/// 群成员缓存策略
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MemberCacheConfig {
+    /// 单个成员缓存的有效时间(秒)
+    #[serde(default = "default_member_ttl")]
+    pub ttl: u64,
+    /// 获取失败(成员不存在)的结果的缓存时间(秒)
+    #[serde(default = "default_negative_ttl")]
+    pub negative_ttl: u64,
+    /// 成员列表的有效时间(秒), 过期后再次获取列表时将重新拉取
+    #[serde(default = "default_member_ttl")]
+    pub list_ttl: u64,
+    /// 缓存过期时是否先返回旧缓存, 并在后台刷新
+    #[serde(default = "true_bool")]
+    pub background_refresh: bool,
+}
+
+impl MemberCacheConfig {
+    pub fn ttl(&self) -> Duration {
+        Duration::from_secs(self.ttl)
+    }
+
+    pub fn negative_ttl(&self) -> Duration {
+        Duration::from_secs(self.negative_ttl)
+    }
+
+    pub fn list_ttl(&self) -> Duration {
+        Duration::from_secs(self.list_ttl)
+    }
+}
+
+impl Default for MemberCacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl: default_member_ttl(),
+            negative_ttl: default_negative_ttl(),
+            list_ttl: default_member_ttl(),
+            background_refresh: true,
+        }
+    }
+}
+
+const fn true_bool() -> bool {
+    true
+}
+
+const fn default_max_staleness() -> u64 {
+    6 * 60 * 60
+}
+
+const fn default_member_ttl() -> u64 {
+    60 * 60
+}
+
+const fn default_negative_ttl() -> u64 {
+    5 * 60
+}
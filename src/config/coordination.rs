@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_CONFIG: &[u8] = include_bytes!("../../default_config/coordination.toml");
+
+/// 多客户端协调配置
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct CoordinationConfig {
+    /// 默认策略
+    #[serde(default)]
+    pub default_policy: Policy,
+    /// 单个群的配置
+    #[serde(default, rename = "group")]
+    pub groups: Vec<GroupCoordination>,
+}
+
+impl CoordinationConfig {
+    pub fn group(&self, group_id: i64) -> Option<&GroupCoordination> {
+        self.groups.iter().find(|g| g.id == group_id)
+    }
+}
+
+/// 单个群的协调配置
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GroupCoordination {
+    /// 群号
+    pub id: i64,
+    /// 策略, 未配置则使用默认策略
+    pub policy: Option<Policy>,
+    /// 客户端的优先顺序
+    #[serde(default)]
+    pub clients: Vec<i64>,
+}
+
+/// 选择处理群消息的客户端的策略
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Policy {
+    /// 不协调, 所有客户端均处理消息
+    #[default]
+    Broadcast,
+    /// 由排序最前的在线客户端处理
+    PrimaryBackup,
+    /// 在线客户端轮流处理
+    RoundRobin,
+    /// 由最久未被限流的客户端处理
+    LeastRateLimited,
+}
@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+pub const DEFAULT_CONFIG: &[u8] = include_bytes!("../../default_config/history.toml");
+
+/// 消息记录配置
+#[derive(Serialize, Deserialize, Debug)]
+pub struct HistoryConfig {
+    /// 是否启用消息记录
+    #[serde(default)]
+    pub enabled: bool,
+    /// 是否记录群消息
+    #[serde(default = "true_bool")]
+    pub group: bool,
+    /// 是否记录好友消息
+    #[serde(default = "true_bool")]
+    pub friend: bool,
+    /// 消息的保留天数, 为0时永久保留
+    #[serde(default = "default_retention_days")]
+    pub retention_days: u64,
+}
+
+impl HistoryConfig {
+    /// 消息的保留时间, 永久保留时为`None`
+    pub fn retention(&self) -> Option<Duration> {
+        (self.retention_days > 0).then(|| Duration::from_secs(self.retention_days * 24 * 60 * 60))
+    }
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            group: true,
+            friend: true,
+            retention_days: default_retention_days(),
+        }
+    }
+}
+
+const fn true_bool() -> bool {
+    true
+}
+
+const fn default_retention_days() -> u64 {
+    30
+}
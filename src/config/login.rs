@@ -10,23 +10,65 @@ pub struct LoginConfig {
     /// 是否自动重连
     #[serde(default = "true_bool")]
     pub auto_reconnect: bool,
+    /// 登录信息加密配置
+    #[serde(default)]
+    pub encryption: EncryptionConfig,
     /// 所有配置进行登录的客户端
     #[serde(default, rename = "client")]
     pub clients: Vec<ClientConfig>,
 }
 
+/// 登录信息加密配置, 均未配置则不加密
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct EncryptionConfig {
+    /// 读取密钥的环境变量
+    pub key_env: Option<String>,
+    /// 密钥文件
+    pub key_file: Option<std::path::PathBuf>,
+}
+
 /// 客户端配置
 #[derive(Deserialize, Serialize, Debug)]
 pub struct ClientConfig {
     /// 账号
     pub account: i64,
-    /// 密码
+    /// 密码, 加密后的密码以`enc:`开头
     pub password: Option<String>,
     /// 登录协议
     pub protocol: Option<Protocol>,
     /// 是否进行登录
     #[serde(default = "true_bool")]
     pub auto_login: bool,
+    /// 连接服务器使用的代理, 支持`socks5://`和`http://`
+    pub proxy: Option<String>,
+    /// 连接服务器使用的地址族
+    #[serde(default)]
+    pub address_family: AddressFamily,
+    /// 指定连接的服务器列表(`host:port`), 为空则使用服务器下发的列表
+    #[serde(default)]
+    pub server_override: Vec<String>,
+}
+
+/// 连接服务器时使用的地址族
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AddressFamily {
+    /// 不限制
+    #[default]
+    Auto,
+    /// 仅使用IPv4
+    V4,
+    /// 仅使用IPv6
+    V6,
+}
+
+impl AddressFamily {
+    pub fn matches(&self, addr: &std::net::SocketAddr) -> bool {
+        match self {
+            Self::Auto => true,
+            Self::V4 => addr.is_ipv4(),
+            Self::V6 => addr.is_ipv6(),
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, Copy, Default)]
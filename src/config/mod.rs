@@ -1,8 +1,12 @@
 use std::path::Path;
 
+pub mod cache;
+pub mod coordination;
+pub mod history;
 pub mod log;
 pub mod login;
 pub mod plugin;
+pub mod scheduler;
 pub mod service;
 
 pub fn service_config_dir_path() -> &'static Path {
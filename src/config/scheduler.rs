@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_CONFIG: &[u8] = include_bytes!("../../default_config/scheduler.toml");
+
+/// 定时任务配置
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SchedulerConfig {
+    /// 与UTC相差的分钟数, 不设置时使用系统时区
+    #[serde(default)]
+    pub utc_offset: Option<i32>,
+    /// 是否保存带有名称的任务的下次运行时间
+    #[serde(default = "true_bool")]
+    pub persist: bool,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            utc_offset: None,
+            persist: true,
+        }
+    }
+}
+
+const fn true_bool() -> bool {
+    true
+}
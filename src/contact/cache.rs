@@ -0,0 +1,512 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use dashmap::DashMap;
+use tracing::error;
+
+use crate::config::cache::MemberCacheConfig;
+use crate::error::AtriResult;
+
+/// 群成员缓存, 每个条目记录缓存时间, 按[`MemberCacheConfig`]判断是否过期
+///
+/// 获取失败(成员不存在)的结果同样会被缓存, 使用单独的有效时间
+pub struct MemberCache<M> {
+    entries: DashMap<i64, Entry<M>>,
+    list_refreshed_at: Mutex<Option<Instant>>,
+    /// 正在后台刷新的成员与列表
+    refreshing: Mutex<HashSet<RefreshKey>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum RefreshKey {
+    Member(i64),
+    List,
+}
+
+/// 后台刷新结束时移除标记
+struct RefreshGuard<'a, M> {
+    cache: &'a MemberCache<M>,
+    key: RefreshKey,
+}
+
+impl<M> Drop for RefreshGuard<'_, M> {
+    fn drop(&mut self) {
+        self.cache
+            .refreshing
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.key);
+    }
+}
+
+struct Entry<M> {
+    member: Option<M>,
+    cached_at: Instant,
+}
+
+/// 查找缓存的结果
+#[derive(Debug, PartialEq, Eq)]
+pub enum Lookup<M> {
+    /// 缓存有效
+    Fresh(Option<M>),
+    /// 缓存已过期, 附带过期的值
+    Stale(Option<M>),
+    /// 无缓存
+    Missing,
+}
+
+impl<M: Clone> MemberCache<M> {
+    pub fn new() -> Self {
+        Self {
+            entries: DashMap::new(),
+            list_refreshed_at: Mutex::new(None),
+            refreshing: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn lookup(&self, id: i64, policy: &MemberCacheConfig) -> Lookup<M> {
+        self.lookup_at(id, policy, Instant::now())
+    }
+
+    pub fn lookup_at(&self, id: i64, policy: &MemberCacheConfig, now: Instant) -> Lookup<M> {
+        let Some(entry) = self.entries.get(&id) else {
+            return Lookup::Missing;
+        };
+
+        let ttl = if entry.member.is_some() {
+            policy.ttl()
+        } else {
+            policy.negative_ttl()
+        };
+
+        if now.saturating_duration_since(entry.cached_at) < ttl {
+            Lookup::Fresh(entry.member.clone())
+        } else {
+            Lookup::Stale(entry.member.clone())
+        }
+    }
+
+    /// 获取已缓存的成员, 不检查是否过期
+    pub fn get(&self, id: i64) -> Option<M> {
+        self.entries.get(&id).and_then(|e| e.member.clone())
+    }
+
+    pub fn insert(&self, id: i64, member: Option<M>) {
+        self.insert_at(id, member, Instant::now());
+    }
+
+    pub fn insert_at(&self, id: i64, member: Option<M>, cached_at: Instant) {
+        self.entries.insert(id, Entry { member, cached_at });
+    }
+
+    pub fn remove(&self, id: i64) -> Option<M> {
+        self.entries.remove(&id).and_then(|(_, e)| e.member)
+    }
+
+    /// 所有已缓存的成员, 不检查是否过期
+    pub fn members(&self) -> Vec<M> {
+        self.entries
+            .iter()
+            .filter_map(|e| e.member.clone())
+            .collect()
+    }
+
+    /// 是否拉取过完整的成员列表
+    pub fn is_list_complete(&self) -> bool {
+        self.list_refreshed_at().is_some()
+    }
+
+    pub fn is_list_fresh(&self, policy: &MemberCacheConfig) -> bool {
+        self.is_list_fresh_at(policy, Instant::now())
+    }
+
+    pub fn is_list_fresh_at(&self, policy: &MemberCacheConfig, now: Instant) -> bool {
+        self.list_refreshed_at()
+            .map(|at| now.saturating_duration_since(at) < policy.list_ttl())
+            .unwrap_or(false)
+    }
+
+    pub fn set_list_refreshed_at(&self, at: Option<Instant>) {
+        *self
+            .list_refreshed_at
+            .lock()
+            .expect("Cannot lock member list") = at;
+    }
+
+    /// 使用拉取的完整成员列表替换缓存, 不在列表中的成员将被移除
+    pub fn replace_list(&self, list: Vec<(i64, M)>) {
+        let now = Instant::now();
+        let ids: HashSet<i64> = list.iter().map(|(id, _)| *id).collect();
+
+        self.entries
+            .retain(|id, e| e.member.is_none() || ids.contains(id));
+        for (id, member) in list {
+            self.insert_at(id, Some(member), now);
+        }
+
+        self.set_list_refreshed_at(Some(now));
+    }
+
+    /// 继承另一个缓存的所有条目, 保留其缓存时间
+    pub fn inherit<O, F: Fn(&O) -> M>(&self, old: &MemberCache<O>, f: F) {
+        for entry in old.entries.iter() {
+            self.insert_at(*entry.key(), entry.member.as_ref().map(&f), entry.cached_at);
+        }
+
+        self.set_list_refreshed_at(old.list_refreshed_at());
+    }
+
+    /// 清空缓存, 之后的查找均会重新获取
+    pub fn invalidate(&self) {
+        self.entries.clear();
+        self.set_list_refreshed_at(None);
+    }
+
+    /// 标记开始后台刷新, 已在刷新时返回`false`
+    fn begin_refresh(&self, key: RefreshKey) -> bool {
+        self.refreshing
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key)
+    }
+
+    fn list_refreshed_at(&self) -> Option<Instant> {
+        *self
+            .list_refreshed_at
+            .lock()
+            .expect("Cannot lock member list")
+    }
+}
+
+impl<M: Clone> Default for MemberCache<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 群成员的来源, 负责向服务器获取成员信息
+#[async_trait]
+pub trait MemberSource: Clone + Send + Sync + 'static {
+    type Member: Clone + Send + Sync + 'static;
+
+    fn cache(&self) -> &MemberCache<Self::Member>;
+
+    /// 获取单个成员, 成员不存在时返回`None`
+    async fn fetch_member(&self, id: i64) -> AtriResult<Option<Self::Member>>;
+
+    /// 获取完整的成员列表
+    async fn fetch_member_list(&self) -> AtriResult<Vec<(i64, Self::Member)>>;
+}
+
+/// 获取成员并更新缓存
+pub async fn refresh_member<S: MemberSource>(source: &S, id: i64) -> AtriResult<Option<S::Member>> {
+    let member = source.fetch_member(id).await?;
+    source.cache().insert(id, member.clone());
+
+    Ok(member)
+}
+
+/// 获取完整的成员列表并替换缓存
+pub async fn refresh_member_list<S: MemberSource>(source: &S) -> AtriResult<Vec<S::Member>> {
+    let list = source.fetch_member_list().await?;
+    let members = list.iter().map(|(_, m)| m.clone()).collect();
+    source.cache().replace_list(list);
+
+    Ok(members)
+}
+
+/// 按缓存策略查找成员
+///
+/// 缓存有效时直接返回; 过期时若启用了后台刷新则返回过期的值并在后台刷新,
+/// 同一成员同时只有一个后台刷新; 否则重新获取, 获取失败时返回过期的值
+pub async fn find_member<S: MemberSource>(
+    source: &S,
+    id: i64,
+    policy: &MemberCacheConfig,
+) -> Option<S::Member> {
+    match source.cache().lookup(id, policy) {
+        Lookup::Fresh(member) => member,
+        Lookup::Stale(old) if policy.background_refresh => {
+            let key = RefreshKey::Member(id);
+            if source.cache().begin_refresh(key) {
+                let source = source.clone();
+                tokio::spawn(async move {
+                    let _guard = RefreshGuard {
+                        cache: source.cache(),
+                        key,
+                    };
+
+                    if let Err(e) = refresh_member(&source, id).await {
+                        error!("刷新成员时出现错误: {}", e);
+                    }
+                });
+            }
+
+            old
+        }
+        Lookup::Stale(old) => refresh_member(source, id).await.unwrap_or_else(|e| {
+            error!("刷新成员时出现错误: {}", e);
+            old
+        }),
+        Lookup::Missing => refresh_member(source, id).await.unwrap_or_else(|e| {
+            error!("刷新成员时出现错误: {}", e);
+            None
+        }),
+    }
+}
+
+/// 按缓存策略获取成员列表, 规则同[`find_member`]
+pub async fn member_list<S: MemberSource>(
+    source: &S,
+    policy: &MemberCacheConfig,
+) -> Vec<S::Member> {
+    let cache = source.cache();
+    if cache.is_list_fresh(policy) {
+        return cache.members();
+    }
+
+    if cache.is_list_complete() && policy.background_refresh {
+        if cache.begin_refresh(RefreshKey::List) {
+            let source = source.clone();
+            tokio::spawn(async move {
+                let _guard = RefreshGuard {
+                    cache: source.cache(),
+                    key: RefreshKey::List,
+                };
+
+                if let Err(e) = refresh_member_list(&source).await {
+                    error!("刷新群聊成员信息时出现错误: {}", e);
+                }
+            });
+        }
+
+        return cache.members();
+    }
+
+    refresh_member_list(source).await.unwrap_or_else(|e| {
+        error!("刷新群聊成员信息时出现错误: {}", e);
+        cache.members()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use async_trait::async_trait;
+
+    use super::{find_member, member_list, Lookup, MemberCache, MemberSource};
+    use crate::config::cache::MemberCacheConfig;
+    use crate::error::{AtriError, AtriResult};
+
+    /// 模拟服务器, 成员为`(id, 名片)`
+    #[derive(Clone, Default)]
+    struct Stub(Arc<StubInner>);
+
+    #[derive(Default)]
+    struct StubInner {
+        cache: MemberCache<String>,
+        members: std::sync::Mutex<Vec<(i64, String)>>,
+        fetches: AtomicUsize,
+        list_fetches: AtomicUsize,
+        fail: std::sync::atomic::AtomicBool,
+    }
+
+    impl Stub {
+        fn set(&self, members: &[(i64, &str)]) {
+            *self.0.members.lock().unwrap() =
+                members.iter().map(|(id, n)| (*id, n.to_string())).collect();
+        }
+
+        fn fetches(&self) -> usize {
+            self.0.fetches.load(Ordering::SeqCst)
+        }
+
+        fn list_fetches(&self) -> usize {
+            self.0.list_fetches.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl MemberSource for Stub {
+        type Member = String;
+
+        fn cache(&self) -> &MemberCache<String> {
+            &self.0.cache
+        }
+
+        async fn fetch_member(&self, id: i64) -> AtriResult<Option<String>> {
+            self.0.fetches.fetch_add(1, Ordering::SeqCst);
+            if self.0.fail.load(Ordering::SeqCst) {
+                return Err(AtriError::NotSupported);
+            }
+
+            Ok(self
+                .0
+                .members
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|(m, _)| *m == id)
+                .map(|(_, n)| n.clone()))
+        }
+
+        async fn fetch_member_list(&self) -> AtriResult<Vec<(i64, String)>> {
+            self.0.list_fetches.fetch_add(1, Ordering::SeqCst);
+            Ok(self.0.members.lock().unwrap().clone())
+        }
+    }
+
+    fn policy(ttl: u64, negative_ttl: u64, background_refresh: bool) -> MemberCacheConfig {
+        MemberCacheConfig {
+            ttl,
+            negative_ttl,
+            list_ttl: ttl,
+            background_refresh,
+        }
+    }
+
+    fn block_on<F: std::future::Future>(f: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(f)
+    }
+
+    async fn settle() {
+        for _ in 0..8 {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[test]
+    fn ttl() {
+        let cache = MemberCache::new();
+        let p = policy(60, 5, false);
+        let now = Instant::now();
+
+        cache.insert_at(1, Some("atri"), now);
+        cache.insert_at(2, None, now);
+
+        assert_eq!(cache.lookup_at(1, &p, now), Lookup::Fresh(Some("atri")));
+        assert_eq!(cache.lookup_at(2, &p, now), Lookup::Fresh(None));
+        assert_eq!(cache.lookup_at(3, &p, now), Lookup::Missing);
+
+        // negative entries expire first
+        let later = now + Duration::from_secs(10);
+        assert_eq!(cache.lookup_at(1, &p, later), Lookup::Fresh(Some("atri")));
+        assert_eq!(cache.lookup_at(2, &p, later), Lookup::Stale(None));
+
+        let much_later = now + Duration::from_secs(61);
+        assert_eq!(
+            cache.lookup_at(1, &p, much_later),
+            Lookup::Stale(Some("atri"))
+        );
+    }
+
+    #[test]
+    fn negative_cache_expires() {
+        block_on(async {
+            let stub = Stub::default();
+            let p = policy(3600, 0, false);
+
+            assert_eq!(find_member(&stub, 1, &p).await, None);
+            stub.set(&[(1, "joined")]);
+
+            // negative ttl is zero, the member is fetched again
+            assert_eq!(find_member(&stub, 1, &p).await.as_deref(), Some("joined"));
+            assert_eq!(find_member(&stub, 1, &p).await.as_deref(), Some("joined"));
+            assert_eq!(stub.fetches(), 2);
+        });
+    }
+
+    #[test]
+    fn stale_member_refreshed() {
+        block_on(async {
+            let stub = Stub::default();
+            stub.set(&[(1, "old")]);
+            let p = policy(0, 0, false);
+
+            assert_eq!(find_member(&stub, 1, &p).await.as_deref(), Some("old"));
+            stub.set(&[(1, "new")]);
+            assert_eq!(find_member(&stub, 1, &p).await.as_deref(), Some("new"));
+
+            // fall back to the stale value on error
+            stub.0.fail.store(true, Ordering::SeqCst);
+            assert_eq!(find_member(&stub, 1, &p).await.as_deref(), Some("new"));
+        });
+    }
+
+    #[test]
+    fn background_refresh() {
+        block_on(async {
+            let stub = Stub::default();
+            stub.set(&[(1, "old")]);
+            let p = policy(0, 0, true);
+
+            assert_eq!(find_member(&stub, 1, &p).await.as_deref(), Some("old"));
+            stub.set(&[(1, "new")]);
+
+            // stale value is returned immediately
+            assert_eq!(find_member(&stub, 1, &p).await.as_deref(), Some("old"));
+            settle().await;
+            assert_eq!(stub.cache().get(1).as_deref(), Some("new"));
+        });
+    }
+
+    #[test]
+    fn single_background_refresh() {
+        block_on(async {
+            let stub = Stub::default();
+            stub.set(&[(1, "a")]);
+            let p = policy(0, 0, true);
+
+            find_member(&stub, 1, &p).await;
+            member_list(&stub, &p).await;
+            assert_eq!(stub.fetches(), 1);
+            assert_eq!(stub.list_fetches(), 1);
+
+            // 刷新完成前的查找不会再次刷新
+            for _ in 0..3 {
+                find_member(&stub, 1, &p).await;
+                member_list(&stub, &p).await;
+            }
+            settle().await;
+            assert_eq!(stub.fetches(), 2);
+            assert_eq!(stub.list_fetches(), 2);
+
+            // 刷新完成后可再次刷新
+            find_member(&stub, 1, &p).await;
+            member_list(&stub, &p).await;
+            settle().await;
+            assert_eq!(stub.fetches(), 3);
+            assert_eq!(stub.list_fetches(), 3);
+        });
+    }
+
+    #[test]
+    fn list_and_invalidate() {
+        block_on(async {
+            let stub = Stub::default();
+            stub.set(&[(1, "a"), (2, "b")]);
+            let p = policy(3600, 300, false);
+
+            assert_eq!(member_list(&stub, &p).await.len(), 2);
+            stub.set(&[(1, "a"), (3, "c")]);
+            assert_eq!(member_list(&stub, &p).await.len(), 2);
+            assert_eq!(stub.list_fetches(), 1);
+
+            stub.cache().invalidate();
+            let mut list = member_list(&stub, &p).await;
+            list.sort();
+            assert_eq!(list, ["a", "c"]);
+            assert_eq!(stub.list_fetches(), 2);
+            assert_eq!(stub.cache().get(2), None);
+        });
+    }
+}
@@ -1,13 +1,18 @@
-use crate::client::WeakClient;
+use crate::client::{image_cache, WeakClient};
 use crate::error::{AtriError, AtriResult};
-use crate::message::forward::ForwardMessage;
+use crate::message::forward;
+use crate::message::forward::{ForwardCard, ForwardMessage};
 use crate::message::image::Image;
 use crate::message::meta::{MessageReceipt, RecallMessage};
-use crate::message::MessageChain;
+use crate::message::voice::Voice;
+use crate::message::{image, voice, MessageChain};
 use crate::Client;
+use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::path::Path;
 use std::sync::Arc;
-use tracing::error;
+use tokio::io::AsyncRead;
+use tracing::{error, warn};
 
 #[derive(Clone)]
 pub struct Friend(Arc<imp::Friend>);
@@ -25,10 +30,36 @@ impl Friend {
         &self.0.info.remark
     }
 
+    /// 好友所在分组的id
+    pub fn category_id(&self) -> u8 {
+        self.0.info.group_id
+    }
+
+    /// 好友所在的分组
+    pub fn category(&self) -> Option<FriendCategory> {
+        self.client().find_friend_category(self.category_id())
+    }
+
     pub fn client(&self) -> Client {
         self.0.client.force_upgrade()
     }
 
+    /// 从所有在线的客户端中寻找一个好友, 多个客户端均添加此好友时返回账号最小的客户端的好友
+    pub fn find_any(id: i64) -> Option<Self> {
+        Client::sorted_list()
+            .into_iter()
+            .filter(Client::is_online)
+            .find_map(|client| client.find_friend(id))
+    }
+
+    /// 从所有已登录的客户端中寻找此好友
+    pub fn find_all(id: i64) -> Vec<Self> {
+        Client::sorted_list()
+            .into_iter()
+            .filter_map(|client| client.find_friend(id))
+            .collect()
+    }
+
     pub async fn delete(&self) -> bool {
         let result = self
             .client()
@@ -51,12 +82,23 @@ impl Friend {
         deleted.is_some()
     }
 
-    async fn _send_message(&self, chain: MessageChain) -> AtriResult<MessageReceipt> {
-        let result = self
-            .client()
-            .request_client()
-            .send_friend_message(self.id(), chain.into())
-            .await;
+    async fn _send_message(&self, mut chain: MessageChain) -> AtriResult<MessageReceipt> {
+        image::convert_for_friend(&mut chain, self).await;
+
+        let result = match chain.voice_to_send()?.cloned() {
+            Some(voice) => {
+                self.client()
+                    .request_client()
+                    .send_friend_audio(self.id(), voice.into_friend_audio())
+                    .await
+            }
+            None => {
+                self.client()
+                    .request_client()
+                    .send_friend_message(self.id(), chain.into())
+                    .await
+            }
+        };
 
         if let Err(ref e) = result {
             error!(
@@ -71,28 +113,95 @@ impl Friend {
         result.map(MessageReceipt::from).map_err(AtriError::from)
     }
 
+    /// 发送消息, 语音需单独发送, 与其他元素一同发送时返回[`AtriError::InvalidMessage`]
+    ///
+    /// 群图片与好友图片会自动转换, 见[`Image::into_group_image`]
     pub async fn send_message<M: Into<MessageChain>>(&self, msg: M) -> AtriResult<MessageReceipt> {
         self._send_message(msg.into()).await
     }
 
-    async fn _send_forward_message(&self, _forward: ForwardMessage) -> AtriResult<MessageReceipt> {
-        Err(AtriError::NotSupported)
+    async fn _send_forward_message(&self, forward: ForwardMessage) -> AtriResult<MessageReceipt> {
+        let card = self.upload_forward_message(forward).await?;
+        self._send_message(MessageChain::from(vec![card.into()]))
+            .await
+    }
+
+    pub async fn send_forward_message<M: Into<ForwardMessage>>(
+        &self,
+        msg: M,
+    ) -> AtriResult<MessageReceipt> {
+        self._send_forward_message(msg.into()).await
+    }
+
+    /// 上传转发消息, 返回的卡片可作为消息元素发送至任意群或好友
+    pub async fn upload_forward_message<M: Into<ForwardMessage>>(
+        &self,
+        msg: M,
+    ) -> AtriResult<ForwardCard> {
+        forward::upload(&self.client(), self.id(), msg.into()).await
     }
 
     pub async fn _upload_image(&self, image: &[u8]) -> AtriResult<Image> {
-        let f = self
-            .client()
+        let client = self.client();
+        let md5 = image_cache::image_md5(image);
+        if let Some(img) = client.image_cache().friend(&md5) {
+            return Ok(Image::Friend(img));
+        }
+
+        let img = client
             .request_client()
             .upload_friend_image(self.id(), image)
             .await?;
 
-        Ok(Image::Friend(f))
+        if let Err(e) = client.image_cache().insert_friend(md5, img.clone()).await {
+            warn!("{}保存图片缓存失败: {}", client, e);
+        }
+
+        Ok(Image::Friend(img))
     }
 
+    /// 上传图片, 相同的图片只会上传一次, 见[`ImageCache`](crate::client::image_cache::ImageCache)
     pub async fn upload_image<B: AsRef<[u8]>>(&self, image: B) -> AtriResult<Image> {
         self._upload_image(image.as_ref()).await
     }
 
+    /// 上传本地图片文件
+    pub async fn upload_image_from_path<P: AsRef<Path>>(&self, path: P) -> AtriResult<Image> {
+        let image = tokio::fs::read(path).await?;
+        self._upload_image(&image).await
+    }
+
+    /// 从异步读取器读取并上传图片, 图片将被完整读入内存, 见[`read_image`](image_cache::read_image)
+    pub async fn upload_image_from_reader<R>(&self, reader: R) -> AtriResult<Image>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let image = image_cache::read_image(reader).await?;
+        self._upload_image(&image).await
+    }
+
+    async fn _upload_voice(&self, silk: &[u8]) -> AtriResult<Voice> {
+        let duration = voice::silk_duration(silk).unwrap_or_default();
+        let audio = self
+            .client()
+            .request_client()
+            .upload_friend_audio(self.id(), silk, duration)
+            .await?;
+
+        Ok(Voice::friend(self.id(), audio))
+    }
+
+    /// 上传silk格式的语音
+    pub async fn upload_voice<B: AsRef<[u8]>>(&self, silk: B) -> AtriResult<Voice> {
+        self._upload_voice(silk.as_ref()).await
+    }
+
+    /// 上传本地音频文件, 非silk格式时使用[`set_voice_encoder`](voice::set_voice_encoder)设置的编码器转换
+    pub async fn upload_voice_file<P: AsRef<Path>>(&self, path: P) -> AtriResult<Voice> {
+        let silk = voice::read_silk(path).await?;
+        self._upload_voice(&silk).await
+    }
+
     async fn _recall_message(&self, receipt: MessageReceipt) -> AtriResult<()> {
         self.client()
             .request_client()
@@ -109,6 +218,71 @@ impl Friend {
     pub async fn recall_message<M: RecallMessage>(&self, msg: &M) -> AtriResult<()> {
         self._recall_message(msg.receipt()).await
     }
+
+    /// 获取好友的资料卡
+    pub async fn profile(&self) -> AtriResult<FriendProfile> {
+        let info = self
+            .client()
+            .request_client()
+            .get_summary_info(self.id())
+            .await?;
+
+        Ok(FriendProfile::from(info))
+    }
+}
+
+/// 好友分组
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FriendCategory {
+    pub id: u8,
+    pub name: String,
+    /// 分组内的好友数
+    pub friend_count: i32,
+    /// 分组内的在线好友数
+    pub online_count: i32,
+}
+
+impl From<ricq::structs::FriendGroupInfo> for FriendCategory {
+    fn from(info: ricq::structs::FriendGroupInfo) -> Self {
+        Self {
+            id: info.group_id,
+            name: info.group_name,
+            friend_count: info.friend_count,
+            online_count: info.online_friend_count,
+        }
+    }
+}
+
+/// 好友资料卡
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FriendProfile {
+    pub id: i64,
+    pub nickname: String,
+    /// 个性签名
+    pub signature: String,
+    /// QQ等级
+    pub level: i32,
+    pub age: u8,
+    /// 0: 男, 1: 女, 其他: 未知
+    pub gender: u8,
+    pub city: String,
+    /// 累计登录天数
+    pub login_days: i64,
+}
+
+impl From<ricq::structs::SummaryCardInfo> for FriendProfile {
+    fn from(info: ricq::structs::SummaryCardInfo) -> Self {
+        Self {
+            id: info.uin,
+            nickname: info.nickname,
+            signature: info.sign,
+            level: info.level,
+            age: info.age,
+            gender: info.sex,
+            city: info.city,
+            login_days: info.login_days,
+        }
+    }
 }
 
 // internal impls
@@ -121,6 +295,11 @@ impl Friend {
 
         Self(Arc::new(f))
     }
+
+    #[inline]
+    pub(crate) fn info(&self) -> &ricq::structs::FriendInfo {
+        &self.0.info
+    }
 }
 
 impl fmt::Debug for Friend {
@@ -1,18 +1,29 @@
-use dashmap::mapref::entry::Entry;
-use dashmap::DashMap;
+pub mod announcement;
+pub mod files;
+
+use async_trait::async_trait;
 use std::fmt::{Debug, Display, Formatter};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::path::Path;
 use std::sync::{Arc, Weak};
 
-use tracing::error;
+use tokio::io::AsyncRead;
+use tracing::{error, warn};
 
-use crate::client::WeakClient;
+use crate::client::{image_cache, WeakClient};
+use crate::contact::cache;
+use crate::contact::cache::{MemberCache, MemberSource};
+use crate::contact::group::announcement::{Announcement, AnnouncementOptions};
+use crate::contact::group::files::GroupFs;
 use crate::contact::member::NamedMember;
 use crate::error::{AtriError, AtriResult};
-use crate::message::forward::ForwardMessage;
+use crate::message::forward;
+use crate::message::forward::{ForwardCard, ForwardMessage};
 use crate::message::image::Image;
 use crate::message::meta::{MessageReceipt, RecallMessage};
-use crate::message::MessageChain;
+use crate::message::voice::{Voice, SILK_CODEC};
+use crate::message::{image, reply, voice, MessageChain};
+use crate::service::cache::cache_config;
+use crate::service::coordinator::{coordinator, Coordinator};
 use crate::Client;
 
 #[derive(Clone)]
@@ -34,53 +45,60 @@ impl Group {
         &self.0.info.name
     }
 
+    /// 从所有在线的客户端中寻找一个群
+    ///
+    /// 多个客户端均加入此群时, 按协调配置中的优先顺序选择客户端
+    pub fn find_any(id: i64) -> Option<Self> {
+        coordinator()
+            .primary(id)
+            .and_then(|client| client.find_group(id))
+    }
+
+    /// 从所有已登录的客户端中寻找此群, 返回每个加入此群的客户端所持有的群
+    pub fn find_all(id: i64) -> Vec<Self> {
+        Client::sorted_list()
+            .into_iter()
+            .filter_map(|client| client.find_group(id))
+            .collect()
+    }
+
+    /// 群成员列表, 按缓存策略使用缓存或向服务器拉取
     pub async fn members(&self) -> Vec<NamedMember> {
-        if self.0.member_list_refreshed.load(Ordering::Relaxed) {
-            self.members_cache()
-                .iter()
-                .filter_map(|named| named.to_owned())
-                .collect()
-        } else {
-            let owner = self.0.info.owner_uin;
-            self.client()
-                .request_client()
-                .get_group_member_list(self.id(), owner)
-                .await
-                .map(|r| {
-                    self.0.member_list_refreshed.store(true, Ordering::Release);
-                    r
-                })
-                .unwrap_or_else(|e| {
-                    error!("刷新群聊成员信息时出现错误: {}", e);
-                    vec![]
-                })
-                .into_iter()
-                .map(|info| {
-                    let named = NamedMember::from(self, info);
-                    self.cache_member(named.clone());
-                    named
-                })
-                .collect()
-        }
+        cache::member_list(self, &cache_config().member).await
     }
 
+    /// 寻找群成员, 按缓存策略使用缓存或向服务器获取
     pub async fn find_member(&self, id: i64) -> Option<NamedMember> {
-        match self.members_cache().entry(id) {
-            Entry::Occupied(entry) => entry.get().clone(),
-            Entry::Vacant(entry) => {
-                let refresh = self.refresh_member(id).await;
-                entry.insert(refresh.clone());
-                refresh
-            }
-        }
+        cache::find_member(self, id, &cache_config().member).await
     }
 
-    async fn _send_message(&self, chain: MessageChain) -> AtriResult<MessageReceipt> {
-        self.client()
-            .request_client()
-            .send_group_message(self.id(), chain.into())
-            .await
+    /// 清空群成员缓存, 之后获取成员时将重新向服务器获取
+    pub fn invalidate_members(&self) {
+        self.members_cache().invalidate();
+    }
+
+    async fn _send_message(&self, mut chain: MessageChain) -> AtriResult<MessageReceipt> {
+        image::convert_for_group(&mut chain, self).await;
+        let sent = chain.clone();
+
+        let result = match chain.voice_to_send()?.cloned() {
+            Some(voice) => {
+                self.client()
+                    .request_client()
+                    .send_group_audio(self.id(), voice.into_group_audio())
+                    .await
+            }
+            None => {
+                self.client()
+                    .request_client()
+                    .send_group_message(self.id(), chain.into())
+                    .await
+            }
+        };
+
+        result
             .map(MessageReceipt::from)
+            .inspect(|receipt| reply::record_sent(self, sent, receipt))
             .map_err(|err| {
                 error!(
                     "{}发送信息失败, 目标群: {}({}), {:?}",
@@ -90,10 +108,17 @@ impl Group {
                     err
                 );
 
+                if Coordinator::is_rate_limited(&err) {
+                    coordinator().mark_rate_limited(self.client().id());
+                }
+
                 AtriError::from(err)
             })
     }
 
+    /// 发送消息, 语音需单独发送, 与其他元素一同发送时返回[`AtriError::InvalidMessage`]
+    ///
+    /// 群图片与好友图片会自动转换, 见[`Image::into_group_image`]
     #[inline]
     pub async fn send_message<M: Into<MessageChain>>(&self, msg: M) -> AtriResult<MessageReceipt> {
         self._send_message(msg.into()).await
@@ -116,63 +141,87 @@ impl Group {
         self._send_forward_message(msg.into()).await
     }
 
-    /*
-    async fn _upload_forward_message(&self, forward: ForwardMessage) -> AtriResult<MessageChain> {
-        let t_sum = msgs.len();
-        let preview = gen_forward_preview(&msgs);
-        let res_id = self.client().request_client().upload_msgs(group_code, msgs, false).await?;
-        // TODO friend template?
-        let template = format!(
-            r##"<?xml version='1.0' encoding='UTF-8' standalone='yes' ?><msg serviceID="35" templateID="1" action="viewMultiMsg" brief="[聊天记录]" m_resid="{}" m_fileName="{}" tSum="{}" sourceMsgId="0" url="" flag="3" adverSign="0" multiMsgFlag="0"><item layout="1" advertiser_id="0" aid="0"><title size="34" maxLines="2" lineSpace="12">群聊的聊天记录</title>{}<hr hidden="false" style="0" /><summary size="26" color="#777777">查看{}条转发消息</summary></item><source name="聊天记录" icon="" action="" appid="-1" /></msg>"##,
-            res_id,
-            std::time::UNIX_EPOCH.elapsed().unwrap_or_else(|| {
-                unreachable!()
-            }).as_millis(),
-            t_sum,
-            preview,
-            t_sum
-        );
-        let mut chain = MessageChain::default();
-        chain.push(ricq::msg::elem::RichMsg {
-            service_id: 35,
-            template1: template,
-        });
-        chain
-            .0
-            .push(pb::msg::elem::Elem::GeneralFlags(pb::msg::GeneralFlags {
-                pendant_id: Some(0),
-                pb_reserve: Some(vec![0x78, 0x00, 0xF8, 0x01, 0x00, 0xC8, 0x02, 0x00]),
-                ..Default::default()
-            }));
-        self._send_group_message(group_code, chain.into(), None)
-            .await
+    /// 上传转发消息, 返回的卡片可作为消息元素发送至任意群或好友
+    pub async fn upload_forward_message<M: Into<ForwardMessage>>(
+        &self,
+        msg: M,
+    ) -> AtriResult<ForwardCard> {
+        forward::upload(&self.client(), self.id(), msg.into()).await
     }
-    */
 
     async fn _upload_image(&self, image: &[u8]) -> AtriResult<Image> {
-        self.client()
+        let client = self.client();
+        let md5 = image_cache::image_md5(image);
+        if let Some(img) = client.image_cache().group(&md5) {
+            return Ok(Image::Group(img));
+        }
+
+        let img = client
             .request_client()
             .upload_group_image(self.id(), image)
             .await
-            .map(Image::Group)
             .map_err(|err| {
                 error!(
                     "{}上传图片失败, 目标群: {}({}), {:?}",
-                    self.client(),
+                    client,
                     self.name(),
                     self.id(),
                     err
                 );
 
                 AtriError::from(err)
-            })
+            })?;
+
+        if let Err(e) = client.image_cache().insert_group(md5, img.clone()).await {
+            warn!("{}保存图片缓存失败: {}", client, e);
+        }
+
+        Ok(Image::Group(img))
     }
 
+    /// 上传图片, 相同的图片只会上传一次, 见[`ImageCache`](crate::client::image_cache::ImageCache)
     #[inline]
     pub async fn upload_image<B: AsRef<[u8]>>(&self, image: B) -> AtriResult<Image> {
         self._upload_image(image.as_ref()).await
     }
 
+    /// 上传本地图片文件
+    pub async fn upload_image_from_path<P: AsRef<Path>>(&self, path: P) -> AtriResult<Image> {
+        let image = tokio::fs::read(path).await?;
+        self._upload_image(&image).await
+    }
+
+    /// 从异步读取器读取并上传图片, 图片将被完整读入内存, 见[`read_image`](image_cache::read_image)
+    pub async fn upload_image_from_reader<R>(&self, reader: R) -> AtriResult<Image>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let image = image_cache::read_image(reader).await?;
+        self._upload_image(&image).await
+    }
+
+    async fn _upload_voice(&self, silk: &[u8]) -> AtriResult<Voice> {
+        let audio = self
+            .client()
+            .request_client()
+            .upload_group_audio(self.id(), silk, SILK_CODEC)
+            .await?;
+
+        Ok(Voice::group(self.id(), audio))
+    }
+
+    /// 上传silk格式的语音
+    #[inline]
+    pub async fn upload_voice<B: AsRef<[u8]>>(&self, silk: B) -> AtriResult<Voice> {
+        self._upload_voice(silk.as_ref()).await
+    }
+
+    /// 上传本地音频文件, 非silk格式时使用[`set_voice_encoder`](voice::set_voice_encoder)设置的编码器转换
+    pub async fn upload_voice_file<P: AsRef<Path>>(&self, path: P) -> AtriResult<Voice> {
+        let silk = voice::read_silk(path).await?;
+        self._upload_voice(&silk).await
+    }
+
     async fn _recall_message(&self, receipt: MessageReceipt) -> AtriResult<()> {
         self.client()
             .request_client()
@@ -223,6 +272,66 @@ impl Group {
             .map_err(AtriError::from)
     }
 
+    /// 开启或关闭全员禁言
+    pub async fn mute_all(&self, mute: bool) -> AtriResult<()> {
+        self.client()
+            .request_client()
+            .group_mute_all(self.id(), mute)
+            .await
+            .map_err(AtriError::from)
+    }
+
+    async fn _operate_essence(&self, receipt: &MessageReceipt, set: bool) -> AtriResult<()> {
+        let (Some(&seq), Some(&rand)) = (receipt.seqs.first(), receipt.rands.first()) else {
+            return Err(AtriError::InvalidMessage(
+                "message receipt has no seq or rand",
+            ));
+        };
+
+        self.client()
+            .request_client()
+            .operate_group_essence(self.id(), seq, rand, set)
+            .await
+            .map(|_| ())
+            .map_err(AtriError::from)
+    }
+
+    /// 将消息设为精华消息, 回执中没有序号时返回[`AtriError::InvalidMessage`]
+    #[inline]
+    pub async fn set_essence(&self, receipt: &MessageReceipt) -> AtriResult<()> {
+        self._operate_essence(receipt, true).await
+    }
+
+    /// 移除精华消息
+    #[inline]
+    pub async fn remove_essence(&self, receipt: &MessageReceipt) -> AtriResult<()> {
+        self._operate_essence(receipt, false).await
+    }
+
+    /// 获取群公告列表
+    pub async fn announcements(&self) -> AtriResult<Vec<Announcement>> {
+        announcement::list(self).await
+    }
+
+    /// 发布群公告, 返回公告id
+    pub async fn publish_announcement<S: Into<String>>(
+        &self,
+        text: S,
+        options: AnnouncementOptions,
+    ) -> AtriResult<String> {
+        announcement::publish(self, text.into(), options).await
+    }
+
+    /// 删除群公告
+    pub async fn delete_announcement(&self, id: &str) -> AtriResult<()> {
+        announcement::delete(self, id).await
+    }
+
+    /// 获取群文件系统
+    pub fn fs(&self) -> GroupFs {
+        GroupFs::new(self.clone())
+    }
+
     pub async fn quit(&self) -> bool {
         let result = self.client().request_client().group_quit(self.id()).await;
         if let Err(e) = result {
@@ -243,8 +352,7 @@ impl Group {
         let inner = imp::GroupInner {
             client: WeakClient::new(client),
             info,
-            member_list_refreshed: AtomicBool::new(false),
-            members: DashMap::new(),
+            members: MemberCache::new(),
         };
 
         Self(Arc::new_cyclic(|weak| imp::Group {
@@ -254,7 +362,19 @@ impl Group {
     }
 
     #[inline]
-    pub(crate) fn members_cache(&self) -> &DashMap<i64, Option<NamedMember>> {
+    pub(crate) fn info(&self) -> &ricq::structs::GroupInfo {
+        &self.0.info
+    }
+
+    /// 继承旧群对象缓存的群成员, 用于刷新群信息时保留成员缓存
+    pub(crate) fn inherit_members(&self, old: &Group) {
+        self.members_cache().inherit(old.members_cache(), |named| {
+            NamedMember::from(self, named.info().clone())
+        });
+    }
+
+    #[inline]
+    pub(crate) fn members_cache(&self) -> &MemberCache<NamedMember> {
         &self.0.members
     }
 
@@ -264,36 +384,52 @@ impl Group {
     }
 
     pub(crate) fn remove_member_cache(&self, member_id: i64) -> Option<NamedMember> {
-        self.members_cache().remove(&member_id).and_then(|m| m.1)
+        self.members_cache().remove(member_id)
     }
 
+    /// 向服务器获取成员并更新缓存
     pub(crate) async fn try_refresh_member(&self, id: i64) -> AtriResult<Option<NamedMember>> {
-        let named = self
+        cache::refresh_member(self, id).await
+    }
+}
+
+#[async_trait]
+impl MemberSource for Group {
+    type Member = NamedMember;
+
+    fn cache(&self) -> &MemberCache<NamedMember> {
+        self.members_cache()
+    }
+
+    async fn fetch_member(&self, id: i64) -> AtriResult<Option<NamedMember>> {
+        let info = self
             .client()
             .request_client()
             .get_group_member_info(self.id(), id)
-            .await
-            .map(|info| {
-                if info.join_time == 0 {
-                    return None;
-                }
+            .await?;
 
-                let named = NamedMember::from(self, info);
-                self.cache_member(named.clone());
-                Some(named)
-            })?;
+        if info.join_time == 0 {
+            return Ok(None);
+        }
 
-        Ok(named)
+        Ok(Some(NamedMember::from(self, info)))
     }
 
-    pub(crate) async fn refresh_member(&self, id: i64) -> Option<NamedMember> {
-        self.try_refresh_member(id)
-            .await
-            .map_err(|e| {
-                error!("刷新成员时出现错误: {}", e);
-                e
+    async fn fetch_member_list(&self) -> AtriResult<Vec<(i64, NamedMember)>> {
+        let owner = self.0.info.owner_uin;
+        let list = self
+            .client()
+            .request_client()
+            .get_group_member_list(self.id(), owner)
+            .await?
+            .into_iter()
+            .map(|info| {
+                let named = NamedMember::from(self, info);
+                (named.id(), named)
             })
-            .unwrap_or(None)
+            .collect();
+
+        Ok(list)
     }
 }
 
@@ -327,13 +463,12 @@ impl WeakGroup {
 }
 
 mod imp {
-    use dashmap::DashMap;
     use ricq::structs::GroupInfo;
     use std::ops::Deref;
-    use std::sync::atomic::AtomicBool;
     use std::sync::Weak;
 
     use crate::client::WeakClient;
+    use crate::contact::cache::MemberCache;
     use crate::contact::member::NamedMember;
 
     pub struct Group {
@@ -352,8 +487,7 @@ mod imp {
     pub struct GroupInner {
         pub client: WeakClient,
         pub info: GroupInfo,
-        pub member_list_refreshed: AtomicBool,
-        pub members: DashMap<i64, Option<NamedMember>>,
+        pub members: MemberCache<NamedMember>,
     }
 }
 
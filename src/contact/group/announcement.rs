@@ -0,0 +1,283 @@
+use serde::{Deserialize, Serialize};
+
+use crate::client::web::{decode_response, http_client, QUN_DOMAIN};
+use crate::contact::group::Group;
+use crate::error::AtriResult;
+
+const LIST_URL: &str = "https://web.qun.qq.com/cgi-bin/announce/get_t_list";
+const PUBLISH_URL: &str = "https://web.qun.qq.com/cgi-bin/announce/add_qun_notice";
+const DELETE_URL: &str = "https://web.qun.qq.com/cgi-bin/announce/del_feed";
+
+/// 每页获取的公告数
+const PAGE_SIZE: usize = 20;
+/// 最多获取的页数, 避免接口异常时无限请求
+const MAX_PAGES: usize = 50;
+
+/// 群公告
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Announcement {
+    /// 公告id
+    pub id: String,
+    /// 发布者
+    pub author: i64,
+    /// 发布时间(Unix秒)
+    pub time: i64,
+    /// 公告内容
+    pub text: String,
+    /// 是否置顶
+    pub pinned: bool,
+    /// 是否需要群成员确认
+    pub confirm_required: bool,
+}
+
+/// 发布群公告的选项
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct AnnouncementOptions {
+    /// 是否置顶
+    pub pinned: bool,
+    /// 是否需要群成员确认
+    pub confirm_required: bool,
+    /// 是否弹窗提示
+    pub popup: bool,
+    /// 是否引导群成员修改群名片
+    pub show_edit_card: bool,
+}
+
+#[derive(Deserialize)]
+struct ListResponse {
+    #[serde(default)]
+    feeds: Vec<Feed>,
+    #[serde(default)]
+    inst: Vec<Feed>,
+}
+
+#[derive(Deserialize)]
+struct Feed {
+    fid: String,
+    u: i64,
+    pubt: i64,
+    msg: FeedMessage,
+    #[serde(default)]
+    pinned: i32,
+    #[serde(default)]
+    settings: FeedSettings,
+}
+
+#[derive(Deserialize)]
+struct FeedMessage {
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Deserialize, Default)]
+struct FeedSettings {
+    #[serde(default)]
+    confirm_required: i32,
+}
+
+#[derive(Deserialize)]
+struct PublishResponse {
+    #[serde(default)]
+    new_fid: String,
+}
+
+impl From<Feed> for Announcement {
+    fn from(feed: Feed) -> Self {
+        Self {
+            id: feed.fid,
+            author: feed.u,
+            time: feed.pubt,
+            text: unescape_html(&feed.msg.text),
+            pinned: feed.pinned != 0,
+            confirm_required: feed.settings.confirm_required != 0,
+        }
+    }
+}
+
+/// 逐页获取所有公告
+pub(super) async fn list(group: &Group) -> AtriResult<Vec<Announcement>> {
+    let credential = group.client().web_credential(QUN_DOMAIN).await;
+
+    let mut list = vec![];
+    for page in 0..MAX_PAGES {
+        // 首页的起始位置为-1
+        let start = match page {
+            0 => -1,
+            n => (n * PAGE_SIZE) as i64,
+        };
+
+        let bytes = http_client()
+            .get(LIST_URL)
+            .header(reqwest::header::COOKIE, &credential.cookie)
+            .query(&[
+                ("bkn", credential.bkn.to_string()),
+                ("qid", group.id().to_string()),
+                ("ft", "23".into()),
+                ("s", start.to_string()),
+                ("n", PAGE_SIZE.to_string()),
+                ("ni", "1".into()),
+                ("i", "1".into()),
+            ])
+            .send()
+            .await?
+            .bytes()
+            .await?;
+
+        let resp: ListResponse = decode_response(&bytes)?;
+        let last = resp.feeds.len() < PAGE_SIZE;
+        merge_feeds(&mut list, resp);
+
+        if last {
+            break;
+        }
+    }
+
+    Ok(list)
+}
+
+pub(super) async fn publish(
+    group: &Group,
+    text: String,
+    options: AnnouncementOptions,
+) -> AtriResult<String> {
+    let credential = group.client().web_credential(QUN_DOMAIN).await;
+
+    let settings = serde_json::json!({
+        "is_show_edit_card": options.show_edit_card as i32,
+        "tip_window_type": options.popup as i32,
+        "confirm_required": options.confirm_required as i32,
+    });
+
+    let bytes = http_client()
+        .post(PUBLISH_URL)
+        .header(reqwest::header::COOKIE, &credential.cookie)
+        .query(&[("bkn", credential.bkn)])
+        .form(&[
+            ("qid", group.id().to_string()),
+            ("bkn", credential.bkn.to_string()),
+            ("text", text),
+            ("pinned", (options.pinned as i32).to_string()),
+            ("type", "1".into()),
+            ("settings", settings.to_string()),
+        ])
+        .send()
+        .await?
+        .bytes()
+        .await?;
+
+    let resp: PublishResponse = decode_response(&bytes)?;
+
+    Ok(resp.new_fid)
+}
+
+pub(super) async fn delete(group: &Group, id: &str) -> AtriResult<()> {
+    let credential = group.client().web_credential(QUN_DOMAIN).await;
+
+    let bytes = http_client()
+        .post(DELETE_URL)
+        .header(reqwest::header::COOKIE, &credential.cookie)
+        .query(&[("bkn", credential.bkn)])
+        .form(&[
+            ("qid", group.id().to_string()),
+            ("bkn", credential.bkn.to_string()),
+            ("fid", id.to_owned()),
+            ("format", "json".into()),
+        ])
+        .send()
+        .await?
+        .bytes()
+        .await?;
+
+    decode_response::<serde_json::Value>(&bytes)?;
+
+    Ok(())
+}
+
+/// 将一页公告加入`list`, 置顶公告位于`inst`中, 可能与`feeds`及其他页重复
+fn merge_feeds(list: &mut Vec<Announcement>, resp: ListResponse) {
+    for feed in resp.inst.into_iter().chain(resp.feeds) {
+        if list.iter().all(|a| a.id != feed.fid) {
+            list.push(feed.into());
+        }
+    }
+}
+
+/// 公告内容为转义后的html文本
+fn unescape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+
+        let entity = &rest[1..end];
+        let c = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => entity
+                .strip_prefix('#')
+                .and_then(|n| match n.strip_prefix(['x', 'X']) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => n.parse().ok(),
+                })
+                .and_then(char::from_u32),
+        };
+
+        match c {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge_feeds, unescape_html, ListResponse};
+
+    #[test]
+    fn unescape() {
+        assert_eq!(unescape_html("a&#10;b&amp;c"), "a\nb&c");
+        assert_eq!(unescape_html("&lt;rule&gt; &#x41;"), "<rule> A");
+        assert_eq!(unescape_html("AT&T; & ;"), "AT&T; & ;");
+    }
+
+    #[test]
+    fn parse_list() {
+        let json = br#"{
+            "ec": 0,
+            "feeds": [
+                {"fid": "a", "u": 1, "pubt": 100, "msg": {"text": "weekly&#10;rules"}, "pinned": 1},
+                {"fid": "b", "u": 2, "pubt": 50, "msg": {"text": "hello"}, "settings": {"confirm_required": 1}}
+            ],
+            "inst": [
+                {"fid": "a", "u": 1, "pubt": 100, "msg": {"text": "weekly&#10;rules"}, "pinned": 1}
+            ]
+        }"#;
+
+        let resp: ListResponse = crate::client::web::decode_response(json).unwrap();
+        let mut list = vec![];
+        merge_feeds(&mut list, resp);
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].text, "weekly\nrules");
+        assert!(list[0].pinned);
+        assert!(list[1].confirm_required);
+    }
+}
@@ -0,0 +1,479 @@
+use rand::{thread_rng, Rng};
+use ricq::msg::MessageElem;
+use serde::{Deserialize, Serialize};
+
+use crate::client::web::{decode_response, http_client, WebCredential, QUN_DOMAIN};
+use crate::contact::group::Group;
+use crate::error::{AtriResult, WebApiError};
+
+const PAN_URL: &str = "https://pan.qun.qq.com/cgi-bin";
+
+/// 根目录的id
+pub const ROOT_FOLDER: &str = "/";
+
+/// 每页获取的条目数
+const PAGE_SIZE: usize = 50;
+/// 最多获取的页数, 避免接口异常时无限请求
+const MAX_PAGES: usize = 100;
+
+/// 群文件系统
+///
+/// 不支持上传文件: 上传需要通过协议的文件通道完成, 目前使用的协议库未提供此功能
+#[derive(Clone)]
+pub struct GroupFs {
+    group: Group,
+}
+
+/// 群文件夹
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GroupFolder {
+    pub id: String,
+    pub name: String,
+    pub parent_id: String,
+    /// 创建者
+    pub creator: i64,
+    /// 创建时间(Unix秒)
+    pub create_time: i64,
+    /// 修改时间(Unix秒)
+    pub modify_time: i64,
+    /// 文件夹内的文件数
+    pub file_count: u32,
+}
+
+/// 群文件
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GroupFile {
+    pub id: String,
+    pub name: String,
+    pub bus_id: u32,
+    pub parent_id: String,
+    /// 文件大小(字节)
+    pub size: u64,
+    /// 上传者
+    pub uploader: i64,
+    /// 上传时间(Unix秒)
+    pub upload_time: i64,
+    /// 过期时间(Unix秒), 永久文件为0
+    pub expire_time: i64,
+}
+
+/// 文件夹中的条目
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", content = "content")]
+#[serde(rename_all = "snake_case")]
+pub enum GroupFsEntry {
+    Folder(GroupFolder),
+    File(GroupFile),
+}
+
+impl GroupFs {
+    pub(super) fn new(group: Group) -> Self {
+        Self { group }
+    }
+
+    pub fn group(&self) -> &Group {
+        &self.group
+    }
+
+    /// 列出文件夹中的所有条目
+    pub async fn list(&self, folder_id: &str) -> AtriResult<Vec<GroupFsEntry>> {
+        let credential = self.credential().await;
+
+        let mut entries = vec![];
+        let mut start = 0;
+        for _ in 0..MAX_PAGES {
+            let bytes = http_client()
+                .get(format!("{PAN_URL}/group_file/get_file_list"))
+                .header(reqwest::header::COOKIE, &credential.cookie)
+                .query(&[
+                    ("gc", self.group.id().to_string()),
+                    ("bkn", credential.bkn.to_string()),
+                    ("start_index", start.to_string()),
+                    ("cnt", PAGE_SIZE.to_string()),
+                    ("filter_code", "0".into()),
+                    ("folder_id", folder_id.to_owned()),
+                    ("show_onlinedoc_folder", "0".into()),
+                ])
+                .send()
+                .await?
+                .bytes()
+                .await?;
+
+            let page: ListResponse = decode_response(&bytes)?;
+            let next = page.next_start(start);
+            entries.extend(page.file_list.into_iter().map(|e| e.into_entry(folder_id)));
+
+            match next {
+                Some(next) => start = next,
+                None => break,
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// 列出文件夹中的文件夹
+    pub async fn folders(&self, folder_id: &str) -> AtriResult<Vec<GroupFolder>> {
+        let folders = self
+            .list(folder_id)
+            .await?
+            .into_iter()
+            .filter_map(|e| match e {
+                GroupFsEntry::Folder(f) => Some(f),
+                GroupFsEntry::File(_) => None,
+            })
+            .collect();
+
+        Ok(folders)
+    }
+
+    /// 列出文件夹中的文件
+    pub async fn files(&self, folder_id: &str) -> AtriResult<Vec<GroupFile>> {
+        let files = self
+            .list(folder_id)
+            .await?
+            .into_iter()
+            .filter_map(|e| match e {
+                GroupFsEntry::File(f) => Some(f),
+                GroupFsEntry::Folder(_) => None,
+            })
+            .collect();
+
+        Ok(files)
+    }
+
+    /// 获取文件的下载链接
+    pub async fn download_url(&self, file: &GroupFile) -> AtriResult<String> {
+        let credential = self.credential().await;
+
+        let bytes = http_client()
+            .get(format!("{PAN_URL}/group_share_get_downurl"))
+            .header(reqwest::header::COOKIE, &credential.cookie)
+            .query(&[
+                ("uin", self.group.client().id().to_string()),
+                ("groupid", self.group.id().to_string()),
+                ("pa", format!("/{}{}", file.bus_id, file.id)),
+                ("r", thread_rng().gen::<u32>().to_string()),
+                ("charset", "utf-8".into()),
+                ("g_tk", credential.bkn.to_string()),
+            ])
+            .send()
+            .await?
+            .bytes()
+            .await?;
+
+        let resp: DownloadResponse = decode_response(&bytes)?;
+        if resp.code != 0 {
+            return Err(WebApiError::Response {
+                code: resp.code,
+                message: "cannot get download url".into(),
+            }
+            .into());
+        }
+
+        Ok(resp.data.url)
+    }
+
+    /// 在根目录创建文件夹
+    ///
+    /// 群文件只支持一层文件夹, 无法在其他文件夹中创建文件夹
+    pub async fn create_folder(&self, name: &str) -> AtriResult<()> {
+        self.post(
+            "group_file/create_folder",
+            &[("parent_id", ROOT_FOLDER.into()), ("name", name.into())],
+        )
+        .await
+    }
+
+    /// 删除文件夹及其中的文件
+    pub async fn delete_folder(&self, folder_id: &str) -> AtriResult<()> {
+        self.post(
+            "group_file/delete_folder",
+            &[("folder_id", folder_id.into())],
+        )
+        .await
+    }
+
+    pub async fn delete_file(&self, file: &GroupFile) -> AtriResult<()> {
+        self.post(
+            "group_file/delete_file",
+            &[
+                ("bus_id", file.bus_id.to_string()),
+                ("file_id", file.id.clone()),
+                ("parent_folder_id", file.parent_id.clone()),
+            ],
+        )
+        .await
+    }
+
+    /// 移动文件至另一个文件夹
+    pub async fn move_file(&self, file: &GroupFile, dest_folder_id: &str) -> AtriResult<()> {
+        self.post(
+            "group_file/move_file",
+            &[
+                ("bus_id", file.bus_id.to_string()),
+                ("file_id", file.id.clone()),
+                ("parent_folder_id", file.parent_id.clone()),
+                ("dest_folder_id", dest_folder_id.into()),
+            ],
+        )
+        .await
+    }
+
+    async fn credential(&self) -> WebCredential {
+        self.group.client().web_credential(QUN_DOMAIN).await
+    }
+
+    async fn post(&self, api: &str, params: &[(&str, String)]) -> AtriResult<()> {
+        let credential = self.credential().await;
+
+        let mut form = vec![
+            ("gc", self.group.id().to_string()),
+            ("bkn", credential.bkn.to_string()),
+        ];
+        form.extend(params.iter().cloned());
+
+        let bytes = http_client()
+            .post(format!("{PAN_URL}/{api}"))
+            .header(reqwest::header::COOKIE, &credential.cookie)
+            .form(&form)
+            .send()
+            .await?
+            .bytes()
+            .await?;
+
+        decode_response::<serde_json::Value>(&bytes)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct ListResponse {
+    #[serde(default)]
+    file_list: Vec<ListEntry>,
+    #[serde(default)]
+    is_end: bool,
+    next_index: Option<usize>,
+}
+
+impl ListResponse {
+    /// 下一页的起始位置, 已是最后一页或起始位置没有前进时返回`None`
+    fn next_start(&self, start: usize) -> Option<usize> {
+        let len = self.file_list.len();
+        if self.is_end || len == 0 {
+            return None;
+        }
+
+        let next = self.next_index.unwrap_or(start + len);
+        (next > start).then_some(next)
+    }
+}
+
+#[derive(Deserialize)]
+struct ListEntry {
+    /// 1: 文件, 2: 文件夹
+    #[serde(rename = "type")]
+    ty: i32,
+    id: String,
+    name: String,
+    #[serde(default)]
+    bus_id: u32,
+    #[serde(default)]
+    size: u64,
+    #[serde(default)]
+    owner_uin: i64,
+    #[serde(default)]
+    create_time: i64,
+    #[serde(default)]
+    modify_time: i64,
+    #[serde(default)]
+    dead_time: i64,
+    #[serde(default)]
+    file_count: u32,
+}
+
+impl ListEntry {
+    fn into_entry(self, parent_id: &str) -> GroupFsEntry {
+        if self.ty == 2 {
+            GroupFsEntry::Folder(GroupFolder {
+                id: self.id,
+                name: self.name,
+                parent_id: parent_id.to_owned(),
+                creator: self.owner_uin,
+                create_time: self.create_time,
+                modify_time: self.modify_time,
+                file_count: self.file_count,
+            })
+        } else {
+            GroupFsEntry::File(GroupFile {
+                id: self.id,
+                name: self.name,
+                bus_id: self.bus_id,
+                parent_id: parent_id.to_owned(),
+                size: self.size,
+                uploader: self.owner_uin,
+                upload_time: self.create_time,
+                expire_time: self.dead_time,
+            })
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct DownloadResponse {
+    #[serde(default)]
+    code: i64,
+    data: DownloadData,
+}
+
+#[derive(Deserialize)]
+struct DownloadData {
+    url: String,
+}
+
+/// 群文件消息中的文件信息, 以`TransElem`传输
+mod trans {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ObjMsg {
+        #[prost(uint32, optional, tag = "1")]
+        pub msg_type: Option<u32>,
+        #[prost(message, repeated, tag = "7")]
+        pub content_info: Vec<MsgContentInfo>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct MsgContentInfo {
+        #[prost(message, optional, tag = "2")]
+        pub file: Option<MsgFile>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct MsgFile {
+        #[prost(uint32, optional, tag = "1")]
+        pub bus_id: Option<u32>,
+        #[prost(bytes = "vec", optional, tag = "2")]
+        pub file_path: Option<Vec<u8>>,
+        #[prost(uint64, optional, tag = "3")]
+        pub file_size: Option<u64>,
+        #[prost(string, optional, tag = "4")]
+        pub file_name: Option<String>,
+        #[prost(int64, optional, tag = "5")]
+        pub dead_time: Option<i64>,
+    }
+}
+
+/// 群文件消息的`TransElem`类型
+const GROUP_FILE_TRANS_TYPE: i32 = 24;
+
+/// 解析群文件消息中的文件信息
+pub(crate) fn parse_upload(
+    elems: &ricq::msg::MessageChain,
+    uploader: i64,
+    time: i64,
+) -> Option<GroupFile> {
+    elems.0.iter().find_map(|elem| {
+        let MessageElem::TransElemInfo(trans) = elem else {
+            return None;
+        };
+
+        if trans.elem_type != Some(GROUP_FILE_TRANS_TYPE) {
+            return None;
+        }
+
+        parse_trans_value(trans.elem_value.as_deref()?, uploader, time)
+    })
+}
+
+/// `TransElem`的值为1字节的标志, 2字节的长度以及`ObjMsg`
+fn parse_trans_value(value: &[u8], uploader: i64, time: i64) -> Option<GroupFile> {
+    let len = u16::from_be_bytes(value.get(1..3)?.try_into().ok()?) as usize;
+    let obj: trans::ObjMsg = prost::Message::decode(value.get(3..3 + len)?).ok()?;
+    let file = obj.content_info.into_iter().find_map(|c| c.file)?;
+
+    Some(GroupFile {
+        id: String::from_utf8(file.file_path?).ok()?,
+        name: file.file_name.unwrap_or_default(),
+        bus_id: file.bus_id.unwrap_or_default(),
+        parent_id: ROOT_FOLDER.into(),
+        size: file.file_size.unwrap_or_default(),
+        uploader,
+        upload_time: time,
+        expire_time: file.dead_time.unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_trans_value, trans, GroupFsEntry, ListResponse};
+    use crate::client::web::decode_response;
+
+    #[test]
+    fn trans_elem() {
+        let obj = trans::ObjMsg {
+            msg_type: Some(6),
+            content_info: vec![trans::MsgContentInfo {
+                file: Some(trans::MsgFile {
+                    bus_id: Some(102),
+                    file_path: Some(b"/a1b2c3".to_vec()),
+                    file_size: Some(1024),
+                    file_name: Some("notes.pdf".into()),
+                    dead_time: None,
+                }),
+            }],
+        };
+        let body = prost::Message::encode_to_vec(&obj);
+
+        let mut value = vec![1];
+        value.extend_from_slice(&(body.len() as u16).to_be_bytes());
+        value.extend_from_slice(&body);
+
+        let file = parse_trans_value(&value, 114514, 1).unwrap();
+        assert_eq!(file.id, "/a1b2c3");
+        assert_eq!(file.name, "notes.pdf");
+        assert_eq!(file.bus_id, 102);
+        assert_eq!(file.size, 1024);
+
+        assert!(parse_trans_value(&value[..4], 114514, 1).is_none());
+    }
+
+    #[test]
+    fn list_entries() {
+        let json = br#"{
+            "ec": 0,
+            "is_end": true,
+            "file_list": [
+                {"type": 2, "id": "/f1", "name": "docs", "file_count": 3},
+                {"type": 1, "id": "/a1b2c3", "name": "notes.pdf", "bus_id": 102, "size": 1024}
+            ]
+        }"#;
+
+        let resp: ListResponse = decode_response(json).unwrap();
+        let entries: Vec<_> = resp
+            .file_list
+            .into_iter()
+            .map(|e| e.into_entry("/"))
+            .collect();
+
+        assert!(matches!(&entries[0], GroupFsEntry::Folder(f) if f.file_count == 3));
+        assert!(matches!(&entries[1], GroupFsEntry::File(f) if f.bus_id == 102));
+    }
+
+    #[test]
+    fn next_page() {
+        let page = |is_end: bool, next_index: Option<usize>| -> ListResponse {
+            let json = format!(
+                r#"{{"ec": 0, "is_end": {is_end}, "next_index": {}, "file_list": [{{"type": 1, "id": "/a", "name": "a"}}]}}"#,
+                next_index.map_or("null".into(), |i| i.to_string())
+            );
+            decode_response(json.as_bytes()).unwrap()
+        };
+
+        assert_eq!(page(false, Some(50)).next_start(0), Some(50));
+        assert_eq!(page(false, None).next_start(50), Some(51));
+        assert_eq!(page(true, Some(100)).next_start(50), None);
+        // next_index not advancing
+        assert_eq!(page(false, Some(50)).next_start(50), None);
+        assert_eq!(page(false, Some(0)).next_start(50), None);
+    }
+}
@@ -8,6 +8,7 @@ use atri_ffi::contact::FFIMember;
 use atri_ffi::ffi::ForFFI;
 use atri_ffi::ManagedCloneable;
 use core::fmt;
+use ricq::structs::GroupMemberPermission;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -90,6 +91,66 @@ impl NamedMember {
         &self.0.info.card_name
     }
 
+    /// 成员在群中的权限
+    pub fn permission(&self) -> MemberPermission {
+        MemberPermission::from(&self.0.info.permission)
+    }
+
+    /// 成员是否为群主
+    pub fn is_owner(&self) -> bool {
+        self.permission() == MemberPermission::Owner
+    }
+
+    /// 成员是否为管理员, 群主也视为管理员
+    pub fn is_admin(&self) -> bool {
+        self.permission() >= MemberPermission::Administrator
+    }
+
+    /// 入群时间(Unix秒)
+    pub fn join_time(&self) -> i64 {
+        self.0.info.join_time
+    }
+
+    /// 最后发言时间(Unix秒)
+    pub fn last_speak_time(&self) -> i64 {
+        self.0.info.last_speak_time
+    }
+
+    /// 专属头衔
+    pub fn special_title(&self) -> &str {
+        &self.0.info.special_title
+    }
+
+    /// 专属头衔过期时间(Unix秒)
+    pub fn special_title_expire_time(&self) -> i64 {
+        self.0.info.special_title_expire_time
+    }
+
+    /// 群等级
+    pub fn level(&self) -> u16 {
+        self.0.info.level
+    }
+
+    /// 性别
+    pub fn gender(&self) -> u8 {
+        self.0.info.gender
+    }
+
+    /// 禁言解除时间(Unix秒), 未被禁言时为0
+    pub fn mute_expire_time(&self) -> i64 {
+        self.0.info.shut_up_timestamp
+    }
+
+    /// 成员当前是否被禁言
+    pub fn is_muted(&self) -> bool {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+
+        self.mute_expire_time() > now
+    }
+
     pub fn group(&self) -> Group {
         self.0.group.force_upgrade()
     }
@@ -107,6 +168,32 @@ impl NamedMember {
             .map_err(AtriError::from)
     }
 
+    /// 解除禁言
+    #[inline]
+    pub async fn unmute(&self) -> AtriResult<()> {
+        self.mute(Duration::ZERO).await
+    }
+
+    /// 设置或取消管理员, 需要群主权限
+    pub async fn set_admin(&self, admin: bool) -> AtriResult<()> {
+        self.group()
+            .client()
+            .request_client()
+            .group_set_admin(self.group().id(), self.id(), admin)
+            .await
+            .map_err(AtriError::from)
+    }
+
+    /// 设置专属头衔, 需要群主权限
+    pub async fn set_special_title<S: Into<String>>(&self, title: S) -> AtriResult<()> {
+        self.group()
+            .client()
+            .request_client()
+            .group_edit_special_title(self.group().id(), self.id(), title.into())
+            .await
+            .map_err(AtriError::from)
+    }
+
     pub async fn kick<S: AsRef<str>>(&self, msg: Option<S>, block: bool) -> AtriResult<()> {
         let msg = msg.as_ref().map(AsRef::<str>::as_ref).unwrap_or("");
 
@@ -161,6 +248,68 @@ impl NamedMember {
 
         Self(inner.into())
     }
+
+    #[inline]
+    pub(crate) fn info(&self) -> &GroupMemberInfo {
+        &self.0.info
+    }
+}
+
+/// 群成员的权限, 可比较大小: 群主 > 管理员 > 成员
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum MemberPermission {
+    Member = 0,
+    Administrator = 1,
+    Owner = 2,
+}
+
+impl From<&GroupMemberPermission> for MemberPermission {
+    fn from(value: &GroupMemberPermission) -> Self {
+        match value {
+            GroupMemberPermission::Owner => Self::Owner,
+            GroupMemberPermission::Administrator => Self::Administrator,
+            GroupMemberPermission::Member => Self::Member,
+        }
+    }
+}
+
+impl From<u8> for MemberPermission {
+    fn from(value: u8) -> Self {
+        match value {
+            2 => Self::Owner,
+            1 => Self::Administrator,
+            _ => Self::Member,
+        }
+    }
+}
+
+impl From<MemberPermission> for GroupMemberPermission {
+    fn from(value: MemberPermission) -> Self {
+        match value {
+            MemberPermission::Owner => Self::Owner,
+            MemberPermission::Administrator => Self::Administrator,
+            MemberPermission::Member => Self::Member,
+        }
+    }
+}
+
+impl fmt::Display for MemberPermission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Member => "成员",
+            Self::Administrator => "管理员",
+            Self::Owner => "群主",
+        };
+
+        f.write_str(s)
+    }
+}
+
+impl From<&NamedMember> for At {
+    fn from(member: &NamedMember) -> Self {
+        member.at()
+    }
 }
 
 impl fmt::Debug for NamedMember {
@@ -5,6 +5,7 @@ use crate::error::{AtriError, AtriResult};
 use crate::message::meta::MessageReceipt;
 use crate::message::MessageChain;
 
+pub mod cache;
 pub mod friend;
 pub mod group;
 pub mod member;
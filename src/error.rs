@@ -9,7 +9,10 @@ pub enum AtriError {
     IO(io::Error),
     Protocol(ricq::RQError),
     Login(LoginError),
+    WebApi(WebApiError),
     NotSupported,
+    /// 消息的内容无法发送
+    InvalidMessage(&'static str),
 }
 
 impl Display for AtriError {
@@ -22,7 +25,12 @@ impl Display for AtriError {
             }
             Self::PluginError(e) => Display::fmt(e, f),
             Self::Protocol(e) => Display::fmt(e, f),
+            Self::WebApi(e) => Display::fmt(e, f),
             Self::NotSupported => f.write_str("operation not supported"),
+            Self::InvalidMessage(reason) => {
+                f.write_str("invalid message: ")?;
+                f.write_str(reason)
+            }
         }
     }
 }
@@ -34,6 +42,10 @@ pub enum LoginError {
     TokenNotExist,
     WrongToken,
     TokenLoginFailed,
+    /// 登录信息已加密, 但未配置密钥
+    StorageKeyNotProvided,
+    /// 密钥错误, 无法解密登录信息
+    WrongStorageKey,
 }
 
 impl Display for LoginError {
@@ -42,12 +54,50 @@ impl Display for LoginError {
             Self::TokenNotExist => f.write_str("token not exist"),
             Self::WrongToken => f.write_str("wrong token"),
             Self::TokenLoginFailed => f.write_str("token login failed. maybe the token is expired"),
+            Self::StorageKeyNotProvided => {
+                f.write_str("login info is encrypted, but no storage key provided")
+            }
+            Self::WrongStorageKey => {
+                f.write_str("cannot decrypt login info. maybe the storage key is wrong")
+            }
         }
     }
 }
 
 impl std::error::Error for LoginError {}
 
+/// 调用QQ网页接口时的错误
+#[derive(Debug)]
+pub enum WebApiError {
+    /// 请求失败
+    Http(reqwest::Error),
+    /// 无法解析响应
+    Decode(serde_json::Error),
+    /// 服务器返回了错误码
+    Response { code: i64, message: String },
+}
+
+impl Display for WebApiError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("web api ")?;
+        match self {
+            Self::Http(e) => {
+                f.write_str("request failed: ")?;
+                Display::fmt(e, f)
+            }
+            Self::Decode(e) => {
+                f.write_str("decode failed: ")?;
+                Display::fmt(e, f)
+            }
+            Self::Response { code, message } => {
+                write!(f, "returned error code {code}: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for WebApiError {}
+
 #[derive(Debug)]
 pub enum PluginError {
     InitializeFail(&'static str),
@@ -84,8 +134,26 @@ impl From<ricq::RQError> for AtriError {
     }
 }
 
+impl From<LoginError> for AtriError {
+    fn from(err: LoginError) -> Self {
+        Self::Login(err)
+    }
+}
+
 impl From<PluginError> for AtriError {
     fn from(err: PluginError) -> Self {
         Self::PluginError(err)
     }
 }
+
+impl From<WebApiError> for AtriError {
+    fn from(err: WebApiError) -> Self {
+        Self::WebApi(err)
+    }
+}
+
+impl From<reqwest::Error> for AtriError {
+    fn from(err: reqwest::Error) -> Self {
+        Self::WebApi(WebApiError::Http(err))
+    }
+}
@@ -7,9 +7,13 @@ use atri_ffi::ManagedCloneable;
 use ricq::handler::QEvent;
 
 use crate::contact::friend::Friend;
+use crate::contact::group::files::GroupFile;
 use crate::contact::group::Group;
 use crate::contact::member::{Member, NamedMember};
 use crate::contact::{Contact, ContactSubject};
+use crate::error::AtriResult;
+use crate::message::reply::RepliedMessage;
+use crate::message::voice::Voice;
 use crate::message::MessageChain;
 use crate::{Client, Listener};
 
@@ -25,6 +29,7 @@ pub enum Event {
     DeleteFriend(DeleteFriendEvent),
     FriendPoke(FriendPokeEvent),
     GroupPoke(GroupPokeEvent),
+    GroupFileUpload(GroupFileUploadEvent),
     Unknown(SharedEvent<QEvent>),
 }
 
@@ -48,6 +53,7 @@ impl Event {
             DeleteFriend => 4;
             FriendPoke => 5;
             GroupPoke => 6;
+            GroupFileUpload => 7;
             Unknown => 255;
         };
 
@@ -80,6 +86,7 @@ macro_rules! event_fun_impl {
             DeleteFriend,
             FriendPoke,
             GroupPoke,
+            GroupFileUpload,
             Unknown;
             $name: $ret as $func
         }
@@ -171,6 +178,11 @@ impl GroupMessageEvent {
         &self.inner().message
     }
 
+    /// 此消息回复的完整消息, 见[`MessageChain::resolve_reply`]
+    pub async fn replied_message(&self) -> AtriResult<Option<RepliedMessage>> {
+        self.message().resolve_reply(self.group()).await
+    }
+
     pub async fn next_event<F>(&self, timeout: Duration, filter: F) -> Option<GroupMessageEvent>
     where
         F: Fn(&GroupMessageEvent) -> bool,
@@ -212,11 +224,17 @@ impl GroupMessageEvent {
         group: Group,
         sender: Member,
         ori: ricq::client::event::GroupMessageEvent,
+        voice: Option<Voice>,
     ) -> Self {
+        let mut message = MessageChain::from(ori.inner);
+        if let Some(voice) = voice {
+            message.push(voice.into());
+        }
+
         Self::new(imp::GroupMessageEvent {
             group,
             sender,
-            message: ori.inner.into(),
+            message,
         })
     }
 }
@@ -252,11 +270,17 @@ impl FriendMessageEvent {
         &self.inner().message
     }
 
-    pub(crate) fn from(friend: Friend, ori: ricq::client::event::FriendMessageEvent) -> Self {
-        let imp = imp::FriendMessageEvent {
-            friend,
-            message: ori.inner.into(),
-        };
+    pub(crate) fn from(
+        friend: Friend,
+        ori: ricq::client::event::FriendMessageEvent,
+        voice: Option<Voice>,
+    ) -> Self {
+        let mut message = MessageChain::from(ori.inner);
+        if let Some(voice) = voice {
+            message.push(voice.into());
+        }
+
+        let imp = imp::FriendMessageEvent { friend, message };
 
         Self::new(imp)
     }
@@ -376,6 +400,57 @@ impl GroupPokeEvent {
     }
 }
 
+pub type GroupFileUploadEvent = SharedEvent<imp::GroupFileUploadEvent>;
+
+impl GroupFileUploadEvent {
+    pub fn group(&self) -> &Group {
+        &self.inner().group
+    }
+
+    pub fn client(&self) -> Client {
+        self.group().client()
+    }
+
+    pub fn uploader(&self) -> &NamedMember {
+        &self.inner().uploader
+    }
+
+    pub fn file(&self) -> &GroupFile {
+        &self.inner().file
+    }
+
+    /// 获取上传文件的下载链接
+    pub async fn download_url(&self) -> AtriResult<String> {
+        self.group().fs().download_url(self.file()).await
+    }
+}
+
+impl GroupFileUploadEvent {
+    pub(crate) fn from(group: Group, uploader: NamedMember, file: GroupFile) -> Self {
+        Self::new(imp::GroupFileUploadEvent {
+            group,
+            uploader,
+            file,
+        })
+    }
+}
+
+impl ContactSubject for GroupFileUploadEvent {
+    fn subject(&self) -> Contact {
+        Contact::Group(self.group().clone())
+    }
+}
+
+impl FromEvent for GroupFileUploadEvent {
+    fn from_event(e: Event) -> Option<Self> {
+        if let Event::GroupFileUpload(e) = e {
+            Some(e)
+        } else {
+            None
+        }
+    }
+}
+
 impl From<QEvent> for SharedEvent<QEvent> {
     fn from(value: QEvent) -> Self {
         Self::new(value)
@@ -385,6 +460,7 @@ impl From<QEvent> for SharedEvent<QEvent> {
 mod imp {
 
     use crate::contact::friend::Friend;
+    use crate::contact::group::files::GroupFile;
     use crate::contact::group::Group;
     use crate::contact::member::{Member, NamedMember};
     use crate::message::MessageChain;
@@ -422,6 +498,12 @@ mod imp {
         pub sender: NamedMember,
         pub target: NamedMember,
     }
+
+    pub struct GroupFileUploadEvent {
+        pub group: Group,
+        pub uploader: NamedMember,
+        pub file: GroupFile,
+    }
 }
 
 pub enum MessageEvent {
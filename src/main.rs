@@ -1,7 +1,8 @@
 use std::error::Error;
 use std::time::Duration;
 
-use atri_bot::service::command::{builtin::handle_plugin_command, PLUGIN_COMMAND};
+use atri_bot::service::command::builtin::{handle_encrypt_command, handle_plugin_command};
+use atri_bot::service::command::{ENCRYPT_COMMAND, PLUGIN_COMMAND};
 use atri_bot::service::log::init_logger;
 use atri_bot::service::login::login_clients;
 use atri_bot::service::plugin::PluginManager;
@@ -18,6 +19,10 @@ fn main() -> MainResult {
     let _guards = init_logger();
     atri_bot::signal::init_crash_handler();
     atri_bot::service::plugin::init_plugin_service();
+    atri_bot::service::coordinator::init_coordinator_service();
+    atri_bot::service::cache::init_cache_service();
+    atri_bot::service::history::init_history_service();
+    atri_bot::service::scheduler::init_scheduler_service();
     pre_create_dirs();
 
     // start
@@ -60,6 +65,7 @@ fn main() -> MainResult {
 }
 
 async fn main0() -> MainResult {
+    atri_bot::service::history::start_retention_task();
     login_clients().await?;
 
     Ok(())
@@ -98,7 +104,11 @@ async fn loop_cli(manager: &mut PluginManager) -> MainResult {
                     stdout.flush().await?;
                 }
                 "help" | "?" | "h" => {
-                    static INFOS: &[&str] = &["help: 显示本帮助", "exit: 退出程序"];
+                    static INFOS: &[&str] = &[
+                        "help: 显示本帮助",
+                        "exit: 退出程序",
+                        "encrypt: 加密已保存的登录信息",
+                    ];
 
                     let mut s = String::from('\n');
                     for &info in INFOS {
@@ -117,6 +127,11 @@ async fn loop_cli(manager: &mut PluginManager) -> MainResult {
                         error!("{}", e);
                     }
                 }
+                encrypt if encrypt.starts_with(ENCRYPT_COMMAND) => {
+                    if let Err(e) = handle_encrypt_command(encrypt) {
+                        error!("{}", e);
+                    }
+                }
                 _ => {
                     info!("未知的命令 '{}', 使用 'help' 显示帮助信息", cmd);
                 }
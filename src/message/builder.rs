@@ -0,0 +1,115 @@
+use crate::message::at::At;
+use crate::message::face::Face;
+use crate::message::image::Image;
+use crate::message::meta::MessageMetadata;
+use crate::message::{MessageChain, MessageElement};
+
+/// 消息链构建器
+///
+/// ```ignore
+/// let chain = MessageChain::builder()
+///     .reply_to(event.message())
+///     .at(&member)
+///     .text(" 早上好")
+///     .build();
+/// ```
+#[derive(Clone, Default)]
+pub struct MessageChainBuilder {
+    meta: MessageMetadata,
+    elements: Vec<MessageElement>,
+}
+
+impl MessageChainBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加文本, 与前一段文本相邻时合并
+    pub fn text<S: AsRef<str>>(mut self, text: S) -> Self {
+        match self.elements.last_mut() {
+            Some(MessageElement::Text(last)) => last.push_str(text.as_ref()),
+            _ => self
+                .elements
+                .push(MessageElement::Text(text.as_ref().to_owned())),
+        }
+
+        self
+    }
+
+    pub fn at<A: Into<At>>(self, target: A) -> Self {
+        self.push(MessageElement::At(target.into()))
+    }
+
+    pub fn at_all(self) -> Self {
+        self.push(MessageElement::AtAll)
+    }
+
+    pub fn face(self, face: Face) -> Self {
+        self.push(MessageElement::Face(face))
+    }
+
+    pub fn image(self, image: Image) -> Self {
+        self.push(MessageElement::Image(image))
+    }
+
+    /// 添加任意元素
+    pub fn push<E: Into<MessageElement>>(mut self, elem: E) -> Self {
+        self.elements.push(elem.into());
+        self
+    }
+
+    /// 回复一条消息, 该消息需包含序号(即收到的或已发送的消息), 不包含序号时不做任何事
+    pub fn reply_to(mut self, chain: &MessageChain) -> Self {
+        if !chain.metadata().seqs.is_empty() {
+            self.meta.reply = Some(chain.reply());
+        }
+
+        self
+    }
+
+    pub fn build(self) -> MessageChain {
+        MessageChain {
+            meta: self.meta,
+            elements: self.elements,
+            raw_elements: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::message::at::At;
+    use crate::message::{MessageChain, MessageElement};
+
+    #[test]
+    fn build() {
+        let mut source = MessageChain::from(vec![MessageElement::Text("早".into())]);
+        source.metadata_mut().seqs = vec![114];
+        source.metadata_mut().sender = 514;
+
+        let chain = MessageChain::builder()
+            .reply_to(&source)
+            .text("Hello")
+            .text(", ")
+            .at(At {
+                target: 1919,
+                display: "Atri".into(),
+            })
+            .at_all()
+            .build();
+
+        assert_eq!(chain.len(), 3);
+        assert_eq!(chain.plain_text(), "Hello, ");
+        assert!(chain.contains_at(1919));
+        assert!(chain.contains_at_all());
+        assert_eq!(chain.referred().map(|r| r.reply_seq), Some(114));
+    }
+
+    #[test]
+    fn reply_without_seq() {
+        let source = MessageChain::from(vec![MessageElement::Text("早".into())]);
+        let chain = MessageChain::builder().reply_to(&source).text("Hi").build();
+
+        assert!(chain.referred().is_none());
+    }
+}
@@ -0,0 +1,613 @@
+//! 消息链的文本格式
+//!
+//! 文本原样书写, 其中的`\`和`$`需以`\`转义.
+//! 其他元素写作`$[类型]`或`$[类型:参数,参数]`, 参数中的`\`, `,`和`]`需以`\`转义,
+//! 最后一个参数中的`,`可不转义.
+//!
+//! | 元素 | 格式 |
+//! | --- | --- |
+//! | [`At`] | `$[at:qq号]`, `$[at:qq号,显示名]` |
+//! | 全体成员 | `$[at_all]` |
+//! | [`Face`] | `$[face:id]`, `$[face:id,名称]` |
+//! | [`Dice`] | `$[dice:点数]` |
+//! | [`FingerGuessing`] | `$[finger_guessing:rock]`, 可选`rock`, `scissors`, `paper` |
+//! | [`Poke`] | `$[poke]`, `$[poke:名称,类型,id]` |
+//! | json卡片 | `$[light_app:内容]` |
+//! | xml卡片 | `$[xml:服务id,内容]` |
+//!
+//! 其余元素的参数为其json序列化的内容, 如`$[image:{"Group":{...}}]`,
+//! 类型与[`MessageChain::to_json`]中的`type`相同. 未知元素的类型为`raw`,
+//! 无法还原原始数据的未知元素写作`$[unknown]`, 无法被解析.
+//!
+//! 另支持CQ码, 见[`MessageChain::to_cq_code`].
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter, Write};
+use std::iter::Peekable;
+use std::mem;
+use std::str::CharIndices;
+
+use serde_json::Value;
+
+use crate::message::at::At;
+use crate::message::face::Face;
+use crate::message::market_face::{Dice, FingerGuessing};
+use crate::message::poke::Poke;
+use crate::message::{MessageChain, MessageElement};
+
+impl MessageChain {
+    /// 编码为文本格式, 仅包含消息元素, 不含元数据
+    ///
+    /// 由[`MessageChain::from_code`]解析时, 相邻的文本会合并, 空文本会被忽略
+    pub fn to_code(&self) -> String {
+        let mut s = String::new();
+        for elem in self {
+            write_code(elem, &mut s);
+        }
+
+        s
+    }
+
+    /// 解析文本格式的消息
+    pub fn from_code(code: &str) -> Result<Self, CodeError> {
+        let mut elements = vec![];
+        let mut text = String::new();
+        let mut chars = code.char_indices().peekable();
+
+        while let Some((pos, c)) = chars.next() {
+            match c {
+                '\\' => text.push(chars.next().map_or('\\', |(_, c)| c)),
+                '$' if matches!(chars.peek(), Some((_, '['))) => {
+                    chars.next();
+                    let (kind, args) = read_code(&mut chars, pos)?;
+                    let elem = element(&kind, args).map_err(|msg| CodeError::new(pos, msg))?;
+
+                    if !text.is_empty() {
+                        elements.push(MessageElement::Text(mem::take(&mut text)));
+                    }
+                    elements.push(elem);
+                }
+                or => text.push(or),
+            }
+        }
+
+        if !text.is_empty() {
+            elements.push(MessageElement::Text(text));
+        }
+
+        Ok(Self::from(elements))
+    }
+
+    /// 编码为CQ码
+    ///
+    /// 没有对应CQ码的元素写作`[CQ:类型,data=json内容]`, 类型同文本格式
+    pub fn to_cq_code(&self) -> String {
+        let mut s = String::new();
+        for elem in self {
+            write_cq_code(elem, &mut s);
+        }
+
+        s
+    }
+
+    /// 解析CQ码
+    ///
+    /// 未指定值的`dice`和`rps`将随机生成
+    pub fn from_cq_code(code: &str) -> Result<Self, CodeError> {
+        let mut elements = vec![];
+        let mut rest = code;
+        let mut offset = 0;
+
+        while let Some(start) = rest.find("[CQ:") {
+            if start > 0 {
+                elements.push(MessageElement::Text(cq_unescape(&rest[..start])));
+            }
+
+            let pos = offset + start;
+            let len = rest[start..]
+                .find(']')
+                .ok_or_else(|| CodeError::new(pos, "unclosed CQ code"))?;
+            let body = &rest[start + 4..start + len];
+            elements.push(cq_element(body).map_err(|msg| CodeError::new(pos, msg))?);
+
+            rest = &rest[start + len + 1..];
+            offset = pos + len + 1;
+        }
+
+        if !rest.is_empty() {
+            elements.push(MessageElement::Text(cq_unescape(rest)));
+        }
+
+        Ok(Self::from(elements))
+    }
+}
+
+/// 解析文本格式时的错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeError {
+    /// 出错元素在文本中的字节位置
+    pub position: usize,
+    pub message: String,
+}
+
+impl CodeError {
+    fn new<S: Into<String>>(position: usize, message: S) -> Self {
+        Self {
+            position,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for CodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl Error for CodeError {}
+
+fn escape_text(text: &str, s: &mut String) {
+    for c in text.chars() {
+        if matches!(c, '\\' | '$') {
+            s.push('\\');
+        }
+        s.push(c);
+    }
+}
+
+fn write_args(kind: &str, args: &[&str], s: &mut String) {
+    s.push_str("$[");
+    s.push_str(kind);
+
+    for (i, arg) in args.iter().enumerate() {
+        s.push(if i == 0 { ':' } else { ',' });
+
+        let last = i == args.len() - 1;
+        for c in arg.chars() {
+            if matches!(c, '\\' | ']') || (c == ',' && !last) {
+                s.push('\\');
+            }
+            s.push(c);
+        }
+    }
+
+    s.push(']');
+}
+
+/// 元素序列化后的类型和json内容, 无法序列化时返回`None`
+fn json_parts(elem: &MessageElement) -> Option<(String, String)> {
+    let value = serde_json::to_value(elem).ok()?;
+    let kind = value["type"].as_str().unwrap_or_default().to_owned();
+
+    Some((kind, value["content"].to_string()))
+}
+
+fn finger_guessing_name(value: FingerGuessing) -> &'static str {
+    match value {
+        FingerGuessing::Rock => "rock",
+        FingerGuessing::Scissors => "scissors",
+        FingerGuessing::Paper => "paper",
+    }
+}
+
+fn parse_finger_guessing(s: &str) -> Result<FingerGuessing, String> {
+    match s {
+        "rock" => Ok(FingerGuessing::Rock),
+        "scissors" => Ok(FingerGuessing::Scissors),
+        "paper" => Ok(FingerGuessing::Paper),
+        or => Err(format!("unknown finger guessing value: {or}")),
+    }
+}
+
+fn write_code(elem: &MessageElement, s: &mut String) {
+    match elem {
+        MessageElement::Text(text) => escape_text(text, s),
+        MessageElement::At(At { target, display }) => {
+            let target = target.to_string();
+            if display.is_empty() {
+                write_args("at", &[&target], s);
+            } else {
+                write_args("at", &[&target, display], s);
+            }
+        }
+        MessageElement::AtAll => write_args("at_all", &[], s),
+        MessageElement::Face(Face { index, name }) => {
+            let index = index.to_string();
+            if name.is_empty() {
+                write_args("face", &[&index], s);
+            } else {
+                write_args("face", &[&index, name], s);
+            }
+        }
+        MessageElement::Dice(dice) => write_args("dice", &[&dice.value.to_string()], s),
+        MessageElement::FingerGuessing(value) => {
+            write_args("finger_guessing", &[finger_guessing_name(*value)], s)
+        }
+        MessageElement::Poke(poke) if *poke == Poke::default() => write_args("poke", &[], s),
+        MessageElement::Poke(Poke {
+            name,
+            poke_type,
+            id,
+        }) => write_args("poke", &[name, &poke_type.to_string(), &id.to_string()], s),
+        MessageElement::LightApp(content) => write_args("light_app", &[content], s),
+        MessageElement::Xml {
+            service_id,
+            content,
+        } => write_args("xml", &[&service_id.to_string(), content], s),
+        or => match json_parts(or) {
+            Some((kind, content)) => write_args(&kind, &[&content], s),
+            None => write_args("unknown", &[], s),
+        },
+    }
+}
+
+/// 元素参数的最大数量, 最后一个参数中的`,`不作为分隔符
+fn arity(kind: &str) -> usize {
+    match kind {
+        "at" | "face" | "xml" => 2,
+        "poke" => 3,
+        _ => 1,
+    }
+}
+
+/// 读取`$[`之后的部分, 返回类型和参数
+fn read_code(
+    chars: &mut Peekable<CharIndices<'_>>,
+    start: usize,
+) -> Result<(String, Vec<String>), CodeError> {
+    let mut kind = String::new();
+    let mut args = vec![];
+    let mut arg: Option<String> = None;
+
+    loop {
+        let (_, c) = chars
+            .next()
+            .ok_or_else(|| CodeError::new(start, "unclosed element"))?;
+
+        match (c, &mut arg) {
+            (']', _) => break,
+            (':', None) => arg = Some(String::new()),
+            (c, None) => kind.push(c),
+            ('\\', Some(arg)) => {
+                let (_, c) = chars
+                    .next()
+                    .ok_or_else(|| CodeError::new(start, "unclosed element"))?;
+                arg.push(c);
+            }
+            (',', Some(cur)) if args.len() + 1 < arity(&kind) => args.push(mem::take(cur)),
+            (c, Some(arg)) => arg.push(c),
+        }
+    }
+
+    args.extend(arg);
+    Ok((kind, args))
+}
+
+fn parse_arg<T>(args: &[String], index: usize, name: &str) -> Result<T, String>
+where
+    T: std::str::FromStr,
+    T::Err: Display,
+{
+    let arg = args
+        .get(index)
+        .ok_or_else(|| format!("missing argument: {name}"))?;
+
+    arg.parse()
+        .map_err(|e| format!("invalid argument {name}: {e}"))
+}
+
+fn element(kind: &str, mut args: Vec<String>) -> Result<MessageElement, String> {
+    let elem = match kind {
+        "at" => MessageElement::At(At {
+            target: parse_arg(&args, 0, "target")?,
+            display: args.get_mut(1).map(mem::take).unwrap_or_default(),
+        }),
+        "at_all" => MessageElement::AtAll,
+        "face" => MessageElement::Face(Face {
+            index: parse_arg(&args, 0, "index")?,
+            name: args.get_mut(1).map(mem::take).unwrap_or_default(),
+        }),
+        "dice" => {
+            let value = parse_arg(&args, 0, "value")?;
+            MessageElement::Dice(Dice::new(value).ok_or("dice value must be 1 to 6")?)
+        }
+        "finger_guessing" => MessageElement::FingerGuessing(parse_finger_guessing(
+            args.first().map(String::as_str).unwrap_or_default(),
+        )?),
+        "poke" if args.is_empty() => MessageElement::Poke(Poke::default()),
+        "poke" => MessageElement::Poke(Poke {
+            poke_type: parse_arg(&args, 1, "type")?,
+            id: parse_arg(&args, 2, "id")?,
+            name: mem::take(&mut args[0]),
+        }),
+        "light_app" => MessageElement::LightApp(args.pop().unwrap_or_default()),
+        "xml" => MessageElement::Xml {
+            service_id: parse_arg(&args, 0, "service id")?,
+            content: args.get_mut(1).map(mem::take).unwrap_or_default(),
+        },
+        or => {
+            let content = args.first().ok_or("missing argument: content")?;
+            json_element(or, content)?
+        }
+    };
+
+    Ok(elem)
+}
+
+fn json_element(kind: &str, content: &str) -> Result<MessageElement, String> {
+    let content: Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    let mut value = serde_json::Map::new();
+    value.insert("type".into(), Value::String(kind.into()));
+    value.insert("content".into(), content);
+
+    serde_json::from_value(Value::Object(value)).map_err(|e| e.to_string())
+}
+
+fn cq_escape(s: &str, param: bool, out: &mut String) {
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '[' => out.push_str("&#91;"),
+            ']' => out.push_str("&#93;"),
+            ',' if param => out.push_str("&#44;"),
+            or => out.push(or),
+        }
+    }
+}
+
+fn cq_unescape(s: &str) -> String {
+    s.replace("&#91;", "[")
+        .replace("&#93;", "]")
+        .replace("&#44;", ",")
+        .replace("&amp;", "&")
+}
+
+fn write_cq(kind: &str, params: &[(&str, &str)], s: &mut String) {
+    s.push_str("[CQ:");
+    s.push_str(kind);
+    for (key, value) in params {
+        let _ = write!(s, ",{key}=");
+        cq_escape(value, true, s);
+    }
+    s.push(']');
+}
+
+fn write_cq_code(elem: &MessageElement, s: &mut String) {
+    match elem {
+        MessageElement::Text(text) => cq_escape(text, false, s),
+        MessageElement::At(At { target, display }) => {
+            let target = target.to_string();
+            if display.is_empty() {
+                write_cq("at", &[("qq", &target)], s);
+            } else {
+                write_cq("at", &[("qq", &target), ("name", display)], s);
+            }
+        }
+        MessageElement::AtAll => write_cq("at", &[("qq", "all")], s),
+        MessageElement::Face(face) => write_cq("face", &[("id", &face.index.to_string())], s),
+        MessageElement::Dice(dice) => write_cq("dice", &[("value", &dice.value.to_string())], s),
+        MessageElement::FingerGuessing(value) => {
+            write_cq("rps", &[("value", finger_guessing_name(*value))], s)
+        }
+        MessageElement::Poke(Poke {
+            name,
+            poke_type,
+            id,
+        }) => write_cq(
+            "poke",
+            &[
+                ("type", &poke_type.to_string()),
+                ("id", &id.to_string()),
+                ("name", name),
+            ],
+            s,
+        ),
+        MessageElement::LightApp(content) => write_cq("json", &[("data", content)], s),
+        MessageElement::Xml {
+            service_id,
+            content,
+        } => write_cq(
+            "xml",
+            &[("data", content), ("resid", &service_id.to_string())],
+            s,
+        ),
+        or => match json_parts(or) {
+            Some((kind, content)) => write_cq(&kind, &[("data", &content)], s),
+            None => write_cq("unknown", &[], s),
+        },
+    }
+}
+
+fn cq_element(body: &str) -> Result<MessageElement, String> {
+    let mut parts = body.split(',');
+    let kind = parts.next().unwrap_or_default();
+
+    let mut params = vec![];
+    for part in parts {
+        let (key, value) = part
+            .split_once('=')
+            .ok_or_else(|| format!("invalid CQ code parameter: {part}"))?;
+        params.push((key, cq_unescape(value)));
+    }
+
+    let param = |key: &str| {
+        params
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v.as_str())
+    };
+    let parse = |key: &str| -> Result<Option<i64>, String> {
+        param(key)
+            .map(|v| {
+                v.parse()
+                    .map_err(|e| format!("invalid parameter {key}: {e}"))
+            })
+            .transpose()
+    };
+
+    let elem = match kind {
+        "at" => match param("qq") {
+            Some("all") => MessageElement::AtAll,
+            _ => MessageElement::At(At {
+                target: parse("qq")?.ok_or("missing parameter: qq")?,
+                display: param("name").unwrap_or_default().to_owned(),
+            }),
+        },
+        "face" => MessageElement::Face(Face {
+            index: parse("id")?.ok_or("missing parameter: id")? as i32,
+            name: String::new(),
+        }),
+        "dice" => {
+            let value = parse("value")?.unwrap_or_else(|| (rand::random::<u32>() % 6 + 1) as i64);
+            MessageElement::Dice(Dice::new(value as i32).ok_or("dice value must be 1 to 6")?)
+        }
+        "rps" => MessageElement::FingerGuessing(match param("value") {
+            Some(value) => parse_finger_guessing(value)?,
+            None => [
+                FingerGuessing::Rock,
+                FingerGuessing::Scissors,
+                FingerGuessing::Paper,
+            ][rand::random::<u32>() as usize % 3],
+        }),
+        "poke" => {
+            let default = Poke::default();
+            MessageElement::Poke(Poke {
+                name: param("name").map_or(default.name, str::to_owned),
+                poke_type: parse("type")?.map_or(default.poke_type, |t| t as i32),
+                id: parse("id")?.map_or(default.id, |id| id as i32),
+            })
+        }
+        "json" => MessageElement::LightApp(param("data").unwrap_or_default().to_owned()),
+        "xml" => MessageElement::Xml {
+            service_id: parse("resid")?.unwrap_or(1) as i32,
+            content: param("data").unwrap_or_default().to_owned(),
+        },
+        or => json_element(or, param("data").ok_or("missing parameter: data")?)?,
+    };
+
+    Ok(elem)
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use crate::message::at::At;
+    use crate::message::face::Face;
+    use crate::message::market_face::{Dice, FingerGuessing};
+    use crate::message::poke::Poke;
+    use crate::message::{MessageChain, MessageElement};
+
+    #[test]
+    fn parse() {
+        let chain =
+            MessageChain::from_code(r"你好$[at:114514,Atri] \$[face:1] $[face:14]$[at_all]")
+                .unwrap();
+
+        assert_eq!(chain.elements.len(), 5);
+        assert!(matches!(
+            &chain.elements[1],
+            MessageElement::At(At { target: 114514, display }) if display == "Atri"
+        ));
+        assert!(matches!(&chain.elements[2], MessageElement::Text(t) if t == " $[face:1] "));
+        assert!(matches!(
+            &chain.elements[3],
+            MessageElement::Face(Face { index: 14, .. })
+        ));
+        assert!(matches!(&chain.elements[4], MessageElement::AtAll));
+    }
+
+    #[test]
+    fn last_argument_keeps_comma() {
+        let chain = MessageChain::from_code(r#"$[light_app:{"a":1,"b":[2\]}]"#).unwrap();
+        assert!(matches!(
+            &chain.elements[0],
+            MessageElement::LightApp(s) if s == r#"{"a":1,"b":[2]}"#
+        ));
+    }
+
+    #[test]
+    fn errors() {
+        let err = MessageChain::from_code("abc$[at:114").unwrap_err();
+        assert_eq!(err.position, 3);
+        assert!(MessageChain::from_code("$[at:Atri]").is_err());
+        assert!(MessageChain::from_code("$[dice:7]").is_err());
+        assert!(MessageChain::from_code("$[nothing:1]").is_err());
+    }
+
+    #[test]
+    fn cq_code() {
+        let chain = MessageChain::from_cq_code(
+            "&#91;hi&#93;[CQ:at,qq=all][CQ:face,id=14][CQ:json,data=&#44;]",
+        )
+        .unwrap();
+
+        assert!(matches!(&chain.elements[0], MessageElement::Text(t) if t == "[hi]"));
+        assert!(matches!(&chain.elements[1], MessageElement::AtAll));
+        assert!(matches!(
+            &chain.elements[2],
+            MessageElement::Face(Face { index: 14, .. })
+        ));
+        assert!(matches!(&chain.elements[3], MessageElement::LightApp(s) if s == ","));
+        assert_eq!(
+            chain.to_cq_code(),
+            "&#91;hi&#93;[CQ:at,qq=all][CQ:face,id=14][CQ:json,data=&#44;]"
+        );
+    }
+
+    fn element() -> impl Strategy<Value = MessageElement> {
+        prop_oneof![
+            ".+".prop_map(MessageElement::Text),
+            (any::<i64>(), ".*")
+                .prop_map(|(target, display)| MessageElement::At(At { target, display })),
+            Just(MessageElement::AtAll),
+            (any::<i32>(), ".*")
+                .prop_map(|(index, name)| MessageElement::Face(Face { index, name })),
+            (1..=6).prop_map(|value| MessageElement::Dice(Dice { value })),
+            prop_oneof![
+                Just(FingerGuessing::Rock),
+                Just(FingerGuessing::Scissors),
+                Just(FingerGuessing::Paper),
+            ]
+            .prop_map(MessageElement::FingerGuessing),
+            (".*", any::<i32>(), any::<i32>()).prop_map(|(name, poke_type, id)| {
+                MessageElement::Poke(Poke::new(name, poke_type, id))
+            }),
+            ".*".prop_map(MessageElement::LightApp),
+            (any::<i32>(), ".*").prop_map(|(service_id, content)| MessageElement::Xml {
+                service_id,
+                content
+            }),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn text_round_trip(text in ".+") {
+            let chain = MessageChain::from(vec![MessageElement::Text(text.clone())]);
+            let parsed = MessageChain::from_code(&chain.to_code()).unwrap();
+
+            prop_assert_eq!(parsed.elements.len(), 1);
+            prop_assert!(matches!(&parsed.elements[0], MessageElement::Text(t) if *t == text));
+        }
+
+        #[test]
+        fn code_round_trip(elements in prop::collection::vec(element(), 0..8)) {
+            let chain = MessageChain::from(elements);
+            let code = chain.to_code();
+            let parsed = MessageChain::from_code(&code).unwrap();
+
+            prop_assert_eq!(parsed.to_code(), code);
+        }
+
+        #[test]
+        fn cq_round_trip(elements in prop::collection::vec(element(), 0..8)) {
+            let chain = MessageChain::from(elements);
+            let code = chain.to_cq_code();
+            let parsed = MessageChain::from_cq_code(&code).unwrap();
+
+            prop_assert_eq!(parsed.to_cq_code(), code);
+        }
+    }
+}
@@ -0,0 +1,347 @@
+//! 消息链的无损序列化
+//!
+//! json中未知元素以base64编码的原始protobuf数据保存, 类型为`raw`:
+//!
+//! ```json
+//! {"version":1,"meta":{...},"elements":[{"type":"raw","content":"ugEA"}]}
+//! ```
+//!
+//! 二进制格式为protobuf, 已知元素同样以其原始protobuf数据保存, 见[`MessageChain::to_bytes`]
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use prost::{DecodeError, Message};
+use ricq::msg::elem::RQElem;
+use ricq::msg::{MessageChain as RQMessageChain, MessageElem, PushElem};
+use ricq::structs::GroupAudio;
+use serde::{Deserialize, Serialize};
+
+use crate::message::meta::MessageMetadata;
+use crate::message::voice::{Voice, VoiceSource};
+use crate::message::{MessageChain, MessageElement};
+
+impl MessageChain {
+    /// 当前的序列化格式版本, 不含`version`字段的json视为版本0
+    pub const SERIALIZE_VERSION: u32 = 1;
+
+    /// 序列化为json, 包含未知元素
+    pub fn to_json_lossless(&self) -> Result<String, EncodeError> {
+        self.check_encodable()?;
+
+        Ok(serde_json::to_string(&Versioned {
+            version: Self::SERIALIZE_VERSION,
+            chain: self,
+        })
+        .expect("Serializing error"))
+    }
+
+    /// 编码为紧凑的二进制格式
+    pub fn to_bytes(&self) -> Result<Vec<u8>, EncodeError> {
+        self.check_encodable()?;
+
+        let mut meta_elems = vec![];
+        PushElem::push_to(
+            MessageChain {
+                meta: self.meta.clone(),
+                elements: vec![],
+                raw_elements: false,
+            },
+            &mut meta_elems,
+        );
+
+        let pb = pb::MessageChain {
+            version: Self::SERIALIZE_VERSION,
+            seqs: self.meta.seqs.clone(),
+            rands: self.meta.rands.clone(),
+            time: self.meta.time,
+            sender: self.meta.sender,
+            meta: meta_elems.into_iter().map(wrap).collect(),
+            elements: self.iter().cloned().map(pb::Element::from).collect(),
+        };
+
+        Ok(pb.encode_to_vec())
+    }
+
+    /// 检查所有未知元素均可还原为原始protobuf数据
+    fn check_encodable(&self) -> Result<(), EncodeError> {
+        for elem in self.iter() {
+            if let MessageElement::Unknown(rq) = elem {
+                if raw_elem(rq).is_none() {
+                    return Err(EncodeError {
+                        element: rq.to_string(),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 从[`MessageChain::to_bytes`]的结果解码
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let pb = pb::MessageChain::decode(bytes)?;
+        if pb.version > Self::SERIALIZE_VERSION {
+            return Err(DecodeError::new("unsupported message chain version"));
+        }
+
+        let meta_elems = pb.meta.into_iter().filter_map(|e| e.elem).collect();
+        let MessageChain { meta, .. } = MessageChain::from(RQMessageChain(meta_elems));
+
+        let elements = pb
+            .elements
+            .into_iter()
+            .filter_map(pb::Element::into_element)
+            .collect();
+
+        Ok(Self {
+            meta: MessageMetadata {
+                seqs: pb.seqs,
+                rands: pb.rands,
+                time: pb.time,
+                sender: pb.sender,
+                ..meta
+            },
+            elements,
+            raw_elements: true,
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub(super) struct Versioned<'a> {
+    pub version: u32,
+    #[serde(flatten)]
+    pub chain: &'a MessageChain,
+}
+
+#[derive(Deserialize)]
+pub(super) struct VersionedOwned {
+    #[serde(default)]
+    pub version: u32,
+    #[serde(flatten)]
+    pub chain: MessageChain,
+}
+
+fn wrap(elem: MessageElem) -> ricq::pb::msg::Elem {
+    ricq::pb::msg::Elem { elem: Some(elem) }
+}
+
+/// 未知元素的原始protobuf数据, 无法还原时返回`None`
+pub(super) fn raw_elem(rq: &RQElem) -> Option<MessageElem> {
+    let mut vec = vec![];
+    match rq {
+        RQElem::Other(elem) => return Some((**elem).clone()),
+        or => PushElem::push_to(MessageElement::from(or.clone()), &mut vec),
+    }
+
+    vec.into_iter().next()
+}
+
+/// 消息链中存在无法还原为原始protobuf数据的元素
+#[derive(Debug)]
+pub struct EncodeError {
+    element: String,
+}
+
+impl Display for EncodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cannot encode element: {}", self.element)
+    }
+}
+
+impl Error for EncodeError {}
+
+/// [`MessageElement::Unknown`]的序列化方式
+pub(super) mod raw {
+    use prost::Message;
+    use ricq::msg::elem::RQElem;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(rq: &RQElem, serializer: S) -> Result<S::Ok, S::Error> {
+        let elem = super::raw_elem(rq)
+            .ok_or_else(|| serde::ser::Error::custom(format!("cannot encode element: {rq}")))?;
+        let bytes = super::wrap(elem).encode_to_vec();
+        serializer.serialize_str(&base64::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<RQElem, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        let bytes = base64::decode(s).map_err(D::Error::custom)?;
+        let elem = ricq::pb::msg::Elem::decode(&*bytes).map_err(D::Error::custom)?;
+
+        elem.elem
+            .map(RQElem::from)
+            .ok_or_else(|| D::Error::custom("empty raw element"))
+    }
+}
+
+mod pb {
+    use super::*;
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct MessageChain {
+        #[prost(uint32, tag = "1")]
+        pub version: u32,
+        #[prost(int32, repeated, tag = "2")]
+        pub seqs: Vec<i32>,
+        #[prost(int32, repeated, tag = "3")]
+        pub rands: Vec<i32>,
+        #[prost(int32, tag = "4")]
+        pub time: i32,
+        #[prost(int64, tag = "5")]
+        pub sender: i64,
+        /// 回复和匿名信息
+        #[prost(message, repeated, tag = "6")]
+        pub meta: Vec<ricq::pb::msg::Elem>,
+        #[prost(message, repeated, tag = "7")]
+        pub elements: Vec<Element>,
+    }
+
+    /// 一个消息元素, `elem`和`ptt`二选一
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Element {
+        /// 元素的首个protobuf元素, 其余部分(如旧版客户端的提示文本)可由其恢复
+        #[prost(message, optional, tag = "1")]
+        pub elem: Option<ricq::pb::msg::Elem>,
+        #[prost(message, optional, tag = "2")]
+        pub ptt: Option<ricq::pb::msg::Ptt>,
+        /// 语音来源, 正数为群号, 负数为好友号的相反数
+        #[prost(int64, tag = "3")]
+        pub voice_source: i64,
+    }
+
+    impl From<MessageElement> for Element {
+        fn from(elem: MessageElement) -> Self {
+            let mut element = Element {
+                elem: None,
+                ptt: None,
+                voice_source: 0,
+            };
+
+            match elem {
+                MessageElement::Voice(voice) => {
+                    element.voice_source = match voice.source() {
+                        VoiceSource::Group(id) => id,
+                        VoiceSource::Friend(id) => -id,
+                    };
+                    element.ptt = Some(voice.into_group_audio().0);
+                }
+                MessageElement::Unknown(rq) => element.elem = raw_elem(&rq).map(wrap),
+                or => {
+                    let mut vec = vec![];
+                    PushElem::push_to(or, &mut vec);
+                    element.elem = vec.into_iter().next().map(wrap);
+                }
+            }
+
+            element
+        }
+    }
+
+    impl Element {
+        pub fn into_element(self) -> Option<MessageElement> {
+            if let Some(ptt) = self.ptt {
+                let voice = if self.voice_source >= 0 {
+                    Voice::group(self.voice_source, GroupAudio(ptt))
+                } else {
+                    Voice::friend(-self.voice_source, ricq::structs::FriendAudio(ptt))
+                };
+
+                return Some(MessageElement::Voice(voice));
+            }
+
+            let rq = RQElem::from(self.elem?.elem?);
+            Some(MessageElement::from(rq))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ricq::msg::elem::RQElem;
+    use ricq::msg::{MessageChain as RQMessageChain, MessageElem};
+    use ricq::pb::msg::GeneralFlags;
+
+    use crate::message::{MessageChain, MessageElement};
+
+    fn chain() -> MessageChain {
+        let mut chain = MessageChain::from(vec![
+            MessageElement::Text("114514".into()),
+            MessageElement::Unknown(RQElem::Other(Box::new(MessageElem::GeneralFlags(
+                GeneralFlags {
+                    pb_reserve: Some(vec![1, 1, 4]),
+                    ..Default::default()
+                },
+            )))),
+        ]);
+        chain.metadata_mut().seqs = vec![1919];
+        chain.metadata_mut().sender = 810;
+        chain
+    }
+
+    fn assert_unknown_kept(chain: &MessageChain) {
+        assert_eq!(chain.elements.len(), 2);
+        let MessageElement::Unknown(RQElem::Other(elem)) = &chain.elements[1] else {
+            panic!("unknown element lost");
+        };
+        let MessageElem::GeneralFlags(flags) = &**elem else {
+            panic!("wrong element");
+        };
+        assert_eq!(flags.pb_reserve.as_deref(), Some(&[1, 1, 4][..]));
+    }
+
+    #[test]
+    fn json_lossless() {
+        let json = chain().to_json_lossless().unwrap();
+        assert!(json.contains(r#""version":1"#));
+        assert!(json.contains(r#""type":"raw""#));
+
+        let chain = MessageChain::from_json(&json).unwrap();
+        assert_unknown_kept(&chain);
+        assert_eq!(chain.metadata().sender, 810);
+    }
+
+    #[test]
+    fn json_legacy() {
+        let chain = chain();
+        assert_eq!(
+            MessageChain::from_json(&chain.to_json())
+                .unwrap()
+                .elements
+                .len(),
+            1
+        );
+
+        let legacy = r#"{"meta":{"seqs":[],"rands":[],"time":0,"sender":0,"anonymous":null,"reply":null},"elements":[{"type":"text","content":"hi"}]}"#;
+        assert_eq!(MessageChain::from_json(legacy).unwrap().to_string(), "hi");
+    }
+
+    #[test]
+    fn json_future_version() {
+        let json = chain()
+            .to_json_lossless()
+            .unwrap()
+            .replace(r#""version":1"#, r#""version":99"#);
+        assert!(MessageChain::from_json(&json).is_err());
+    }
+
+    #[test]
+    fn bytes() {
+        let chain = MessageChain::from_bytes(&chain().to_bytes().unwrap()).unwrap();
+        assert_unknown_kept(&chain);
+        assert_eq!(chain.metadata().seqs, vec![1919]);
+        assert_eq!(chain.to_string().get(..6), Some("114514"));
+    }
+
+    #[test]
+    fn send_raw_elements() {
+        let rq = RQMessageChain::from(chain());
+        assert_eq!(rq.0.len(), 1);
+
+        let decoded = MessageChain::from_bytes(&chain().to_bytes().unwrap()).unwrap();
+        let rq = RQMessageChain::from(decoded);
+        assert!(matches!(rq.0.last(), Some(MessageElem::GeneralFlags(_))));
+    }
+}
@@ -1,5 +1,16 @@
+use crate::config::service_config_dir_path;
+use crate::message::MessageElement;
 use ricq::msg::{MessageElem, PushElem};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use tracing::error;
+
+/// 内置的表情表
+pub const FACE_TABLE: &str = include_str!("../../default_config/faces.toml");
+
+/// 用户的表情表文件名, 位于服务配置目录
+pub const FACE_TABLE_FILE: &str = "faces.toml";
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Face {
@@ -7,6 +18,117 @@ pub struct Face {
     pub name: String,
 }
 
+impl Face {
+    /// 根据表情名或别名查找表情, 可带有前缀`/`, 如`/微笑`
+    pub fn from_name(name: &str) -> Option<Self> {
+        face_table().find_name(name).map(FaceInfo::to_face)
+    }
+
+    pub fn from_index(index: i32) -> Option<Self> {
+        face_table().get(index).map(FaceInfo::to_face)
+    }
+}
+
+/// 表情表中的条目
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FaceInfo {
+    pub index: i32,
+    pub name: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+impl FaceInfo {
+    fn to_face(&self) -> Face {
+        Face {
+            index: self.index,
+            name: self.name.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct FaceTableFile {
+    #[serde(default)]
+    faces: Vec<FaceInfo>,
+}
+
+/// 表情表, 支持按序号, 表情名与别名查找
+#[derive(Default)]
+pub struct FaceTable {
+    faces: HashMap<i32, FaceInfo>,
+    names: HashMap<String, i32>,
+}
+
+impl FaceTable {
+    /// 从toml加载, 格式见`default_config/faces.toml`
+    pub fn from_toml(s: &str) -> Result<Self, toml::de::Error> {
+        let mut table = Self::default();
+        table.extend_from_toml(s)?;
+        Ok(table)
+    }
+
+    /// 添加toml中的条目, 同序号的条目将被覆盖
+    pub fn extend_from_toml(&mut self, s: &str) -> Result<(), toml::de::Error> {
+        let file: FaceTableFile = toml::from_str(s)?;
+        for info in file.faces {
+            self.insert(info);
+        }
+
+        Ok(())
+    }
+
+    pub fn insert(&mut self, info: FaceInfo) {
+        if let Some(old) = self.faces.remove(&info.index) {
+            self.names.retain(|_, index| *index != old.index);
+        }
+
+        for name in std::iter::once(&info.name).chain(&info.aliases) {
+            self.names.insert(name.to_lowercase(), info.index);
+        }
+        self.faces.insert(info.index, info);
+    }
+
+    pub fn get(&self, index: i32) -> Option<&FaceInfo> {
+        self.faces.get(&index)
+    }
+
+    /// 根据表情名或别名查找, 忽略大小写与前缀`/`
+    pub fn find_name(&self, name: &str) -> Option<&FaceInfo> {
+        let name = name.strip_prefix('/').unwrap_or(name).to_lowercase();
+        self.names.get(&name).and_then(|index| self.get(*index))
+    }
+
+    pub fn len(&self) -> usize {
+        self.faces.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.faces.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &FaceInfo> {
+        self.faces.values()
+    }
+}
+
+/// 全局的表情表, 由内置表情表与`service/faces.toml`合并而成
+pub fn face_table() -> &'static FaceTable {
+    static TABLE: OnceLock<FaceTable> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = FaceTable::from_toml(FACE_TABLE).expect("Cannot parse face table");
+
+        let path = service_config_dir_path().join(FACE_TABLE_FILE);
+        if let Ok(s) = std::fs::read_to_string(&path) {
+            if let Err(e) = table.extend_from_toml(&s) {
+                error!("读取表情表({:?})失败: {}", path, e);
+            }
+        }
+
+        table
+    })
+}
+
 impl From<ricq::msg::elem::Face> for Face {
     fn from(ricq::msg::elem::Face { index, name }: ricq::msg::elem::Face) -> Self {
         Self { index, name }
@@ -26,6 +148,12 @@ impl PushElem for Face {
     }
 }
 
+impl From<Face> for MessageElement {
+    fn from(face: Face) -> Self {
+        Self::Face(face)
+    }
+}
+
 mod ffi {
     use crate::message::face::Face;
     use atri_ffi::ffi::ForFFI;
@@ -51,3 +179,37 @@ mod ffi {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Face, FaceTable, FACE_TABLE};
+
+    #[test]
+    fn lookup() {
+        let face = Face::from_name("微笑").unwrap();
+        assert_eq!(face.index, 14);
+        assert_eq!(Face::from_index(14).unwrap().name, "微笑");
+        assert_eq!(Face::from_name("/wx").unwrap().index, 14);
+        assert_eq!(Face::from_name("DOGE").unwrap().index, 179);
+        assert!(Face::from_name("不存在的表情").is_none());
+    }
+
+    #[test]
+    fn extend() {
+        let mut table = FaceTable::from_toml(FACE_TABLE).unwrap();
+        let len = table.len();
+        table
+            .extend_from_toml(
+                r#"faces = [
+                    { index = 14, name = "笑", aliases = ["smile"] },
+                    { index = 999, name = "新表情" },
+                ]"#,
+            )
+            .unwrap();
+
+        assert_eq!(table.len(), len + 1);
+        assert_eq!(table.find_name("smile").unwrap().name, "笑");
+        assert!(table.find_name("微笑").is_none());
+        assert_eq!(table.find_name("新表情").unwrap().index, 999);
+    }
+}
@@ -1,4 +1,5 @@
 use super::MessageChain;
+use crate::error::{AtriError, AtriResult};
 use crate::message::at::At;
 use crate::message::face::Face;
 use crate::message::meta::{Anonymous, MessageMetadata, Reply};
@@ -11,7 +12,29 @@ use atri_ffi::message::{
     FFIAt, FFIMessageChain, FFIMessageElement, MessageElementFlag, MessageElementUnion,
 };
 use atri_ffi::{ManagedCloneable, RustString, RustVec};
+use ricq::msg::elem::RQElem;
 use std::mem::{ManuallyDrop, MaybeUninit};
+use tracing::error;
+
+impl MessageChain {
+    /// 转换插件传入的消息, 包含无法识别的元素时返回错误
+    pub fn try_from_ffi(ffi: FFIMessageChain) -> AtriResult<Self> {
+        let meta = MessageMetadata::from_ffi(ffi.meta);
+
+        let elements = ffi
+            .inner
+            .into_vec()
+            .into_iter()
+            .map(MessageElement::try_from_ffi)
+            .collect::<AtriResult<_>>()?;
+
+        Ok(Self {
+            meta,
+            elements,
+            raw_elements: false,
+        })
+    }
+}
 
 impl ForFFI for MessageChain {
     type FFIValue = FFIMessageChain;
@@ -28,15 +51,17 @@ impl ForFFI for MessageChain {
         FFIMessageChain { meta, inner: raw }
     }
 
+    /// 忽略无法识别的元素, 需要报告错误时使用[`MessageChain::try_from_ffi`]
     fn from_ffi(ffi: Self::FFIValue) -> Self {
         let meta = MessageMetadata::from_ffi(ffi.meta);
 
         let v = ffi.inner.into_vec();
-        let values: Vec<MessageElement> = v.into_iter().map(MessageElement::from_ffi).collect();
+        let values: Vec<MessageElement> = from_ffi_elements(v);
 
         Self {
             meta,
             elements: values,
+            raw_elements: false,
         }
     }
 }
@@ -79,6 +104,22 @@ impl ForFFI for MessageElement {
                     face: ManuallyDrop::new(face.into_ffi()),
                 },
             },
+            // 转发卡片以原始的卡片消息传递, 可通过`forward_get_res_id`读取资源id
+            MessageElement::Forward(card) => {
+                MessageElement::Unknown(RQElem::RichMsg(card.to_rich_msg())).into_ffi()
+            }
+            // atri_ffi的`MessageElementFlag`没有以下元素:
+            // 语音以`[语音]`文本传递, 可通过`group_message_event_get_voice`等函数获取;
+            // 其余元素以原始的消息元素传递, 传回时还原, 可通过`dice_get_value`等函数读取
+            elem @ (MessageElement::Voice(_)
+            | MessageElement::Video(_)
+            | MessageElement::FlashImage(_)
+            | MessageElement::MarketFace(_)
+            | MessageElement::Dice(_)
+            | MessageElement::FingerGuessing(_)
+            | MessageElement::Poke(_)
+            | MessageElement::LightApp(_)
+            | MessageElement::Xml { .. }) => MessageElement::Unknown(RQElem::from(elem)).into_ffi(),
             MessageElement::Unknown(rq) => FFIMessageElement {
                 t: MessageElementFlag::Unknown.value(),
                 union: MessageElementUnion {
@@ -88,11 +129,23 @@ impl ForFFI for MessageElement {
         }
     }
 
+    /// 类型标识无法识别时返回空文本, 需要报告错误时使用[`MessageElement::try_from_ffi`]
     fn from_ffi(value: Self::FFIValue) -> Self {
-        unsafe {
-            match MessageElementFlag::try_from(value.t)
-                .unwrap_or_else(|e| panic!("Unknown message value flag: {e}"))
-            {
+        Self::try_from_ffi(value).unwrap_or_else(|e| {
+            error!("{}", e);
+            MessageElement::Text(String::new())
+        })
+    }
+}
+
+impl MessageElement {
+    /// 转换插件传入的元素, 类型标识无法识别时返回错误
+    pub fn try_from_ffi(value: FFIMessageElement) -> AtriResult<Self> {
+        let flag = MessageElementFlag::try_from(value.t)
+            .map_err(|_| AtriError::InvalidMessage("unknown message element flag"))?;
+
+        let elem = unsafe {
+            match flag {
                 MessageElementFlag::Text => {
                     MessageElement::Text(ManuallyDrop::into_inner(value.union.text).into())
                 }
@@ -110,14 +163,28 @@ impl ForFFI for MessageElement {
                 MessageElementFlag::Face => {
                     MessageElement::Face(Face::from_ffi(ManuallyDrop::into_inner(value.union.face)))
                 }
-                MessageElementFlag::Unknown => MessageElement::Unknown(
-                    ManuallyDrop::into_inner(value.union.unknown).into_value(),
+                MessageElementFlag::Unknown => MessageElement::from(
+                    ManuallyDrop::into_inner(value.union.unknown).into_value::<RQElem>(),
                 ),
             }
-        }
+        };
+
+        Ok(elem)
     }
 }
 
+/// 转换插件传入的元素, 忽略无法识别的元素
+fn from_ffi_elements(elements: Vec<FFIMessageElement>) -> Vec<MessageElement> {
+    elements
+        .into_iter()
+        .filter_map(|e| {
+            MessageElement::try_from_ffi(e)
+                .map_err(|e| error!("忽略插件传入的消息元素: {}", e))
+                .ok()
+        })
+        .collect()
+}
+
 impl ForFFI for Reply {
     type FFIValue = FFIReply;
 
@@ -146,7 +213,7 @@ impl ForFFI for Reply {
         }: Self::FFIValue,
     ) -> Self {
         let elems = elements.into_vec();
-        let values: Vec<MessageElement> = elems.into_iter().map(MessageElement::from_ffi).collect();
+        let values: Vec<MessageElement> = from_ffi_elements(elems);
 
         Self {
             reply_seq,
@@ -1,5 +1,12 @@
-use crate::message::MessageChain;
+use crate::error::AtriResult;
+use crate::message::{xml_escape, MessageChain, MessageElement};
+use crate::Client;
+use regex::Regex;
+use ricq::msg::elem::RichMsg;
+use ricq::msg::{MessageElem, PushElem};
 use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::sync::OnceLock;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ForwardMessage(Vec<ForwardNode>);
@@ -150,6 +157,167 @@ impl From<ForwardMessage> for Vec<ricq::structs::ForwardMessage> {
     }
 }
 
+/// 转发消息卡片, 收到的合并转发消息以此形式出现, 可通过[`ForwardCard::download`]展开
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ForwardCard {
+    /// 转发消息的资源id
+    pub res_id: String,
+    /// 消息总数
+    #[serde(default)]
+    pub total: usize,
+    /// 卡片中的预览
+    #[serde(default)]
+    pub preview: Vec<String>,
+}
+
+impl ForwardCard {
+    /// 转发消息卡片的服务id
+    pub const SERVICE_ID: i32 = 35;
+
+    /// 最多预览的消息数
+    const PREVIEW_LEN: usize = 4;
+
+    pub(crate) fn new(res_id: String, forward: &ForwardMessage) -> Self {
+        let preview = forward
+            .iter()
+            .take(Self::PREVIEW_LEN)
+            .map(|node| match node {
+                ForwardNode::NormalMessage { info, chain } => {
+                    format!("{}: {}", info.sender_name, chain.to_string())
+                }
+                ForwardNode::ForwardMessage { info, .. } => {
+                    format!("{}: [聊天记录]", info.sender_name)
+                }
+            })
+            .collect();
+
+        Self {
+            res_id,
+            total: forward.len(),
+            preview,
+        }
+    }
+
+    /// 下载并展开转发消息
+    pub async fn download(&self, client: &Client) -> AtriResult<ForwardMessage> {
+        let msgs = client
+            .request_client()
+            .download_msgs(self.res_id.clone())
+            .await?;
+
+        Ok(ForwardMessage::from(msgs))
+    }
+
+    /// 从卡片消息中解析, 不是转发消息时返回`None`
+    pub fn from_rich_msg(rich: &RichMsg) -> Option<Self> {
+        fn res_id_regex() -> &'static Regex {
+            static REGEX: OnceLock<Regex> = OnceLock::new();
+            REGEX.get_or_init(|| Regex::new(r#"m_resid="([^"]+)""#).expect("Cannot parse regex"))
+        }
+
+        fn total_regex() -> &'static Regex {
+            static REGEX: OnceLock<Regex> = OnceLock::new();
+            REGEX.get_or_init(|| Regex::new(r#"tSum="(\d+)""#).expect("Cannot parse regex"))
+        }
+
+        fn title_regex() -> &'static Regex {
+            static REGEX: OnceLock<Regex> = OnceLock::new();
+            REGEX.get_or_init(|| {
+                Regex::new(r#"<title size="26"[^>]*>([^<]*)</title>"#).expect("Cannot parse regex")
+            })
+        }
+
+        if rich.service_id != Self::SERVICE_ID {
+            return None;
+        }
+
+        let xml = &rich.template1;
+        let res_id = res_id_regex().captures(xml)?.get(1)?.as_str();
+        let total = total_regex()
+            .captures(xml)
+            .and_then(|c| c.get(1)?.as_str().parse().ok())
+            .unwrap_or_default();
+        let preview = title_regex()
+            .captures_iter(xml)
+            .filter_map(|c| Some(xml_unescape(c.get(1)?.as_str())))
+            .collect();
+
+        Some(Self {
+            res_id: xml_unescape(res_id),
+            total,
+            preview,
+        })
+    }
+
+    /// 生成卡片消息
+    pub fn to_rich_msg(&self) -> RichMsg {
+        let mut preview = String::new();
+        for line in &self.preview {
+            let _ = write!(
+                preview,
+                r##"<title size="26" color="#777777" maxLines="2" lineSpace="12">{}</title>"##,
+                xml_escape(line)
+            );
+        }
+
+        let file_name = std::time::UNIX_EPOCH
+            .elapsed()
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+
+        let template = format!(
+            r##"<?xml version='1.0' encoding='UTF-8' standalone='yes' ?><msg serviceID="35" templateID="1" action="viewMultiMsg" brief="[聊天记录]" m_resid="{res_id}" m_fileName="{file_name}" tSum="{total}" sourceMsgId="0" url="" flag="3" adverSign="0" multiMsgFlag="0"><item layout="1" advertiser_id="0" aid="0"><title size="34" maxLines="2" lineSpace="12">聊天记录</title>{preview}<hr hidden="false" style="0" /><summary size="26" color="#777777">查看{total}条转发消息</summary></item><source name="聊天记录" icon="" action="" appid="-1" /></msg>"##,
+            res_id = xml_escape(&self.res_id),
+            total = self.total,
+        );
+
+        RichMsg {
+            service_id: Self::SERVICE_ID,
+            template1: template,
+        }
+    }
+}
+
+impl PushElem for ForwardCard {
+    fn push_to(elem: Self, vec: &mut Vec<MessageElem>) {
+        PushElem::push_to(elem.to_rich_msg(), vec);
+        vec.push(MessageElem::GeneralFlags(ricq::pb::msg::GeneralFlags {
+            pendant_id: Some(0),
+            pb_reserve: Some(vec![0x78, 0x00, 0xF8, 0x01, 0x00, 0xC8, 0x02, 0x00]),
+            ..Default::default()
+        }));
+    }
+}
+
+impl From<ForwardCard> for MessageElement {
+    fn from(card: ForwardCard) -> Self {
+        Self::Forward(card)
+    }
+}
+
+/// 上传转发消息, 返回可发送的卡片
+pub(crate) async fn upload(
+    client: &Client,
+    target: i64,
+    forward: ForwardMessage,
+) -> AtriResult<ForwardCard> {
+    let preview = ForwardCard::new(String::new(), &forward);
+    let res_id = client
+        .request_client()
+        .upload_msgs(target, forward.into(), false)
+        .await?;
+
+    Ok(ForwardCard { res_id, ..preview })
+}
+
+fn xml_unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
 mod ffi {
     use super::{ForwardMessage, ForwardNode, ForwardNodeInfo};
     use crate::message::MessageChain;
@@ -256,3 +424,26 @@ mod ffi {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ForwardCard, ForwardMessage};
+    use crate::message::MessageChain;
+
+    #[test]
+    fn card_round_trip() {
+        let forward = ForwardMessage::from_message_chain(
+            114514,
+            "<atri>".into(),
+            0,
+            MessageChain::from(vec!["Hello & bye".to_owned().into()]),
+        );
+
+        let card = ForwardCard::new("res&id".into(), &forward);
+        assert_eq!(card.total, 1);
+        assert_eq!(card.preview, vec!["<atri>: Hello & bye".to_owned()]);
+
+        let parsed = ForwardCard::from_rich_msg(&card.to_rich_msg()).unwrap();
+        assert_eq!(parsed, card);
+    }
+}
@@ -1,7 +1,16 @@
-use crate::message::MessageElement;
+use crate::client::image_cache::ImageCache;
+use crate::client::web::http_client;
+use crate::contact::friend::Friend;
+use crate::contact::group::Group;
+use crate::error::AtriResult;
+use crate::message::{hex, MessageChain, MessageElement};
+use ricq::command::img_store::GroupImageStoreResp;
+use ricq::command::long_conn::OffPicUpResp;
 use ricq::msg::elem::{FlashImage, FriendImage, GroupImage};
 use ricq::msg::{MessageElem, PushElem};
+use ricq::structs::ImageInfo;
 use serde::{Deserialize, Serialize};
+use tracing::warn;
 
 #[derive(Serialize, Deserialize, Clone)]
 pub enum Image {
@@ -24,12 +33,193 @@ impl Image {
         }
     }
 
+    /// 作为闪照发送
+    pub fn into_flash(self) -> MessageElement {
+        MessageElement::FlashImage(self)
+    }
+
     pub fn url(&self) -> String {
         match self {
             Self::Group(g) => g.url(),
             Self::Friend(f) => f.url(),
         }
     }
+
+    pub fn md5(&self) -> &[u8] {
+        match self {
+            Self::Group(g) => &g.md5,
+            Self::Friend(f) => &f.md5,
+        }
+    }
+
+    /// 转换为可发送至群的图片
+    ///
+    /// 依次尝试:
+    /// 1. 使用[`ImageCache`]中相同md5的群图片
+    /// 2. 以md5与大小向服务器查询, 服务器已存在相同图片时直接使用
+    /// 3. 下载后重新上传
+    pub async fn into_group_image(self, group: &Group) -> AtriResult<Self> {
+        if let Self::Group(_) = self {
+            return Ok(self);
+        }
+
+        let client = group.client();
+        if let Some(img) = self.cached_for_group(client.image_cache()) {
+            return Ok(img);
+        }
+
+        match self.rederive_group(group).await {
+            Ok(Some(img)) => {
+                if let Err(e) = client
+                    .image_cache()
+                    .insert_group(hex(self.md5()), img.clone())
+                    .await
+                {
+                    warn!("{}保存图片缓存失败: {}", client, e);
+                }
+
+                return Ok(Self::Group(img));
+            }
+            Ok(None) => {}
+            Err(e) => warn!("查询群图片失败, 将重新上传: {}", e),
+        }
+
+        let bytes = self.download().await?;
+        group.upload_image(bytes).await
+    }
+
+    /// 转换为可发送至好友的图片, 同[`Image::into_group_image`]
+    pub async fn into_friend_image(self, friend: &Friend) -> AtriResult<Self> {
+        if let Self::Friend(_) = self {
+            return Ok(self);
+        }
+
+        let client = friend.client();
+        if let Some(img) = self.cached_for_friend(client.image_cache()) {
+            return Ok(img);
+        }
+
+        match self.rederive_friend(friend).await {
+            Ok(Some(img)) => {
+                if let Err(e) = client
+                    .image_cache()
+                    .insert_friend(hex(self.md5()), img.clone())
+                    .await
+                {
+                    warn!("{}保存图片缓存失败: {}", client, e);
+                }
+
+                return Ok(Self::Friend(img));
+            }
+            Ok(None) => {}
+            Err(e) => warn!("查询好友图片失败, 将重新上传: {}", e),
+        }
+
+        let bytes = self.download().await?;
+        friend.upload_image(bytes).await
+    }
+
+    fn cached_for_group(&self, cache: &ImageCache) -> Option<Self> {
+        cache.group(&hex(self.md5())).map(Self::Group)
+    }
+
+    fn cached_for_friend(&self, cache: &ImageCache) -> Option<Self> {
+        cache.friend(&hex(self.md5())).map(Self::Friend)
+    }
+
+    /// 上传时使用的图片信息, 由md5与大小等得出, 无需图片数据
+    fn image_info(&self) -> ImageInfo {
+        let (size, width, height, image_type) = match self {
+            Self::Group(g) => (g.size, g.width, g.height, g.image_type),
+            Self::Friend(f) => (f.size, f.width, f.height, f.image_type),
+        };
+
+        ImageInfo {
+            md5: self.md5().to_vec(),
+            width,
+            height,
+            image_type,
+            size,
+            filename: self.id().to_owned(),
+        }
+    }
+
+    /// 服务器已存在相同的图片时无需下载, 不存在时返回`None`
+    async fn rederive_group(&self, group: &Group) -> AtriResult<Option<GroupImage>> {
+        let info = self.image_info();
+        let store = group
+            .client()
+            .request_client()
+            .get_group_image_store(group.id(), &info)
+            .await?;
+
+        let GroupImageStoreResp::Exist { file_id, addrs } = store else {
+            return Ok(None);
+        };
+
+        Ok(addrs
+            .first()
+            .cloned()
+            .map(|addr| info.into_group_image(file_id, addr, Vec::new())))
+    }
+
+    /// 同[`Image::rederive_group`]
+    async fn rederive_friend(&self, friend: &Friend) -> AtriResult<Option<FriendImage>> {
+        let info = self.image_info();
+        let store = friend
+            .client()
+            .request_client()
+            .get_off_pic_store(friend.id(), &info)
+            .await?;
+
+        let OffPicUpResp::Exist(res_id) = store else {
+            return Ok(None);
+        };
+
+        Ok(Some(info.into_friend_image(res_id)))
+    }
+
+    async fn download(&self) -> AtriResult<Vec<u8>> {
+        let bytes = http_client()
+            .get(self.url())
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+
+        Ok(bytes.to_vec())
+    }
+}
+
+/// 将消息中的好友图片转换为群图片, 用于发送至群
+///
+/// 转换失败的图片保持原样发送, 不影响消息中的其他元素
+pub(crate) async fn convert_for_group(chain: &mut MessageChain, group: &Group) {
+    for elem in &mut chain.elements {
+        if let MessageElement::Image(img @ Image::Friend(_))
+        | MessageElement::FlashImage(img @ Image::Friend(_)) = elem
+        {
+            match img.clone().into_group_image(group).await {
+                Ok(converted) => *img = converted,
+                Err(e) => warn!("转换图片{}失败, 将以原图片发送: {}", img.id(), e),
+            }
+        }
+    }
+}
+
+/// 将消息中的群图片转换为好友图片, 用于发送至好友, 同[`convert_for_group`]
+pub(crate) async fn convert_for_friend(chain: &mut MessageChain, friend: &Friend) {
+    for elem in &mut chain.elements {
+        if let MessageElement::Image(img @ Image::Group(_))
+        | MessageElement::FlashImage(img @ Image::Group(_)) = elem
+        {
+            match img.clone().into_friend_image(friend).await {
+                Ok(converted) => *img = converted,
+                Err(e) => warn!("转换图片{}失败, 将以原图片发送: {}", img.id(), e),
+            }
+        }
+    }
 }
 
 impl PushElem for Image {
@@ -53,8 +243,69 @@ impl From<FriendImage> for Image {
     }
 }
 
+impl From<FlashImage> for Image {
+    fn from(flash: FlashImage) -> Self {
+        match flash {
+            FlashImage::GroupImage(g) => Self::Group(g),
+            FlashImage::FriendImage(f) => Self::Friend(f),
+        }
+    }
+}
+
 impl From<Image> for MessageElement {
     fn from(img: Image) -> Self {
         Self::Image(img)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ricq::msg::elem::{FriendImage, GroupImage};
+
+    use super::Image;
+    use crate::client::image_cache::ImageCache;
+    use crate::message::hex;
+
+    fn block_on<F: std::future::Future>(f: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(f)
+    }
+
+    #[test]
+    fn cache_hit() {
+        block_on(async {
+            let dir = std::env::temp_dir().join(format!("atri_image_test_{}", std::process::id()));
+            std::fs::create_dir_all(&dir).unwrap();
+            let cache = ImageCache::load(&dir);
+
+            let md5 = vec![1, 2, 3, 4];
+            let group = GroupImage {
+                md5: md5.clone(),
+                file_id: 114514,
+                ..Default::default()
+            };
+            cache.insert_group(hex(&md5), group).await.unwrap();
+
+            let friend = Image::Friend(FriendImage {
+                md5: md5.clone(),
+                ..Default::default()
+            });
+            match friend.cached_for_group(&cache) {
+                Some(Image::Group(img)) => assert_eq!(img.file_id, 114514),
+                _ => panic!("cached group image not used"),
+            }
+            assert!(friend.cached_for_friend(&cache).is_none());
+
+            let other = Image::Friend(FriendImage {
+                md5: vec![5, 6],
+                ..Default::default()
+            });
+            assert!(other.cached_for_group(&cache).is_none());
+
+            let _ = std::fs::remove_dir_all(&dir);
+        });
+    }
+}
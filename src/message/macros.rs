@@ -1 +1,37 @@
+/// 构造消息链, 每个元素需实现`Into<MessageElement>`
+///
+/// ```ignore
+/// let chain = msg!["你好, ", member.at(), MessageElement::AtAll, image];
+/// ```
+#[macro_export]
+macro_rules! msg {
+    ($($elem:expr),* $(,)?) => {
+        {
+            let elements: ::std::vec::Vec<$crate::message::MessageElement> =
+                ::std::vec![$($crate::message::MessageElement::from($elem)),*];
+            $crate::message::MessageChain::from(elements)
+        }
+    };
+}
 
+#[cfg(test)]
+mod tests {
+    use crate::message::at::At;
+    use crate::message::MessageElement;
+
+    #[test]
+    fn msg() {
+        let chain = msg![
+            "Hello ",
+            At {
+                target: 114514,
+                display: String::new(),
+            },
+            MessageElement::AtAll,
+        ];
+
+        assert_eq!(chain.len(), 3);
+        assert!(chain.contains_at(114514));
+        assert!(msg![].is_empty());
+    }
+}
@@ -0,0 +1,206 @@
+use std::fmt;
+
+use ricq::msg::{MessageElem, PushElem};
+use serde::{Deserialize, Serialize};
+
+use crate::message::{hex, unhex, MessageElement};
+
+/// 商城表情
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MarketFace {
+    pub name: String,
+    /// 十六进制编码的表情id
+    pub face_id: String,
+    pub tab_id: u32,
+    pub item_type: u32,
+    pub sub_type: u32,
+    pub media_type: u32,
+    /// 十六进制编码的密钥
+    pub encrypt_key: String,
+    pub magic_value: String,
+}
+
+impl From<ricq::msg::elem::MarketFace> for MarketFace {
+    fn from(
+        ricq::msg::elem::MarketFace {
+            name,
+            face_id,
+            tab_id,
+            item_type,
+            sub_type,
+            media_type,
+            encrypt_key,
+            magic_value,
+        }: ricq::msg::elem::MarketFace,
+    ) -> Self {
+        Self {
+            name,
+            face_id: hex(&face_id),
+            tab_id,
+            item_type,
+            sub_type,
+            media_type,
+            encrypt_key: hex(&encrypt_key),
+            magic_value,
+        }
+    }
+}
+
+impl From<MarketFace> for ricq::msg::elem::MarketFace {
+    fn from(
+        MarketFace {
+            name,
+            face_id,
+            tab_id,
+            item_type,
+            sub_type,
+            media_type,
+            encrypt_key,
+            magic_value,
+        }: MarketFace,
+    ) -> Self {
+        Self {
+            name,
+            face_id: unhex(&face_id),
+            tab_id,
+            item_type,
+            sub_type,
+            media_type,
+            encrypt_key: unhex(&encrypt_key),
+            magic_value,
+        }
+    }
+}
+
+impl PushElem for MarketFace {
+    fn push_to(elem: Self, vec: &mut Vec<MessageElem>) {
+        let rq: ricq::msg::elem::MarketFace = elem.into();
+        PushElem::push_to(rq, vec);
+    }
+}
+
+impl From<MarketFace> for MessageElement {
+    fn from(face: MarketFace) -> Self {
+        Self::MarketFace(face)
+    }
+}
+
+/// 骰子
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dice {
+    /// 点数, 1到6
+    pub value: i32,
+}
+
+impl Dice {
+    /// 点数超出范围时返回`None`
+    pub fn new(value: i32) -> Option<Self> {
+        (1..=6).contains(&value).then_some(Self { value })
+    }
+}
+
+impl From<ricq::msg::elem::Dice> for Dice {
+    fn from(dice: ricq::msg::elem::Dice) -> Self {
+        Self { value: dice.value }
+    }
+}
+
+impl From<Dice> for ricq::msg::elem::Dice {
+    fn from(Dice { value }: Dice) -> Self {
+        Self { value }
+    }
+}
+
+impl PushElem for Dice {
+    fn push_to(elem: Self, vec: &mut Vec<MessageElem>) {
+        let rq: ricq::msg::elem::Dice = elem.into();
+        PushElem::push_to(rq, vec);
+    }
+}
+
+impl From<Dice> for MessageElement {
+    fn from(dice: Dice) -> Self {
+        Self::Dice(dice)
+    }
+}
+
+/// 猜拳
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FingerGuessing {
+    Rock,
+    Scissors,
+    Paper,
+}
+
+impl fmt::Display for FingerGuessing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Rock => "石头",
+            Self::Scissors => "剪刀",
+            Self::Paper => "布",
+        })
+    }
+}
+
+impl From<ricq::msg::elem::FingerGuessing> for FingerGuessing {
+    fn from(value: ricq::msg::elem::FingerGuessing) -> Self {
+        match value {
+            ricq::msg::elem::FingerGuessing::Rock => Self::Rock,
+            ricq::msg::elem::FingerGuessing::Scissors => Self::Scissors,
+            ricq::msg::elem::FingerGuessing::Paper => Self::Paper,
+        }
+    }
+}
+
+impl From<FingerGuessing> for ricq::msg::elem::FingerGuessing {
+    fn from(value: FingerGuessing) -> Self {
+        match value {
+            FingerGuessing::Rock => Self::Rock,
+            FingerGuessing::Scissors => Self::Scissors,
+            FingerGuessing::Paper => Self::Paper,
+        }
+    }
+}
+
+impl PushElem for FingerGuessing {
+    fn push_to(elem: Self, vec: &mut Vec<MessageElem>) {
+        let rq: ricq::msg::elem::FingerGuessing = elem.into();
+        PushElem::push_to(rq, vec);
+    }
+}
+
+impl From<FingerGuessing> for MessageElement {
+    fn from(value: FingerGuessing) -> Self {
+        Self::FingerGuessing(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Dice, MarketFace};
+
+    #[test]
+    fn market_face_round_trip() {
+        let face = MarketFace {
+            name: "[嘿嘿]".into(),
+            face_id: "0a1b2c".into(),
+            tab_id: 114514,
+            item_type: 6,
+            sub_type: 3,
+            media_type: 0,
+            encrypt_key: "ff00".into(),
+            magic_value: "".into(),
+        };
+
+        let rq: ricq::msg::elem::MarketFace = face.clone().into();
+        assert_eq!(rq.face_id, vec![0x0a, 0x1b, 0x2c]);
+        assert_eq!(MarketFace::from(rq), face);
+    }
+
+    #[test]
+    fn dice_range() {
+        assert!(Dice::new(0).is_none());
+        assert_eq!(Dice::new(6).map(|d| d.value), Some(6));
+    }
+}
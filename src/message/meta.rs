@@ -21,6 +21,12 @@ pub trait RecallMessage {
     fn receipt(&self) -> MessageReceipt;
 }
 
+impl RecallMessage for MessageReceipt {
+    fn receipt(&self) -> MessageReceipt {
+        self.clone()
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Default)]
 pub struct MessageMetadata {
     pub seqs: Vec<i32>,
@@ -1,18 +1,35 @@
 pub mod at;
+pub mod builder;
+pub mod code;
+pub mod codec;
 pub mod face;
 pub mod ffi;
 pub mod forward;
 pub mod image;
 pub mod macros;
+pub mod market_face;
 pub mod meta;
-
+pub mod music;
+pub mod poke;
+pub mod render;
+pub mod reply;
+pub mod video;
+pub mod voice;
+
+use crate::error::{AtriError, AtriResult};
 use crate::event::{Event, FromEvent};
 use crate::message::at::At;
+use crate::message::builder::MessageChainBuilder;
 use crate::message::face::Face;
+use crate::message::forward::ForwardCard;
+use crate::message::market_face::{Dice, FingerGuessing, MarketFace};
 use crate::message::meta::{Anonymous, MessageMetadata, MessageReceipt, RecallMessage, Reply};
+use crate::message::poke::Poke;
+use crate::message::video::Video;
+use crate::message::voice::Voice;
 use crate::Text;
 use image::Image;
-use ricq::msg::elem::RQElem;
+use ricq::msg::elem::{LightApp, RQElem, RichMsg};
 use ricq::msg::{MessageChain as RQMessageChain, MessageElem, PushElem};
 use ricq::structs::{FriendMessage, GroupMessage};
 use serde::{Deserialize, Serialize};
@@ -23,6 +40,9 @@ use std::vec;
 pub struct MessageChain {
     meta: MessageMetadata,
     elements: Vec<MessageElement>,
+    /// 发送时是否还原未知元素的原始数据
+    #[serde(skip)]
+    raw_elements: bool,
 }
 
 impl MessageChain {
@@ -60,6 +80,13 @@ impl MessageChain {
         }
     }
 
+    /// 发送时是否还原未知元素的原始数据, 默认不还原.
+    ///
+    /// 从[`MessageChain::from_bytes`]或[`MessageChain::from_json`]解码的消息默认还原
+    pub fn with_raw_elements(&mut self, enabled: bool) {
+        self.raw_elements = enabled;
+    }
+
     pub fn with_reply(&mut self, reply: Reply) {
         self.metadata_mut().reply = Some(reply)
     }
@@ -68,6 +95,7 @@ impl MessageChain {
         self.metadata_mut().anonymous = Some(ano)
     }
 
+    /// 序列化为json, 不包含未知元素, 见[`MessageChain::to_json_lossless`]
     pub fn to_json(&self) -> String {
         let known: Vec<MessageElement> = self
             .into_iter()
@@ -77,15 +105,98 @@ impl MessageChain {
             })
             .collect();
 
-        serde_json::to_string(&MessageChain {
-            meta: self.metadata().clone(),
-            elements: known,
+        serde_json::to_string(&codec::Versioned {
+            version: Self::SERIALIZE_VERSION,
+            chain: &MessageChain {
+                meta: self.metadata().clone(),
+                elements: known,
+                raw_elements: false,
+            },
         })
         .expect("Serializing error")
     }
 
     pub fn from_json(s: &str) -> serde_json::Result<Self> {
-        serde_json::from_str(s)
+        let codec::VersionedOwned { version, mut chain } = serde_json::from_str(s)?;
+        if version > Self::SERIALIZE_VERSION {
+            return Err(serde::de::Error::custom(format!(
+                "unsupported message chain version: {version}"
+            )));
+        }
+
+        chain.raw_elements = true;
+        Ok(chain)
+    }
+
+    /// 消息中的语音
+    pub fn voice(&self) -> Option<&Voice> {
+        self.iter().find_map(|e| match e {
+            MessageElement::Voice(v) => Some(v),
+            _ => None,
+        })
+    }
+
+    /// 发送前检查语音, 语音只能单独发送, 与其他元素一同发送时返回错误
+    pub(crate) fn voice_to_send(&self) -> AtriResult<Option<&Voice>> {
+        match self.voice() {
+            Some(_) if self.len() > 1 => Err(AtriError::InvalidMessage(
+                "voice must be sent without other elements",
+            )),
+            voice => Ok(voice),
+        }
+    }
+
+    pub fn builder() -> MessageChainBuilder {
+        MessageChainBuilder::new()
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    pub fn push<E: Into<MessageElement>>(&mut self, elem: E) {
+        self.elements.push(elem.into());
+    }
+
+    /// 在`index`处插入元素, 超出长度时panic
+    pub fn insert<E: Into<MessageElement>>(&mut self, index: usize, elem: E) {
+        self.elements.insert(index, elem.into());
+    }
+
+    /// 移除`index`处的元素, 超出长度时panic
+    pub fn remove(&mut self, index: usize) -> MessageElement {
+        self.elements.remove(index)
+    }
+
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(&MessageElement) -> bool,
+    {
+        self.elements.retain(f);
+    }
+
+    /// 消息中所有文本的拼接
+    pub fn plain_text(&self) -> String {
+        self.iter()
+            .filter_map(|e| match e {
+                MessageElement::Text(s) => Some(s.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// 消息是否at了`id`, 不包括at全体成员
+    pub fn contains_at(&self, id: i64) -> bool {
+        self.iter()
+            .any(|e| matches!(e, MessageElement::At(At { target, .. }) if *target == id))
+    }
+
+    pub fn contains_at_all(&self) -> bool {
+        self.iter().any(|e| matches!(e, MessageElement::AtAll))
     }
 }
 
@@ -195,7 +306,18 @@ impl From<ricq::msg::MessageChain> for MessageChain {
                 }
                 or => {
                     let rq = ricq::msg::elem::RQElem::from(or);
-                    value.push(MessageElement::from(rq));
+                    let elem = MessageElement::from(rq);
+
+                    // 戳一戳后附带的文本仅供旧版客户端显示
+                    if let (MessageElement::Text(text), Some(MessageElement::Poke(_))) =
+                        (&elem, value.last())
+                    {
+                        if Poke::is_fallback_text(text) {
+                            continue;
+                        }
+                    }
+
+                    value.push(elem);
                 }
             }
         }
@@ -203,6 +325,7 @@ impl From<ricq::msg::MessageChain> for MessageChain {
         Self {
             meta,
             elements: value,
+            raw_elements: false,
         }
     }
 }
@@ -228,7 +351,12 @@ impl PushElem for MessageChain {
         }
 
         for value in elem.elements {
-            MessageElement::push_to(value, vec);
+            match value {
+                MessageElement::Unknown(rq) if elem.raw_elements => {
+                    vec.extend(codec::raw_elem(&rq));
+                }
+                or => MessageElement::push_to(or, vec),
+            }
         }
     }
 }
@@ -239,10 +367,26 @@ impl PushElem for MessageChain {
 pub enum MessageElement {
     Text(String),
     Image(Image),
+    FlashImage(Image),
     At(At),
     AtAll,
     Face(Face),
-    #[serde(skip)]
+    Forward(ForwardCard),
+    Voice(Voice),
+    Video(Video),
+    MarketFace(MarketFace),
+    Dice(Dice),
+    FingerGuessing(FingerGuessing),
+    Poke(Poke),
+    /// json卡片, 如小程序
+    LightApp(String),
+    /// xml卡片, 如链接分享
+    Xml {
+        service_id: i32,
+        content: String,
+    },
+    /// 未知元素, 序列化为base64编码的原始protobuf数据
+    #[serde(rename = "raw", with = "codec::raw")]
     Unknown(RQElem),
 }
 
@@ -255,6 +399,9 @@ impl ToString for MessageElement {
             Self::Image(img) => {
                 let _ = write!(s, "$[Image:{}]", img.url());
             }
+            Self::FlashImage(img) => {
+                let _ = write!(s, "$[FlashImage:{}]", img.url());
+            }
             Self::At(At { target, display }) => {
                 let _ = write!(s, "$[At:{display}({target})]");
             }
@@ -262,6 +409,31 @@ impl ToString for MessageElement {
             Self::Face(f) => {
                 let _ = write!(s, "$[Face:{}]", f.name);
             }
+            Self::Forward(card) => {
+                let _ = write!(s, "$[Forward:{}]", card.res_id);
+            }
+            Self::Voice(voice) => {
+                let _ = write!(s, "$[Voice:{}]", voice.name());
+            }
+            Self::Video(video) => {
+                let _ = write!(s, "$[Video:{}]", video.name);
+            }
+            Self::MarketFace(face) => {
+                let _ = write!(s, "$[MarketFace:{}]", face.name);
+            }
+            Self::Dice(dice) => {
+                let _ = write!(s, "$[Dice:{}]", dice.value);
+            }
+            Self::FingerGuessing(value) => {
+                let _ = write!(s, "$[FingerGuessing:{value}]");
+            }
+            Self::Poke(poke) => {
+                let _ = write!(s, "$[Poke:{}]", poke.name);
+            }
+            Self::LightApp(_) => s.push_str("$[LightApp]"),
+            Self::Xml { service_id, .. } => {
+                let _ = write!(s, "$[Xml:{service_id}]");
+            }
             Self::Unknown(rq) => s.push_str(&rq.to_string()),
         }
 
@@ -275,6 +447,18 @@ impl From<String> for MessageElement {
     }
 }
 
+impl From<&str> for MessageElement {
+    fn from(s: &str) -> Self {
+        Self::Text(s.to_owned())
+    }
+}
+
+impl From<MessageElement> for MessageChain {
+    fn from(elem: MessageElement) -> Self {
+        Self::from(vec![elem])
+    }
+}
+
 impl From<MessageElement> for RQElem {
     fn from(val: MessageElement) -> Self {
         match val {
@@ -283,9 +467,26 @@ impl From<MessageElement> for RQElem {
                 Image::Friend(img) => RQElem::FriendImage(img),
                 Image::Group(img) => RQElem::GroupImage(img),
             },
+            MessageElement::FlashImage(img) => RQElem::FlashImage(img.flash()),
             MessageElement::At(at) => RQElem::At(at.into()),
             MessageElement::AtAll => RQElem::At(At::ALL.into()),
             MessageElement::Face(face) => RQElem::Face(face.into()),
+            MessageElement::Forward(card) => RQElem::RichMsg(card.to_rich_msg()),
+            // 语音不属于消息元素, 以文本代替. 发送时语音由`MessageChain::voice_to_send`单独处理, 不会经过此处
+            MessageElement::Voice(_) => RQElem::Text(Text::new("[语音]".into())),
+            MessageElement::Video(video) => RQElem::VideoFile(video.into()),
+            MessageElement::MarketFace(face) => RQElem::MarketFace(face.into()),
+            MessageElement::Dice(dice) => RQElem::Dice(dice.into()),
+            MessageElement::FingerGuessing(value) => RQElem::FingerGuessing(value.into()),
+            MessageElement::Poke(poke) => RQElem::Other(Box::new(poke.into())),
+            MessageElement::LightApp(content) => RQElem::LightApp(LightApp { content }),
+            MessageElement::Xml {
+                service_id,
+                content,
+            } => RQElem::RichMsg(RichMsg {
+                service_id,
+                template1: content,
+            }),
             MessageElement::Unknown(rq) => rq,
         }
     }
@@ -297,6 +498,7 @@ impl From<RQElem> for MessageElement {
             RQElem::Text(Text { content }) => MessageElement::Text(content),
             RQElem::GroupImage(img) => MessageElement::Image(Image::Group(img)),
             RQElem::FriendImage(img) => MessageElement::Image(Image::Friend(img)),
+            RQElem::FlashImage(img) => MessageElement::FlashImage(img.into()),
             RQElem::At(at) => {
                 if at.target == 0 {
                     MessageElement::AtAll
@@ -308,6 +510,29 @@ impl From<RQElem> for MessageElement {
                 }
             }
             RQElem::Face(face) => MessageElement::Face(face.into()),
+            RQElem::VideoFile(video) => MessageElement::Video(video.into()),
+            RQElem::MarketFace(face) => MessageElement::MarketFace(face.into()),
+            RQElem::Dice(dice) => MessageElement::Dice(dice.into()),
+            RQElem::FingerGuessing(value) => MessageElement::FingerGuessing(value.into()),
+            RQElem::Other(elem) => {
+                let poke = match &*elem {
+                    MessageElem::CommonElem(common) => Poke::from_common_elem(common),
+                    _ => None,
+                };
+
+                match poke {
+                    Some(poke) => MessageElement::Poke(poke),
+                    None => Self::Unknown(RQElem::Other(elem)),
+                }
+            }
+            RQElem::RichMsg(rich) => match ForwardCard::from_rich_msg(&rich) {
+                Some(card) => MessageElement::Forward(card),
+                None => MessageElement::Xml {
+                    service_id: rich.service_id,
+                    content: rich.template1,
+                },
+            },
+            RQElem::LightApp(LightApp { content }) => MessageElement::LightApp(content),
             or => Self::Unknown(or),
         }
     }
@@ -318,18 +543,86 @@ impl PushElem for MessageElement {
         match elem {
             Self::Text(s) => PushElem::push_to(Text::new(s), vec),
             Self::Image(img) => PushElem::push_to(img, vec),
+            Self::FlashImage(img) => PushElem::push_to(img.flash(), vec),
             Self::At(at) => PushElem::push_to(at, vec),
             Self::AtAll => PushElem::push_to(At::ALL, vec),
             Self::Face(face) => PushElem::push_to(face, vec),
+            Self::Forward(card) => PushElem::push_to(card, vec),
+            // 语音需单独发送, 与其他元素一同发送时会返回错误, 见`MessageChain::voice_to_send`
+            Self::Voice(_) => {}
+            Self::Video(video) => PushElem::push_to(video, vec),
+            Self::MarketFace(face) => PushElem::push_to(face, vec),
+            Self::Dice(dice) => PushElem::push_to(dice, vec),
+            Self::FingerGuessing(value) => PushElem::push_to(value, vec),
+            Self::Poke(poke) => PushElem::push_to(poke, vec),
+            Self::LightApp(content) => PushElem::push_to(LightApp { content }, vec),
+            Self::Xml {
+                service_id,
+                content,
+            } => PushElem::push_to(
+                RichMsg {
+                    service_id,
+                    template1: content,
+                },
+                vec,
+            ),
+            // 未知元素默认不发送, 见`MessageChain::with_raw_elements`
             Self::Unknown(_rq) => {}
         }
     }
 }
 
+/// 将字节编码为小写十六进制字符串
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// 解码十六进制字符串, 忽略无效的字符
+pub(crate) fn unhex(s: &str) -> Vec<u8> {
+    (0..s.len() / 2)
+        .filter_map(|i| u8::from_str_radix(s.get(i * 2..i * 2 + 2)?, 16).ok())
+        .collect()
+}
+
+/// 转义xml中的特殊字符
+pub(crate) fn xml_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            or => escaped.push(or),
+        }
+    }
+
+    escaped
+}
+
 #[cfg(test)]
 mod tests {
+    use ricq::pb::msg::Ptt;
+    use ricq::structs::GroupAudio;
+
+    use crate::message::voice::Voice;
     use crate::message::{MessageChain, MessageElement};
 
+    #[test]
+    fn voice_sent_alone() {
+        let voice = MessageElement::Voice(Voice::group(1, GroupAudio(Ptt::default())));
+
+        let chain = MessageChain::from(vec![voice.clone()]);
+        assert!(chain.voice_to_send().unwrap().is_some());
+
+        let chain = MessageChain::from(vec![voice, MessageElement::Text("text".into())]);
+        assert!(chain.voice_to_send().is_err());
+
+        let chain = MessageChain::from(vec![MessageElement::Text("text".into())]);
+        assert!(chain.voice_to_send().unwrap().is_none());
+    }
+
     #[test]
     fn serde() {
         let mut chain = MessageChain::default();
@@ -338,4 +631,23 @@ mod tests {
 
         println!("{}", chain.to_json());
     }
+
+    #[test]
+    fn rich_message_serde() {
+        let mut chain = MessageChain::default();
+        chain.elements.push(MessageElement::LightApp(
+            r#"{"app":"com.tencent.miniapp"}"#.into(),
+        ));
+        chain.elements.push(MessageElement::Xml {
+            service_id: 1,
+            content: "<msg />".into(),
+        });
+
+        let chain = MessageChain::from_json(&chain.to_json()).unwrap();
+        assert!(matches!(&chain.elements[0], MessageElement::LightApp(s) if s.contains("miniapp")));
+        assert!(matches!(
+            &chain.elements[1],
+            MessageElement::Xml { service_id: 1, .. }
+        ));
+    }
 }
@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+
+use crate::message::{xml_escape, MessageElement};
+
+/// 音乐分享卡片的服务id
+const MUSIC_SERVICE_ID: i32 = 2;
+
+/// 音乐分享, 以xml卡片发送
+///
+/// ```ignore
+/// let music = MusicShare::builder(MusicKind::NetEase, "Atri", "https://music.163.com/song?id=1")
+///     .summary("ATRI -My Dear Moments-")
+///     .music_url("https://music.163.com/song/media/outer/url?id=1.mp3")
+///     .build();
+/// group.send_message(MessageChain::from(vec![music.into()])).await?;
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MusicShare {
+    pub kind: MusicKind,
+    pub title: String,
+    /// 卡片的副标题, 通常为歌手
+    #[serde(default)]
+    pub summary: String,
+    /// 点击卡片时跳转的链接
+    pub jump_url: String,
+    /// 封面图片链接
+    #[serde(default)]
+    pub picture_url: String,
+    /// 音频链接
+    #[serde(default)]
+    pub music_url: String,
+}
+
+/// 音乐来源, 决定卡片底部显示的应用
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MusicKind {
+    #[serde(rename = "qq")]
+    QQ,
+    #[serde(rename = "netease")]
+    NetEase,
+    Custom {
+        /// 来源名称
+        name: String,
+        /// 来源图标链接
+        icon: String,
+    },
+}
+
+impl MusicKind {
+    /// (名称, 图标, 应用id)
+    fn source(&self) -> (&str, &str, &str) {
+        match self {
+            Self::QQ => (
+                "QQ音乐",
+                "https://i.gtimg.cn/open/app_icon/01/07/98/56/1101079856_100_m.png",
+                "100497308",
+            ),
+            Self::NetEase => (
+                "网易云音乐",
+                "https://i.gtimg.cn/open/app_icon/00/49/50/85/100495085_100_m.png",
+                "100495085",
+            ),
+            Self::Custom { name, icon } => (name, icon, ""),
+        }
+    }
+}
+
+impl MusicShare {
+    pub fn builder<T, U>(kind: MusicKind, title: T, jump_url: U) -> MusicShareBuilder
+    where
+        T: Into<String>,
+        U: Into<String>,
+    {
+        MusicShareBuilder(Self {
+            kind,
+            title: title.into(),
+            summary: String::new(),
+            jump_url: jump_url.into(),
+            picture_url: String::new(),
+            music_url: String::new(),
+        })
+    }
+
+    /// 生成xml卡片内容
+    pub fn to_xml(&self) -> String {
+        let (name, icon, app_id) = self.kind.source();
+
+        format!(
+            r#"<?xml version='1.0' encoding='UTF-8' standalone='yes' ?><msg serviceID="2" templateID="1" action="web" brief="[分享] {title}" sourceMsgId="0" url="{jump}" flag="0" adverSign="0" multiMsgFlag="0"><item layout="2"><audio cover="{picture}" src="{music}" /><title>{title}</title><summary>{summary}</summary></item><source name="{name}" icon="{icon}" url="" action="app" a_actionData="" i_actionData="" appid="{app_id}" /></msg>"#,
+            title = xml_escape(&self.title),
+            jump = xml_escape(&self.jump_url),
+            picture = xml_escape(&self.picture_url),
+            music = xml_escape(&self.music_url),
+            summary = xml_escape(&self.summary),
+            name = xml_escape(name),
+            icon = xml_escape(icon),
+        )
+    }
+}
+
+impl From<MusicShare> for MessageElement {
+    fn from(music: MusicShare) -> Self {
+        Self::Xml {
+            service_id: MUSIC_SERVICE_ID,
+            content: music.to_xml(),
+        }
+    }
+}
+
+/// [`MusicShare`]的构建器
+#[derive(Debug, Clone)]
+pub struct MusicShareBuilder(MusicShare);
+
+impl MusicShareBuilder {
+    pub fn summary<S: Into<String>>(mut self, summary: S) -> Self {
+        self.0.summary = summary.into();
+        self
+    }
+
+    pub fn picture_url<S: Into<String>>(mut self, url: S) -> Self {
+        self.0.picture_url = url.into();
+        self
+    }
+
+    pub fn music_url<S: Into<String>>(mut self, url: S) -> Self {
+        self.0.music_url = url.into();
+        self
+    }
+
+    pub fn build(self) -> MusicShare {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MusicKind, MusicShare};
+    use crate::message::MessageElement;
+
+    #[test]
+    fn xml_card() {
+        let music = MusicShare::builder(MusicKind::QQ, "Atri & Natsuki", "https://y.qq.com")
+            .summary("<ATRI>")
+            .build();
+
+        let MessageElement::Xml {
+            service_id,
+            content,
+        } = music.into()
+        else {
+            unreachable!()
+        };
+        assert_eq!(service_id, 2);
+        assert!(content.contains("<title>Atri &amp; Natsuki</title>"));
+        assert!(content.contains("<summary>&lt;ATRI&gt;</summary>"));
+        assert!(content.contains(r#"name="QQ音乐""#));
+    }
+}
@@ -0,0 +1,135 @@
+use ricq::msg::elem::Text;
+use ricq::msg::{MessageElem, PushElem};
+use ricq::pb::msg::CommonElem;
+use serde::{Deserialize, Serialize};
+
+use crate::message::MessageElement;
+
+/// 消息中的戳一戳
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Poke {
+    pub name: String,
+    /// 戳一戳的类型
+    pub poke_type: i32,
+    pub id: i32,
+}
+
+impl Poke {
+    /// 戳一戳消息的服务类型
+    const SERVICE_TYPE: i32 = 2;
+
+    /// 旧版客户端显示的文本
+    const FALLBACK: &'static str = "[戳一戳]请使用最新版手机QQ体验新功能。";
+
+    pub fn new<S: Into<String>>(name: S, poke_type: i32, id: i32) -> Self {
+        Self {
+            name: name.into(),
+            poke_type,
+            id,
+        }
+    }
+
+    /// 从通用元素中解析, 不是戳一戳时返回`None`
+    pub(crate) fn from_common_elem(elem: &CommonElem) -> Option<Self> {
+        if elem.service_type != Some(Self::SERVICE_TYPE) {
+            return None;
+        }
+
+        let info: pb::PokeInfo = prost::Message::decode(elem.pb_elem.as_deref()?).ok()?;
+
+        Some(Self {
+            name: info.vaspoke_name.unwrap_or_default(),
+            poke_type: info.poke_type.unwrap_or_default(),
+            id: info.vaspoke_id.unwrap_or(-1),
+        })
+    }
+
+    fn to_common_elem(&self) -> CommonElem {
+        let info = pb::PokeInfo {
+            poke_type: Some(self.poke_type),
+            vaspoke_id: Some(self.id),
+            vaspoke_name: Some(self.name.clone()),
+            vaspoke_minver: Some("7.2.0".into()),
+            poke_strength: Some(0),
+            msg_type: Some(0),
+            face_bubble_count: Some(0),
+            poke_flag: Some(0),
+        };
+
+        CommonElem {
+            service_type: Some(Self::SERVICE_TYPE),
+            pb_elem: Some(prost::Message::encode_to_vec(&info)),
+            business_type: Some(self.poke_type),
+        }
+    }
+
+    /// 是否为旧版客户端显示的替代文本
+    pub(crate) fn is_fallback_text(text: &str) -> bool {
+        text == Self::FALLBACK
+    }
+}
+
+impl Default for Poke {
+    /// 普通的戳一戳
+    fn default() -> Self {
+        Self::new("戳一戳", 1, -1)
+    }
+}
+
+impl PushElem for Poke {
+    fn push_to(elem: Self, vec: &mut Vec<MessageElem>) {
+        vec.push(MessageElem::CommonElem(elem.to_common_elem()));
+        PushElem::push_to(Text::new(Self::FALLBACK.into()), vec);
+    }
+}
+
+impl From<Poke> for MessageElem {
+    fn from(poke: Poke) -> Self {
+        MessageElem::CommonElem(poke.to_common_elem())
+    }
+}
+
+impl From<Poke> for MessageElement {
+    fn from(poke: Poke) -> Self {
+        Self::Poke(poke)
+    }
+}
+
+mod pb {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct PokeInfo {
+        #[prost(int32, optional, tag = "1")]
+        pub poke_type: Option<i32>,
+        #[prost(int32, optional, tag = "2")]
+        pub vaspoke_id: Option<i32>,
+        #[prost(string, optional, tag = "3")]
+        pub vaspoke_name: Option<String>,
+        #[prost(string, optional, tag = "4")]
+        pub vaspoke_minver: Option<String>,
+        #[prost(int32, optional, tag = "5")]
+        pub poke_strength: Option<i32>,
+        #[prost(int32, optional, tag = "6")]
+        pub msg_type: Option<i32>,
+        #[prost(int32, optional, tag = "7")]
+        pub face_bubble_count: Option<i32>,
+        #[prost(int32, optional, tag = "8")]
+        pub poke_flag: Option<i32>,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Poke;
+
+    #[test]
+    fn common_elem_round_trip() {
+        let poke = Poke {
+            name: "比心".into(),
+            poke_type: 2,
+            id: -1,
+        };
+
+        let elem = poke.to_common_elem();
+        assert_eq!(Poke::from_common_elem(&elem), Some(poke));
+    }
+}
@@ -0,0 +1,513 @@
+//! 消息渲染
+//!
+//! 将[`MessageChain`]与[`ForwardMessage`]渲染为html, CommonMark或纯文本.
+//! 实现[`MessageRenderer`]即可添加新的格式;
+//! 未知元素可通过[`register_element_renderer`]按格式注册渲染方式
+//!
+//! ```ignore
+//! let html = HtmlRenderer.render(event.message());
+//! ```
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+
+use crate::message::at::At;
+use crate::message::face::Face;
+use crate::message::forward::{ForwardMessage, ForwardNode, ForwardNodeInfo};
+use crate::message::image::Image;
+use crate::message::meta::Reply;
+use crate::message::{xml_escape, MessageChain, MessageElement};
+
+/// 消息渲染器, 各方法将对应的内容追加至`out`
+pub trait MessageRenderer {
+    /// 格式名, 用于查找注册的元素渲染器
+    fn format(&self) -> &str;
+
+    fn text(&self, out: &mut String, text: &str);
+
+    fn at(&self, out: &mut String, at: &At);
+
+    fn at_all(&self, out: &mut String);
+
+    fn face(&self, out: &mut String, face: &Face);
+
+    fn image(&self, out: &mut String, image: &Image, flash: bool);
+
+    /// 回复的消息, `quoted`为已渲染的引用内容
+    fn reply(&self, out: &mut String, reply: &Reply, quoted: &str);
+
+    /// 转发消息的一个节点, `content`为已渲染的节点内容
+    fn forward_node(&self, out: &mut String, info: &ForwardNodeInfo, content: &str);
+
+    /// 包裹整条转发消息
+    fn forward(&self, out: &mut String, nodes: &str) {
+        out.push_str(nodes);
+    }
+
+    /// 其他元素, 默认渲染为其文本形式
+    fn other(&self, out: &mut String, elem: &MessageElement) {
+        self.text(out, &elem.to_string());
+    }
+
+    fn element(&self, out: &mut String, elem: &MessageElement) {
+        match elem {
+            MessageElement::Text(text) => self.text(out, text),
+            MessageElement::At(at) => self.at(out, at),
+            MessageElement::AtAll => self.at_all(out),
+            MessageElement::Face(face) => self.face(out, face),
+            MessageElement::Image(image) => self.image(out, image, false),
+            MessageElement::FlashImage(image) => self.image(out, image, true),
+            MessageElement::Unknown(_) => match render_registered(self.format(), elem) {
+                Some(s) => out.push_str(&s),
+                None => self.other(out, elem),
+            },
+            or => self.other(out, or),
+        }
+    }
+
+    /// 渲染消息链, 包括回复的消息
+    fn render(&self, chain: &MessageChain) -> String {
+        let mut out = String::new();
+
+        if let Some(reply) = chain.referred() {
+            let mut quoted = String::new();
+            for elem in &reply.elements {
+                self.element(&mut quoted, elem);
+            }
+            self.reply(&mut out, reply, &quoted);
+        }
+
+        for elem in chain {
+            self.element(&mut out, elem);
+        }
+
+        out
+    }
+
+    /// 渲染转发消息, 嵌套的转发消息渲染为嵌套的块
+    fn render_forward(&self, forward: &ForwardMessage) -> String {
+        let mut nodes = String::new();
+        for node in forward.iter() {
+            match node {
+                ForwardNode::NormalMessage { info, chain } => {
+                    self.forward_node(&mut nodes, info, &self.render(chain));
+                }
+                ForwardNode::ForwardMessage { info, forward } => {
+                    self.forward_node(&mut nodes, info, &self.render_forward(forward));
+                }
+            }
+        }
+
+        let mut out = String::new();
+        self.forward(&mut out, &nodes);
+        out
+    }
+}
+
+/// 仅允许http(s)链接, 防止`javascript:`等链接
+fn safe_url(url: &str) -> Option<&str> {
+    let lower = url.trim_start().to_ascii_lowercase();
+    (lower.starts_with("http://") || lower.starts_with("https://")).then_some(url)
+}
+
+/// 表情名为空时从表情表中查找
+fn face_name(face: &Face) -> String {
+    if !face.name.is_empty() {
+        return face.name.clone();
+    }
+
+    Face::from_index(face.index)
+        .map(|f| f.name)
+        .unwrap_or_else(|| format!("表情{}", face.index))
+}
+
+/// 为每行添加前缀
+fn prefix_lines(out: &mut String, content: &str, prefix: &str) {
+    for line in content.split('\n') {
+        out.push_str(prefix);
+        out.push_str(line);
+        out.push('\n');
+    }
+}
+
+/// 纯文本
+pub struct PlainTextRenderer;
+
+impl MessageRenderer for PlainTextRenderer {
+    fn format(&self) -> &str {
+        "text"
+    }
+
+    fn text(&self, out: &mut String, text: &str) {
+        out.push_str(text);
+    }
+
+    fn at(&self, out: &mut String, at: &At) {
+        if at.display.starts_with('@') {
+            out.push_str(&at.display);
+        } else {
+            let _ = write!(out, "@{}", at.display);
+        }
+    }
+
+    fn at_all(&self, out: &mut String) {
+        out.push_str("@全体成员");
+    }
+
+    fn face(&self, out: &mut String, face: &Face) {
+        let _ = write!(out, "[{}]", face_name(face));
+    }
+
+    fn image(&self, out: &mut String, _image: &Image, flash: bool) {
+        out.push_str(if flash { "[闪照]" } else { "[图片]" });
+    }
+
+    fn reply(&self, out: &mut String, _reply: &Reply, quoted: &str) {
+        prefix_lines(out, quoted, "> ");
+    }
+
+    fn forward_node(&self, out: &mut String, info: &ForwardNodeInfo, content: &str) {
+        let _ = writeln!(out, "{}:", info.sender_name);
+        prefix_lines(out, content.trim_end_matches('\n'), "  ");
+    }
+}
+
+/// 经过转义的html片段, 元素带有`class`以便设置样式
+pub struct HtmlRenderer;
+
+impl MessageRenderer for HtmlRenderer {
+    fn format(&self) -> &str {
+        "html"
+    }
+
+    fn text(&self, out: &mut String, text: &str) {
+        out.push_str(&xml_escape(text).replace('\n', "<br>"));
+    }
+
+    fn at(&self, out: &mut String, at: &At) {
+        let _ = write!(out, r#"<span class="at" data-target="{}">"#, at.target);
+        let mut s = String::new();
+        PlainTextRenderer.at(&mut s, at);
+        out.push_str(&xml_escape(&s));
+        out.push_str("</span>");
+    }
+
+    fn at_all(&self, out: &mut String) {
+        out.push_str(r#"<span class="at-all">@全体成员</span>"#);
+    }
+
+    fn face(&self, out: &mut String, face: &Face) {
+        let _ = write!(
+            out,
+            r#"<span class="face" data-index="{}">[{}]</span>"#,
+            face.index,
+            xml_escape(&face_name(face))
+        );
+    }
+
+    fn image(&self, out: &mut String, image: &Image, flash: bool) {
+        let alt = if flash { "[闪照]" } else { "[图片]" };
+        match safe_url(&image.url()) {
+            Some(url) => {
+                let _ = write!(
+                    out,
+                    r#"<img class="image" src="{}" alt="{alt}">"#,
+                    xml_escape(url)
+                );
+            }
+            None => out.push_str(alt),
+        }
+    }
+
+    fn reply(&self, out: &mut String, reply: &Reply, quoted: &str) {
+        let _ = write!(
+            out,
+            r#"<blockquote class="reply" data-seq="{}" data-sender="{}">{quoted}</blockquote>"#,
+            reply.reply_seq, reply.sender
+        );
+    }
+
+    fn forward_node(&self, out: &mut String, info: &ForwardNodeInfo, content: &str) {
+        let _ = write!(
+            out,
+            r#"<div class="forward-node" data-sender="{}"><div class="sender">{}</div><div class="content">{content}</div></div>"#,
+            info.sender_id,
+            xml_escape(&info.sender_name)
+        );
+    }
+
+    fn forward(&self, out: &mut String, nodes: &str) {
+        let _ = write!(out, r#"<div class="forward">{nodes}</div>"#);
+    }
+}
+
+/// CommonMark
+pub struct MarkdownRenderer;
+
+/// 需要转义的字符
+const MARKDOWN_SPECIAL: &str = "\\`*_{}[]()<>#+-.!|~&";
+
+impl MarkdownRenderer {
+    fn escape(text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len());
+        for c in text.chars() {
+            match c {
+                '\n' => escaped.push_str("\\\n"),
+                c if MARKDOWN_SPECIAL.contains(c) => {
+                    escaped.push('\\');
+                    escaped.push(c);
+                }
+                or => escaped.push(or),
+            }
+        }
+
+        escaped
+    }
+}
+
+impl MessageRenderer for MarkdownRenderer {
+    fn format(&self) -> &str {
+        "markdown"
+    }
+
+    fn text(&self, out: &mut String, text: &str) {
+        out.push_str(&Self::escape(text));
+    }
+
+    fn at(&self, out: &mut String, at: &At) {
+        let mut s = String::new();
+        PlainTextRenderer.at(&mut s, at);
+        out.push_str(&Self::escape(&s));
+    }
+
+    fn at_all(&self, out: &mut String) {
+        out.push_str("@全体成员");
+    }
+
+    fn face(&self, out: &mut String, face: &Face) {
+        let _ = write!(out, "\\[{}\\]", Self::escape(&face_name(face)));
+    }
+
+    fn image(&self, out: &mut String, image: &Image, flash: bool) {
+        let alt = if flash { "闪照" } else { "图片" };
+        match safe_url(&image.url()) {
+            Some(url) => {
+                let url = url
+                    .replace('<', "%3C")
+                    .replace('>', "%3E")
+                    .replace(' ', "%20")
+                    .replace('\n', "");
+                let _ = write!(out, "![{alt}](<{url}>)");
+            }
+            None => {
+                let _ = write!(out, "\\[{alt}\\]");
+            }
+        }
+    }
+
+    fn reply(&self, out: &mut String, _reply: &Reply, quoted: &str) {
+        prefix_lines(out, quoted, "> ");
+        out.push('\n');
+    }
+
+    fn forward_node(&self, out: &mut String, info: &ForwardNodeInfo, content: &str) {
+        let _ = writeln!(out, "> **{}**:", Self::escape(&info.sender_name));
+        prefix_lines(out, content.trim_end_matches('\n'), "> ");
+        out.push('\n');
+    }
+}
+
+/// 按格式名获取内置的渲染器: `text`, `html`或`markdown`
+pub fn builtin_renderer(format: &str) -> Option<&'static dyn MessageRenderer> {
+    match format {
+        "text" => Some(&PlainTextRenderer),
+        "html" => Some(&HtmlRenderer),
+        "markdown" => Some(&MarkdownRenderer),
+        _ => None,
+    }
+}
+
+type ElementRenderFn = Arc<dyn Fn(&MessageElement) -> Option<String> + Send + Sync>;
+
+struct RegisteredRenderer {
+    id: usize,
+    format: String,
+    f: ElementRenderFn,
+}
+
+static ELEMENT_RENDERERS: RwLock<Vec<RegisteredRenderer>> = RwLock::new(Vec::new());
+static NEXT_RENDERER_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// 为格式`format`注册未知元素的渲染方式, 返回`None`表示不处理该元素
+///
+/// 返回值需为该格式下已转义的内容. 后注册的优先, 丢弃返回的守卫即取消注册
+pub fn register_element_renderer<F>(format: &str, f: F) -> ElementRendererGuard
+where
+    F: Fn(&MessageElement) -> Option<String> + Send + Sync + 'static,
+{
+    let id = NEXT_RENDERER_ID.fetch_add(1, Ordering::Relaxed);
+    ELEMENT_RENDERERS
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(RegisteredRenderer {
+            id,
+            format: format.to_owned(),
+            f: Arc::new(f),
+        });
+
+    ElementRendererGuard { id }
+}
+
+/// 回调在锁外调用, 回调中可注册或注销渲染方式
+fn render_registered(format: &str, elem: &MessageElement) -> Option<String> {
+    let matched: Vec<ElementRenderFn> = ELEMENT_RENDERERS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .rev()
+        .filter(|r| r.format == format)
+        .map(|r| r.f.clone())
+        .collect();
+
+    matched.iter().find_map(|f| f(elem))
+}
+
+#[must_use = "if unused the renderer will immediately be unregistered"]
+pub struct ElementRendererGuard {
+    id: usize,
+}
+
+impl Drop for ElementRendererGuard {
+    fn drop(&mut self) {
+        ELEMENT_RENDERERS
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|r| r.id != self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ricq::msg::elem::RQElem;
+    use ricq::msg::MessageElem;
+    use ricq::pb::msg::GeneralFlags;
+
+    use super::{
+        register_element_renderer, render_registered, HtmlRenderer, MarkdownRenderer,
+        MessageRenderer, PlainTextRenderer,
+    };
+    use crate::message::at::At;
+    use crate::message::face::Face;
+    use crate::message::forward::{ForwardMessage, ForwardNode, ForwardNodeInfo};
+    use crate::message::{MessageChain, MessageElement};
+
+    fn chain() -> MessageChain {
+        let mut source = MessageChain::from(vec![MessageElement::Text("原消息".into())]);
+        source.metadata_mut().seqs = vec![1];
+
+        MessageChain::builder()
+            .reply_to(&source)
+            .at(At {
+                target: 10,
+                display: "<Atri>".into(),
+            })
+            .text(" 1 * 2 & 3")
+            .face(Face {
+                index: 1,
+                name: "撇嘴".into(),
+            })
+            .build()
+    }
+
+    #[test]
+    fn plain_text() {
+        assert_eq!(
+            PlainTextRenderer.render(&chain()),
+            "> 原消息\n@<Atri> 1 * 2 & 3[撇嘴]"
+        );
+    }
+
+    #[test]
+    fn html() {
+        let html = HtmlRenderer.render(&chain());
+        assert!(html.starts_with(r#"<blockquote class="reply" data-seq="1""#));
+        assert!(html.contains("@&lt;Atri&gt;"));
+        assert!(html.contains(" 1 * 2 &amp; 3"));
+        assert!(!html.contains("<Atri>"));
+    }
+
+    #[test]
+    fn markdown() {
+        assert_eq!(
+            MarkdownRenderer.render(&chain()),
+            "> 原消息\n\n@\\<Atri\\> 1 \\* 2 \\& 3\\[撇嘴\\]"
+        );
+    }
+
+    #[test]
+    fn forward() {
+        let info = |name: &str| ForwardNodeInfo {
+            sender_id: 1,
+            sender_name: name.into(),
+            time: 0,
+        };
+        let inner = ForwardMessage::from([ForwardNode::NormalMessage {
+            info: info("B"),
+            chain: MessageChain::from(vec![MessageElement::Text("内层".into())]),
+        }]);
+        let forward = ForwardMessage::from([
+            ForwardNode::NormalMessage {
+                info: info("A"),
+                chain: MessageChain::from(vec![MessageElement::Text("外层".into())]),
+            },
+            ForwardNode::ForwardMessage {
+                info: info("C"),
+                forward: inner,
+            },
+        ]);
+
+        assert_eq!(
+            PlainTextRenderer.render_forward(&forward),
+            "A:\n  外层\nC:\n  B:\n    内层\n"
+        );
+        assert_eq!(
+            HtmlRenderer
+                .render_forward(&forward)
+                .matches(r#"class="forward""#)
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn registered() {
+        let chain = MessageChain::from(vec![MessageElement::Unknown(RQElem::Other(Box::new(
+            MessageElem::GeneralFlags(GeneralFlags::default()),
+        )))]);
+
+        let guard = register_element_renderer("html", |elem| match elem {
+            MessageElement::Unknown(RQElem::Other(_)) => Some("<hr>".into()),
+            _ => None,
+        });
+        assert_eq!(HtmlRenderer.render(&chain), "<hr>");
+        assert_ne!(MarkdownRenderer.render(&chain), "<hr>");
+
+        drop(guard);
+        assert_ne!(HtmlRenderer.render(&chain), "<hr>");
+    }
+
+    #[test]
+    fn reentrant_renderer() {
+        let elem = MessageElement::Text("text".into());
+
+        let _guard = register_element_renderer("reentrant", |_| {
+            // 回调中注册与注销不会死锁
+            let inner = register_element_renderer("reentrant", |_| None);
+            drop(inner);
+            Some("ok".into())
+        });
+
+        assert_eq!(render_registered("reentrant", &elem).as_deref(), Some("ok"));
+    }
+}
@@ -0,0 +1,180 @@
+//! 回复消息的解析
+//!
+//! 消息中的[`Reply`](crate::message::meta::Reply)仅包含客户端截取的部分内容,
+//! 此模块根据序号查找被回复的完整消息, 依次查找最近消息缓存, 消息记录与服务器
+
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+
+use dashmap::DashMap;
+
+use crate::contact::group::Group;
+use crate::contact::member::NamedMember;
+use crate::error::{AtriError, AtriResult};
+use crate::event::Event;
+use crate::message::meta::MessageReceipt;
+use crate::message::MessageChain;
+use crate::service::history::history_store;
+
+/// 每个群缓存的最近消息数量
+pub const RECENT_CAPACITY: usize = 128;
+
+/// 被回复的完整消息
+#[derive(Clone)]
+pub struct RepliedMessage {
+    pub message: MessageChain,
+    /// 发送者, 已退群或为匿名消息时为`None`, 其号码见[`MessageMetadata::sender`](crate::message::meta::MessageMetadata::sender)
+    pub sender: Option<NamedMember>,
+}
+
+/// 各群最近的消息, 每个群最多保存[`RECENT_CAPACITY`]条
+#[derive(Default)]
+pub struct RecentMessages {
+    groups: DashMap<i64, Mutex<VecDeque<MessageChain>>>,
+}
+
+impl RecentMessages {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加一条消息, 已存在相同序号的消息时不做任何事
+    pub fn push(&self, group_id: i64, chain: MessageChain) {
+        let Some(&seq) = chain.metadata().seqs.first() else {
+            return;
+        };
+
+        let entry = self.groups.entry(group_id).or_default();
+        let mut queue = entry.lock().unwrap_or_else(|e| e.into_inner());
+
+        if queue.iter().any(|c| c.metadata().seqs.contains(&seq)) {
+            return;
+        }
+
+        if queue.len() >= RECENT_CAPACITY {
+            queue.pop_front();
+        }
+        queue.push_back(chain);
+    }
+
+    pub fn find(&self, group_id: i64, seq: i32) -> Option<MessageChain> {
+        let queue = self.groups.get(&group_id)?;
+        let queue = queue.lock().unwrap_or_else(|e| e.into_inner());
+
+        queue
+            .iter()
+            .rev()
+            .find(|c| c.metadata().seqs.contains(&seq))
+            .cloned()
+    }
+
+    pub fn len(&self, group_id: i64) -> usize {
+        self.groups
+            .get(&group_id)
+            .map(|q| q.lock().unwrap_or_else(|e| e.into_inner()).len())
+            .unwrap_or_default()
+    }
+}
+
+pub fn recent_messages() -> &'static RecentMessages {
+    static RECENT: OnceLock<RecentMessages> = OnceLock::new();
+    RECENT.get_or_init(RecentMessages::new)
+}
+
+/// 缓存收到的群消息
+pub(crate) fn record_event(event: &Event) {
+    if let Event::GroupMessage(e) = event {
+        recent_messages().push(e.group().id(), e.message().clone());
+    }
+}
+
+/// 缓存已发送的群消息
+pub(crate) fn record_sent(group: &Group, mut chain: MessageChain, receipt: &MessageReceipt) {
+    let meta = chain.metadata_mut();
+    meta.seqs = receipt.seqs.clone();
+    meta.rands = receipt.rands.clone();
+    meta.time = receipt.time as i32;
+    meta.sender = group.client().id();
+
+    recent_messages().push(group.id(), chain);
+}
+
+async fn find_message(group: &Group, seq: i32) -> AtriResult<Option<MessageChain>> {
+    if let Some(chain) = recent_messages().find(group.id(), seq) {
+        return Ok(Some(chain));
+    }
+
+    if let Some(store) = history_store() {
+        if let Some(record) = store.find_group_message(group.id(), seq).await? {
+            return Ok(Some(record.chain));
+        }
+    }
+
+    let messages = group
+        .client()
+        .request_client()
+        .get_group_msgs(group.id(), seq, seq)
+        .await
+        .map_err(AtriError::from)?;
+
+    let chain = messages
+        .into_iter()
+        .find(|msg| msg.seqs.contains(&seq))
+        .map(MessageChain::from);
+
+    if let Some(chain) = &chain {
+        recent_messages().push(group.id(), chain.clone());
+    }
+
+    Ok(chain)
+}
+
+impl MessageChain {
+    /// 查找此消息回复的完整消息, 不是回复消息或找不到时返回`None`
+    pub async fn resolve_reply(&self, group: &Group) -> AtriResult<Option<RepliedMessage>> {
+        let Some(reply) = self.referred() else {
+            return Ok(None);
+        };
+
+        let Some(message) = find_message(group, reply.reply_seq).await? else {
+            return Ok(None);
+        };
+
+        let sender_id = match message.metadata().sender {
+            0 => reply.sender,
+            id => id,
+        };
+        let sender = group.find_member(sender_id).await;
+
+        Ok(Some(RepliedMessage { message, sender }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RecentMessages, RECENT_CAPACITY};
+    use crate::message::{MessageChain, MessageElement};
+
+    fn chain(seq: i32) -> MessageChain {
+        let mut chain = MessageChain::from(vec![MessageElement::Text(seq.to_string())]);
+        chain.metadata_mut().seqs = vec![seq];
+        chain
+    }
+
+    #[test]
+    fn ring_buffer() {
+        let recent = RecentMessages::new();
+        for seq in 0..RECENT_CAPACITY as i32 + 10 {
+            recent.push(1, chain(seq));
+        }
+        recent.push(1, chain(20));
+
+        assert_eq!(recent.len(1), RECENT_CAPACITY);
+        assert!(recent.find(1, 5).is_none());
+        assert_eq!(recent.find(1, 20).unwrap().plain_text(), "20");
+        assert!(recent.find(2, 20).is_none());
+
+        recent.push(1, MessageChain::default());
+        assert_eq!(recent.len(1), RECENT_CAPACITY);
+    }
+}
@@ -0,0 +1,103 @@
+use ricq::msg::elem::VideoFile;
+use ricq::msg::{MessageElem, PushElem};
+use serde::{Deserialize, Serialize};
+
+use crate::message::{hex, unhex, MessageElement};
+
+/// 短视频消息
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Video {
+    pub name: String,
+    /// 十六进制编码的视频uuid
+    pub uuid: String,
+    /// 视频大小(字节)
+    pub size: i32,
+    /// 缩略图大小(字节)
+    pub thumb_size: i32,
+    /// 十六进制编码的视频md5
+    pub md5: String,
+    /// 十六进制编码的缩略图md5
+    pub thumb_md5: String,
+}
+
+impl From<VideoFile> for Video {
+    fn from(
+        VideoFile {
+            name,
+            uuid,
+            size,
+            thumb_size,
+            md5,
+            thumb_md5,
+        }: VideoFile,
+    ) -> Self {
+        Self {
+            name,
+            uuid: hex(&uuid),
+            size,
+            thumb_size,
+            md5: hex(&md5),
+            thumb_md5: hex(&thumb_md5),
+        }
+    }
+}
+
+impl From<Video> for VideoFile {
+    fn from(
+        Video {
+            name,
+            uuid,
+            size,
+            thumb_size,
+            md5,
+            thumb_md5,
+        }: Video,
+    ) -> Self {
+        Self {
+            name,
+            uuid: unhex(&uuid),
+            size,
+            thumb_size,
+            md5: unhex(&md5),
+            thumb_md5: unhex(&thumb_md5),
+        }
+    }
+}
+
+impl PushElem for Video {
+    fn push_to(elem: Self, vec: &mut Vec<MessageElem>) {
+        let rq: VideoFile = elem.into();
+        PushElem::push_to(rq, vec);
+    }
+}
+
+impl From<Video> for MessageElement {
+    fn from(video: Video) -> Self {
+        Self::Video(video)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Video;
+    use ricq::msg::elem::VideoFile;
+
+    #[test]
+    fn round_trip() {
+        let file = VideoFile {
+            name: "atri.mp4".into(),
+            uuid: vec![0x11, 0x45, 0x14],
+            size: 1024,
+            thumb_size: 64,
+            md5: vec![0xab; 16],
+            thumb_md5: vec![0xcd; 16],
+        };
+
+        let video = Video::from(file);
+        assert_eq!(video.uuid, "114514");
+
+        let back = VideoFile::from(video);
+        assert_eq!(back.uuid, vec![0x11, 0x45, 0x14]);
+        assert_eq!(back.md5, vec![0xab; 16]);
+    }
+}
@@ -0,0 +1,244 @@
+use std::io;
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use ricq::pb::msg::Ptt;
+use ricq::structs::{FriendAudio, GroupAudio};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AtriError, AtriResult};
+use crate::message::MessageElement;
+use crate::Client;
+
+/// 语音消息
+///
+/// 语音不在普通消息元素中传输, 需单独发送, 与其他元素一同发送时返回[`AtriError::InvalidMessage`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(try_from = "VoiceRepr", into = "VoiceRepr")]
+pub struct Voice {
+    source: VoiceSource,
+    ptt: Ptt,
+}
+
+/// 语音的来源, 获取下载链接时需要
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VoiceSource {
+    /// 群语音, 值为群号
+    Group(i64),
+    /// 好友语音, 值为发送者
+    Friend(i64),
+}
+
+impl Voice {
+    pub fn source(&self) -> VoiceSource {
+        self.source
+    }
+
+    pub fn name(&self) -> String {
+        String::from_utf8_lossy(self.ptt.file_name.as_deref().unwrap_or_default()).into_owned()
+    }
+
+    pub fn md5(&self) -> &[u8] {
+        self.ptt.file_md5.as_deref().unwrap_or_default()
+    }
+
+    /// 文件大小(字节)
+    pub fn size(&self) -> i32 {
+        self.ptt.file_size.unwrap_or_default()
+    }
+
+    /// 获取语音的下载链接
+    pub async fn url(&self, client: &Client) -> AtriResult<String> {
+        let url = match self.source {
+            VoiceSource::Group(group_id) => {
+                client
+                    .request_client()
+                    .get_group_audio_url(group_id, GroupAudio(self.ptt.clone()))
+                    .await?
+            }
+            VoiceSource::Friend(sender) => {
+                client
+                    .request_client()
+                    .get_friend_audio_url(sender, FriendAudio(self.ptt.clone()))
+                    .await?
+            }
+        };
+
+        Ok(url)
+    }
+
+    pub(crate) fn group(group_id: i64, audio: GroupAudio) -> Self {
+        Self {
+            source: VoiceSource::Group(group_id),
+            ptt: audio.0,
+        }
+    }
+
+    pub(crate) fn friend(sender: i64, audio: FriendAudio) -> Self {
+        Self {
+            source: VoiceSource::Friend(sender),
+            ptt: audio.0,
+        }
+    }
+
+    pub(crate) fn into_group_audio(self) -> GroupAudio {
+        GroupAudio(self.ptt)
+    }
+
+    pub(crate) fn into_friend_audio(self) -> FriendAudio {
+        FriendAudio(self.ptt)
+    }
+}
+
+impl From<Voice> for MessageElement {
+    fn from(voice: Voice) -> Self {
+        Self::Voice(voice)
+    }
+}
+
+/// 语音编码器, 将其他格式的音频转换为silk
+pub trait VoiceEncoder: Send + Sync + 'static {
+    /// `path`为原始音频文件的路径
+    fn encode(&self, path: &Path) -> io::Result<Vec<u8>>;
+}
+
+static VOICE_ENCODER: OnceLock<Box<dyn VoiceEncoder>> = OnceLock::new();
+
+/// 设置全局的语音编码器, 仅可设置一次, 已设置时返回`false`
+pub fn set_voice_encoder<E: VoiceEncoder>(encoder: E) -> bool {
+    VOICE_ENCODER.set(Box::new(encoder)).is_ok()
+}
+
+/// 读取本地音频文件, 若不是silk格式则使用设置的编码器转换
+///
+/// 未设置编码器时返回[`AtriError::NotSupported`]
+pub async fn read_silk<P: AsRef<Path>>(path: P) -> AtriResult<Vec<u8>> {
+    let path = path.as_ref().to_owned();
+    let data = tokio::fs::read(&path).await?;
+    if is_silk(&data) {
+        return Ok(data);
+    }
+
+    let Some(encoder) = VOICE_ENCODER.get() else {
+        return Err(AtriError::NotSupported);
+    };
+
+    let silk = tokio::task::spawn_blocking(move || encoder.encode(&path))
+        .await
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))??;
+
+    Ok(silk)
+}
+
+/// 上传群语音时silk格式的编码
+pub(crate) const SILK_CODEC: u32 = 1;
+
+/// silk v3文件头, 部分编码器会在前面添加一个字节`0x02`
+const SILK_HEADER: &[u8] = b"#!SILK_V3";
+
+/// 每个silk帧的时长
+const SILK_FRAME: Duration = Duration::from_millis(20);
+
+pub fn is_silk(data: &[u8]) -> bool {
+    silk_header_len(data).is_some()
+}
+
+fn silk_header_len(data: &[u8]) -> Option<usize> {
+    if data.starts_with(SILK_HEADER) {
+        Some(SILK_HEADER.len())
+    } else if data.first() == Some(&0x02) && data[1..].starts_with(SILK_HEADER) {
+        Some(SILK_HEADER.len() + 1)
+    } else {
+        None
+    }
+}
+
+/// 由silk帧数估算语音时长, 数据不是silk格式时返回`None`
+pub fn silk_duration(data: &[u8]) -> Option<Duration> {
+    let mut pos = silk_header_len(data)?;
+    let mut frames = 0u32;
+    while let Some(len) = data.get(pos..pos + 2) {
+        let len = i16::from_le_bytes([len[0], len[1]]);
+        if len < 0 {
+            break;
+        }
+
+        pos += 2 + len as usize;
+        if pos > data.len() {
+            break;
+        }
+        frames += 1;
+    }
+
+    Some(SILK_FRAME * frames)
+}
+
+#[derive(Serialize, Deserialize)]
+struct VoiceRepr {
+    source: VoiceSource,
+    /// base64编码的原始语音信息
+    ptt: String,
+}
+
+impl From<Voice> for VoiceRepr {
+    fn from(Voice { source, ptt }: Voice) -> Self {
+        Self {
+            source,
+            ptt: base64::encode(prost::Message::encode_to_vec(&ptt)),
+        }
+    }
+}
+
+impl TryFrom<VoiceRepr> for Voice {
+    type Error = String;
+
+    fn try_from(VoiceRepr { source, ptt }: VoiceRepr) -> Result<Self, Self::Error> {
+        let bytes = base64::decode(ptt).map_err(|e| e.to_string())?;
+        let ptt: Ptt = prost::Message::decode(&*bytes).map_err(|e| e.to_string())?;
+
+        Ok(Self { source, ptt })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_silk, silk_duration, Voice, VoiceSource};
+    use ricq::pb::msg::Ptt;
+    use std::time::Duration;
+
+    #[test]
+    fn silk() {
+        let mut data = vec![0x02];
+        data.extend_from_slice(b"#!SILK_V3");
+        for _ in 0..50 {
+            data.extend_from_slice(&3i16.to_le_bytes());
+            data.extend_from_slice(&[0; 3]);
+        }
+        data.extend_from_slice(&(-1i16).to_le_bytes());
+
+        assert!(is_silk(&data));
+        assert_eq!(silk_duration(&data), Some(Duration::from_secs(1)));
+        assert!(!is_silk(b"RIFF"));
+        assert_eq!(silk_duration(b"RIFF"), None);
+    }
+
+    #[test]
+    fn serde() {
+        let voice = Voice {
+            source: VoiceSource::Group(114514),
+            ptt: Ptt {
+                file_name: Some(b"atri.amr".to_vec()),
+                file_size: Some(1024),
+                ..Default::default()
+            },
+        };
+
+        let json = serde_json::to_string(&voice).unwrap();
+        let de: Voice = serde_json::from_str(&json).unwrap();
+        assert_eq!(de.source(), VoiceSource::Group(114514));
+        assert_eq!(de.name(), "atri.amr");
+        assert_eq!(de.size(), 1024);
+    }
+}
@@ -72,6 +72,30 @@ pub extern "C" fn client_get_friends(client: Handle) -> RustVec<Handle> {
     RustVec::from(ma)
 }
 
+pub extern "C" fn client_get_all_groups() -> RustVec<Handle> {
+    let ma: Vec<Handle> = Client::all_groups()
+        .map(|(_, g)| unsafe { group_to_handle(g) })
+        .collect();
+
+    RustVec::from(ma)
+}
+
+pub extern "C" fn client_get_all_friends() -> RustVec<Handle> {
+    let ma: Vec<Handle> = Client::all_friends()
+        .map(|(_, f)| unsafe { friend_to_ptr(f) })
+        .collect();
+
+    RustVec::from(ma)
+}
+
+/// 返回json格式的[`FriendCategory`](crate::contact::friend::FriendCategory)列表
+pub extern "C" fn client_get_friend_categories(client: Handle) -> RustString {
+    let b: &Client = cast_ref_phandle(&client);
+    let json =
+        serde_json::to_string(&b.friend_categories()).expect("Cannot serialize friend categories");
+    RustString::from(json)
+}
+
 pub extern "C" fn client_clone(client: Handle) -> Handle {
     let b: &Client = cast_ref_phandle(&client);
     unsafe { client_to_handle(b.clone()) }
@@ -1,12 +1,15 @@
 use super::cast_ref;
-use crate::event::{FriendMessageEvent, GroupMessageEvent};
+use crate::event::{FriendMessageEvent, GroupFileUploadEvent, GroupMessageEvent};
 use atri_ffi::contact::FFIMember;
+use atri_ffi::error::FFIResult;
 use atri_ffi::ffi::ForFFI;
+use atri_ffi::future::FFIFuture;
 
 use crate::contact::friend::Friend;
 use crate::contact::group::Group;
+use crate::contact::member::NamedMember;
 use atri_ffi::message::FFIMessageChain;
-use atri_ffi::PHandle;
+use atri_ffi::{FFIOption, ManagedCloneable, PHandle, RustString};
 use std::sync::atomic::{AtomicBool, Ordering};
 
 pub extern "C" fn event_intercept(intercepted: *const ()) {
@@ -36,6 +39,13 @@ pub extern "C" fn group_message_event_get_sender(event: *const ()) -> FFIMember
     sender.into_ffi()
 }
 
+/// 消息中的语音, 语音在[`group_message_event_get_message`]中仅为`[语音]`文本
+pub extern "C" fn group_message_event_get_voice(event: *const ()) -> FFIOption<ManagedCloneable> {
+    let event: &GroupMessageEvent = cast_ref(event);
+    let voice = event.message().voice().cloned();
+    FFIOption::from(voice.map(ManagedCloneable::from_value))
+}
+
 pub extern "C" fn friend_message_event_get_friend(event: *const ()) -> PHandle {
     let event: &FriendMessageEvent = cast_ref(event);
     event.friend() as *const Friend as PHandle
@@ -46,3 +56,34 @@ pub extern "C" fn friend_message_event_get_message(event: *const ()) -> FFIMessa
     let chain = event.message().to_owned();
     chain.into_ffi()
 }
+
+/// 消息中的语音, 语音在[`friend_message_event_get_message`]中仅为`[语音]`文本
+pub extern "C" fn friend_message_event_get_voice(event: *const ()) -> FFIOption<ManagedCloneable> {
+    let event: &FriendMessageEvent = cast_ref(event);
+    let voice = event.message().voice().cloned();
+    FFIOption::from(voice.map(ManagedCloneable::from_value))
+}
+
+pub extern "C" fn group_file_upload_event_get_group(event: *const ()) -> PHandle {
+    let event: &GroupFileUploadEvent = cast_ref(event);
+    event.group() as *const Group as PHandle
+}
+
+pub extern "C" fn group_file_upload_event_get_uploader(event: *const ()) -> PHandle {
+    let event: &GroupFileUploadEvent = cast_ref(event);
+    event.uploader() as *const NamedMember as PHandle
+}
+
+/// 返回json格式的[`GroupFile`](crate::contact::group::files::GroupFile)
+pub extern "C" fn group_file_upload_event_get_file(event: *const ()) -> RustString {
+    let event: &GroupFileUploadEvent = cast_ref(event);
+    let json = serde_json::to_string(event.file()).expect("Cannot serialize group file");
+    RustString::from(json)
+}
+
+pub extern "C" fn group_file_upload_event_get_download_url(
+    event: *const (),
+) -> FFIFuture<FFIResult<RustString>> {
+    let event: GroupFileUploadEvent = cast_ref::<GroupFileUploadEvent>(event).clone();
+    FFIFuture::from(async move { event.download_url().await.map(RustString::from).into() })
+}
@@ -1,14 +1,17 @@
 use super::rt::future_block_on;
 use crate::contact::friend::Friend;
+use crate::error::AtriResult;
+use crate::message::forward::ForwardMessage;
 use crate::message::meta::MessageReceipt;
-use crate::message::MessageChain;
+use crate::message::{MessageChain, MessageElement};
 use crate::plugin::ffi::cast_ref_phandle;
 use crate::plugin::ffi::client::client_to_handle;
 use atri_ffi::error::FFIResult;
 use atri_ffi::ffi::ForFFI;
 use atri_ffi::future::FFIFuture;
+use atri_ffi::message::forward::FFIForwardNode;
 use atri_ffi::message::{FFIMessageChain, FFIMessageReceipt};
-use atri_ffi::{Handle, ManagedCloneable, RustStr, RustVec};
+use atri_ffi::{Handle, ManagedCloneable, RustStr, RustString, RustVec};
 use std::slice;
 
 pub unsafe fn friend_to_ptr(friend: Friend) -> Handle {
@@ -21,6 +24,10 @@ pub unsafe fn friend_to_ptr_option(friend: Option<Friend>) -> Handle {
         .unwrap_or_else(std::ptr::null)
 }
 
+pub extern "C" fn friend_find_any(id: i64) -> Handle {
+    unsafe { friend_to_ptr_option(Friend::find_any(id)) }
+}
+
 pub extern "C" fn friend_get_id(friend: Handle) -> i64 {
     let f: &Friend = cast_ref_phandle(&friend);
     f.id()
@@ -32,6 +39,17 @@ pub extern "C" fn friend_get_nickname(friend: Handle) -> RustStr {
     RustStr::from(s)
 }
 
+pub extern "C" fn friend_get_remark(friend: Handle) -> RustStr {
+    let f: &Friend = cast_ref_phandle(&friend);
+    let s = f.remark();
+    RustStr::from(s)
+}
+
+pub extern "C" fn friend_get_category_id(friend: Handle) -> u8 {
+    let f: &Friend = cast_ref_phandle(&friend);
+    f.category_id()
+}
+
 pub extern "C" fn friend_get_client(friend: Handle) -> Handle {
     let f: &Friend = cast_ref_phandle(&friend);
     unsafe { client_to_handle(f.client()) }
@@ -43,8 +61,10 @@ pub extern "C" fn friend_send_message(
 ) -> FFIFuture<FFIResult<FFIMessageReceipt>> {
     FFIFuture::from(async move {
         let f: &Friend = cast_ref_phandle(&friend);
-        let chain = MessageChain::from_ffi(chain);
-        let result = f.send_message(chain).await.map(MessageReceipt::into_ffi);
+        let result = match MessageChain::try_from_ffi(chain) {
+            Ok(chain) => f.send_message(chain).await.map(MessageReceipt::into_ffi),
+            Err(e) => Err(e),
+        };
 
         FFIResult::from(result)
     })
@@ -56,7 +76,10 @@ pub extern "C" fn friend_send_message_blocking(
     chain: FFIMessageChain,
 ) -> FFIResult<FFIMessageReceipt> {
     let friend: &Friend = cast_ref_phandle(&friend);
-    let chain = MessageChain::from_ffi(chain);
+    let chain = match MessageChain::try_from_ffi(chain) {
+        Ok(chain) => chain,
+        Err(e) => return FFIResult::from(Err::<FFIMessageReceipt, _>(e)),
+    };
 
     future_block_on(manager, async move {
         let result = friend
@@ -81,6 +104,109 @@ pub extern "C" fn friend_upload_image(
     })
 }
 
+pub extern "C" fn friend_upload_voice(
+    friend: Handle,
+    silk: RustVec<u8>,
+) -> FFIFuture<FFIResult<ManagedCloneable>> {
+    let friend: &Friend = cast_ref_phandle(&friend);
+    let silk = silk.into_vec();
+    FFIFuture::from(async move {
+        let result = friend
+            .upload_voice(silk)
+            .await
+            .map(ManagedCloneable::from_value);
+
+        FFIResult::from(result)
+    })
+}
+
+pub extern "C" fn friend_upload_image_from_path(
+    friend: Handle,
+    path: RustStr,
+) -> FFIFuture<FFIResult<ManagedCloneable>> {
+    let friend: &Friend = cast_ref_phandle(&friend);
+    let path = path.as_ref().to_owned();
+    FFIFuture::from(async move {
+        let result = friend
+            .upload_image_from_path(path)
+            .await
+            .map(ManagedCloneable::from_value);
+
+        FFIResult::from(result)
+    })
+}
+
+pub extern "C" fn friend_upload_image_from_path_blocking(
+    manager: Handle,
+    friend: Handle,
+    path: RustStr,
+) -> FFIResult<ManagedCloneable> {
+    let friend: &Friend = cast_ref_phandle(&friend);
+    let path = path.as_ref().to_owned();
+
+    future_block_on(manager, async move {
+        let result = friend
+            .upload_image_from_path(path)
+            .await
+            .map(ManagedCloneable::from_value);
+
+        FFIResult::from(result)
+    })
+}
+
+pub extern "C" fn friend_upload_voice_blocking(
+    manager: Handle,
+    friend: Handle,
+    silk: RustVec<u8>,
+) -> FFIResult<ManagedCloneable> {
+    let friend: &Friend = cast_ref_phandle(&friend);
+    let silk = silk.into_vec();
+
+    future_block_on(manager, async move {
+        let result = friend
+            .upload_voice(silk)
+            .await
+            .map(ManagedCloneable::from_value);
+
+        FFIResult::from(result)
+    })
+}
+
+/// 发送语音, `voice`为[`friend_upload_voice`]返回的语音
+pub extern "C" fn friend_send_voice(
+    friend: Handle,
+    voice: ManagedCloneable,
+) -> FFIFuture<FFIResult<FFIMessageReceipt>> {
+    let friend: &Friend = cast_ref_phandle(&friend);
+    let chain = MessageChain::from(vec![MessageElement::Voice(unsafe { voice.into_value() })]);
+    FFIFuture::from(async move {
+        let result = friend
+            .send_message(chain)
+            .await
+            .map(MessageReceipt::into_ffi);
+
+        FFIResult::from(result)
+    })
+}
+
+pub extern "C" fn friend_send_voice_blocking(
+    manager: Handle,
+    friend: Handle,
+    voice: ManagedCloneable,
+) -> FFIResult<FFIMessageReceipt> {
+    let friend: &Friend = cast_ref_phandle(&friend);
+    let chain = MessageChain::from(vec![MessageElement::Voice(unsafe { voice.into_value() })]);
+
+    future_block_on(manager, async move {
+        let result = friend
+            .send_message(chain)
+            .await
+            .map(MessageReceipt::into_ffi);
+
+        FFIResult::from(result)
+    })
+}
+
 pub extern "C" fn friend_upload_image_blocking(
     manager: Handle,
     friend: Handle,
@@ -115,6 +241,79 @@ pub extern "C" fn friend_upload_image_ex(
     })
 }
 
+pub extern "C" fn friend_recall_message(
+    friend: Handle,
+    receipt: FFIMessageReceipt,
+) -> FFIFuture<FFIResult<()>> {
+    let friend: &Friend = cast_ref_phandle(&friend);
+    let receipt = MessageReceipt::from_ffi(receipt);
+    FFIFuture::from(async move { friend.recall_message(&receipt).await.into() })
+}
+
+pub extern "C" fn friend_recall_message_blocking(
+    manager: Handle,
+    friend: Handle,
+    receipt: FFIMessageReceipt,
+) -> FFIResult<()> {
+    let friend: &Friend = cast_ref_phandle(&friend);
+    let receipt = MessageReceipt::from_ffi(receipt);
+    future_block_on(manager, async move {
+        friend.recall_message(&receipt).await.into()
+    })
+}
+
+/// 返回json格式的[`FriendProfile`](crate::contact::friend::FriendProfile)
+pub extern "C" fn friend_get_profile(friend: Handle) -> FFIFuture<FFIResult<RustString>> {
+    let friend: &Friend = cast_ref_phandle(&friend);
+    FFIFuture::from(async move { profile_to_json(friend).await.into() })
+}
+
+pub extern "C" fn friend_get_profile_blocking(
+    manager: Handle,
+    friend: Handle,
+) -> FFIResult<RustString> {
+    let friend: &Friend = cast_ref_phandle(&friend);
+    future_block_on(manager, async move { profile_to_json(friend).await.into() })
+}
+
+async fn profile_to_json(friend: &Friend) -> AtriResult<RustString> {
+    let profile = friend.profile().await?;
+    let json = serde_json::to_string(&profile).expect("Cannot serialize friend profile");
+
+    Ok(RustString::from(json))
+}
+
+pub extern "C" fn friend_send_forward_message(
+    friend: Handle,
+    msg: RustVec<FFIForwardNode>,
+) -> FFIFuture<FFIResult<FFIMessageReceipt>> {
+    let friend: &Friend = cast_ref_phandle(&friend);
+    let forward = ForwardMessage::from_ffi(msg);
+    FFIFuture::from(async move {
+        friend
+            .send_forward_message(forward)
+            .await
+            .map(MessageReceipt::into_ffi)
+            .into()
+    })
+}
+
+pub extern "C" fn friend_send_forward_message_blocking(
+    manager: Handle,
+    friend: Handle,
+    msg: RustVec<FFIForwardNode>,
+) -> FFIResult<FFIMessageReceipt> {
+    let friend: &Friend = cast_ref_phandle(&friend);
+    let forward = ForwardMessage::from_ffi(msg);
+    future_block_on(manager, async move {
+        friend
+            .send_forward_message(forward)
+            .await
+            .map(MessageReceipt::into_ffi)
+            .into()
+    })
+}
+
 pub extern "C" fn friend_clone(friend: Handle) -> Handle {
     let f: &Friend = cast_ref_phandle(&friend);
     unsafe { friend_to_ptr(f.clone()) }
@@ -1,5 +1,7 @@
 use super::rt::future_block_on;
+use crate::contact::group::announcement::AnnouncementOptions;
 use crate::contact::group::Group;
+use crate::error::{AtriError, AtriResult};
 use crate::message;
 use crate::message::forward::ForwardMessage;
 use crate::message::meta::MessageReceipt;
@@ -10,8 +12,8 @@ use atri_ffi::ffi::ForFFI;
 use atri_ffi::future::FFIFuture;
 use atri_ffi::message::forward::FFIForwardNode;
 use atri_ffi::message::{FFIMessageChain, FFIMessageReceipt};
-use atri_ffi::{Handle, ManagedCloneable, RustStr, RustVec};
-use message::MessageChain;
+use atri_ffi::{Handle, ManagedCloneable, RustStr, RustString, RustVec};
+use message::{MessageChain, MessageElement};
 use std::slice;
 
 pub unsafe fn group_to_handle(group: Group) -> Handle {
@@ -24,6 +26,10 @@ pub unsafe fn group_to_ptr_option(group: Option<Group>) -> Handle {
         .unwrap_or_else(std::ptr::null)
 }
 
+pub extern "C" fn group_find_any(id: i64) -> Handle {
+    unsafe { group_to_ptr_option(Group::find_any(id)) }
+}
+
 pub extern "C" fn group_get_id(group: Handle) -> i64 {
     let group: &Group = cast_ref_phandle(&group);
     group.id()
@@ -55,6 +61,11 @@ pub extern "C" fn group_get_members(group: Handle) -> FFIFuture<RustVec<ManagedC
     })
 }
 
+pub extern "C" fn group_invalidate_members(group: Handle) {
+    let group: &Group = cast_ref_phandle(&group);
+    group.invalidate_members();
+}
+
 pub extern "C" fn group_find_member(group: Handle, id: i64) -> FFIFuture<ManagedCloneable> {
     let group: &Group = cast_ref_phandle(&group);
     FFIFuture::from(async move {
@@ -72,10 +83,13 @@ pub extern "C" fn group_send_message(
 ) -> FFIFuture<FFIResult<FFIMessageReceipt>> {
     FFIFuture::from(async move {
         let group: &Group = cast_ref_phandle(&group);
-        let result = group
-            .send_message(MessageChain::from_ffi(chain))
-            .await
-            .map(MessageReceipt::into_ffi);
+        let result = match MessageChain::try_from_ffi(chain) {
+            Ok(chain) => group
+                .send_message(chain)
+                .await
+                .map(MessageReceipt::into_ffi),
+            Err(e) => Err(e),
+        };
 
         FFIResult::from(result)
     })
@@ -87,7 +101,10 @@ pub extern "C" fn group_send_message_blocking(
     chain: FFIMessageChain,
 ) -> FFIResult<FFIMessageReceipt> {
     let group: &Group = cast_ref_phandle(&group);
-    let chain = MessageChain::from_ffi(chain);
+    let chain = match MessageChain::try_from_ffi(chain) {
+        Ok(chain) => chain,
+        Err(e) => return FFIResult::from(Err::<FFIMessageReceipt, _>(e)),
+    };
 
     future_block_on(manager, async move {
         let result = group
@@ -115,6 +132,109 @@ pub extern "C" fn group_upload_image(
     })
 }
 
+pub extern "C" fn group_upload_voice(
+    group: Handle,
+    silk: RustVec<u8>,
+) -> FFIFuture<FFIResult<ManagedCloneable>> {
+    let group: &Group = cast_ref_phandle(&group);
+    let silk = silk.into_vec();
+    FFIFuture::from(async move {
+        let result = group
+            .upload_voice(silk)
+            .await
+            .map(ManagedCloneable::from_value);
+
+        FFIResult::from(result)
+    })
+}
+
+pub extern "C" fn group_upload_image_from_path(
+    group: Handle,
+    path: RustStr,
+) -> FFIFuture<FFIResult<ManagedCloneable>> {
+    let group: &Group = cast_ref_phandle(&group);
+    let path = path.as_ref().to_owned();
+    FFIFuture::from(async move {
+        let result = group
+            .upload_image_from_path(path)
+            .await
+            .map(ManagedCloneable::from_value);
+
+        FFIResult::from(result)
+    })
+}
+
+pub extern "C" fn group_upload_image_from_path_blocking(
+    manager: Handle,
+    group: Handle,
+    path: RustStr,
+) -> FFIResult<ManagedCloneable> {
+    let group: &Group = cast_ref_phandle(&group);
+    let path = path.as_ref().to_owned();
+
+    future_block_on(manager, async move {
+        let result = group
+            .upload_image_from_path(path)
+            .await
+            .map(ManagedCloneable::from_value);
+
+        FFIResult::from(result)
+    })
+}
+
+pub extern "C" fn group_upload_voice_blocking(
+    manager: Handle,
+    group: Handle,
+    silk: RustVec<u8>,
+) -> FFIResult<ManagedCloneable> {
+    let group: &Group = cast_ref_phandle(&group);
+    let silk = silk.into_vec();
+
+    future_block_on(manager, async move {
+        let result = group
+            .upload_voice(silk)
+            .await
+            .map(ManagedCloneable::from_value);
+
+        FFIResult::from(result)
+    })
+}
+
+/// 发送语音, `voice`为[`group_upload_voice`]返回的语音
+pub extern "C" fn group_send_voice(
+    group: Handle,
+    voice: ManagedCloneable,
+) -> FFIFuture<FFIResult<FFIMessageReceipt>> {
+    let group: &Group = cast_ref_phandle(&group);
+    let chain = MessageChain::from(vec![MessageElement::Voice(unsafe { voice.into_value() })]);
+    FFIFuture::from(async move {
+        let result = group
+            .send_message(chain)
+            .await
+            .map(MessageReceipt::into_ffi);
+
+        FFIResult::from(result)
+    })
+}
+
+pub extern "C" fn group_send_voice_blocking(
+    manager: Handle,
+    group: Handle,
+    voice: ManagedCloneable,
+) -> FFIResult<FFIMessageReceipt> {
+    let group: &Group = cast_ref_phandle(&group);
+    let chain = MessageChain::from(vec![MessageElement::Voice(unsafe { voice.into_value() })]);
+
+    future_block_on(manager, async move {
+        let result = group
+            .send_message(chain)
+            .await
+            .map(MessageReceipt::into_ffi);
+
+        FFIResult::from(result)
+    })
+}
+
 pub extern "C" fn group_upload_image_blocking(
     manager: Handle,
     group: Handle,
@@ -234,3 +354,149 @@ pub extern "C" fn group_clone(group: Handle) -> Handle {
 pub extern "C" fn group_drop(group: Handle) {
     drop::<Group>(unsafe { std::mem::transmute(group) })
 }
+
+pub extern "C" fn group_mute_all(group: Handle, mute: bool) -> FFIFuture<FFIResult<()>> {
+    let group: &Group = cast_ref_phandle(&group);
+    FFIFuture::from(async move { group.mute_all(mute).await.into() })
+}
+
+pub extern "C" fn group_mute_all_blocking(
+    manager: Handle,
+    group: Handle,
+    mute: bool,
+) -> FFIResult<()> {
+    let group: &Group = cast_ref_phandle(&group);
+    future_block_on(manager, async move { group.mute_all(mute).await.into() })
+}
+
+pub extern "C" fn group_set_essence(
+    group: Handle,
+    receipt: FFIMessageReceipt,
+) -> FFIFuture<FFIResult<()>> {
+    let group: &Group = cast_ref_phandle(&group);
+    let receipt = MessageReceipt::from_ffi(receipt);
+    FFIFuture::from(async move { group.set_essence(&receipt).await.into() })
+}
+
+pub extern "C" fn group_set_essence_blocking(
+    manager: Handle,
+    group: Handle,
+    receipt: FFIMessageReceipt,
+) -> FFIResult<()> {
+    let group: &Group = cast_ref_phandle(&group);
+    let receipt = MessageReceipt::from_ffi(receipt);
+    future_block_on(
+        manager,
+        async move { group.set_essence(&receipt).await.into() },
+    )
+}
+
+pub extern "C" fn group_remove_essence(
+    group: Handle,
+    receipt: FFIMessageReceipt,
+) -> FFIFuture<FFIResult<()>> {
+    let group: &Group = cast_ref_phandle(&group);
+    let receipt = MessageReceipt::from_ffi(receipt);
+    FFIFuture::from(async move { group.remove_essence(&receipt).await.into() })
+}
+
+pub extern "C" fn group_remove_essence_blocking(
+    manager: Handle,
+    group: Handle,
+    receipt: FFIMessageReceipt,
+) -> FFIResult<()> {
+    let group: &Group = cast_ref_phandle(&group);
+    let receipt = MessageReceipt::from_ffi(receipt);
+    future_block_on(manager, async move {
+        group.remove_essence(&receipt).await.into()
+    })
+}
+
+pub extern "C" fn group_get_announcements(group: Handle) -> FFIFuture<FFIResult<RustString>> {
+    let group: &Group = cast_ref_phandle(&group);
+    FFIFuture::from(async move { announcements_to_json(group).await.into() })
+}
+
+pub extern "C" fn group_get_announcements_blocking(
+    manager: Handle,
+    group: Handle,
+) -> FFIResult<RustString> {
+    let group: &Group = cast_ref_phandle(&group);
+    future_block_on(
+        manager,
+        async move { announcements_to_json(group).await.into() },
+    )
+}
+
+async fn announcements_to_json(group: &Group) -> AtriResult<RustString> {
+    let list = group.announcements().await?;
+    let json = serde_json::to_string(&list).expect("Cannot serialize announcements");
+
+    Ok(RustString::from(json))
+}
+
+/// `options`为json格式的[`AnnouncementOptions`], 为空时使用默认选项
+pub extern "C" fn group_publish_announcement(
+    group: Handle,
+    text: RustStr,
+    options: RustStr,
+) -> FFIFuture<FFIResult<RustString>> {
+    let group: &Group = cast_ref_phandle(&group);
+    let text = text.as_ref().to_owned();
+    let options = parse_announcement_options(options.as_ref());
+    FFIFuture::from(async move { publish_announcement(group, text, options).await.into() })
+}
+
+pub extern "C" fn group_publish_announcement_blocking(
+    manager: Handle,
+    group: Handle,
+    text: RustStr,
+    options: RustStr,
+) -> FFIResult<RustString> {
+    let group: &Group = cast_ref_phandle(&group);
+    let text = text.as_ref().to_owned();
+    let options = parse_announcement_options(options.as_ref());
+    future_block_on(manager, async move {
+        publish_announcement(group, text, options).await.into()
+    })
+}
+
+fn parse_announcement_options(options: &str) -> AtriResult<AnnouncementOptions> {
+    if options.is_empty() {
+        return Ok(AnnouncementOptions::default());
+    }
+
+    serde_json::from_str(options).map_err(|e| AtriError::from(std::io::Error::from(e)))
+}
+
+async fn publish_announcement(
+    group: &Group,
+    text: String,
+    options: AtriResult<AnnouncementOptions>,
+) -> AtriResult<RustString> {
+    group
+        .publish_announcement(text, options?)
+        .await
+        .map(RustString::from)
+}
+
+pub extern "C" fn group_delete_announcement(
+    group: Handle,
+    id: RustStr,
+) -> FFIFuture<FFIResult<()>> {
+    let group: &Group = cast_ref_phandle(&group);
+    let id = id.as_ref().to_owned();
+    FFIFuture::from(async move { group.delete_announcement(&id).await.into() })
+}
+
+pub extern "C" fn group_delete_announcement_blocking(
+    manager: Handle,
+    group: Handle,
+    id: RustStr,
+) -> FFIResult<()> {
+    let group: &Group = cast_ref_phandle(&group);
+    let id = id.as_ref().to_owned();
+    future_block_on(manager, async move {
+        group.delete_announcement(&id).await.into()
+    })
+}
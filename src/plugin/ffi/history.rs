@@ -0,0 +1,46 @@
+use crate::error::{AtriError, AtriResult};
+use crate::service::history::{history_store, HistoryContact, HistoryQuery};
+use atri_ffi::error::FFIResult;
+use atri_ffi::future::FFIFuture;
+use atri_ffi::{RustStr, RustString};
+use std::io;
+
+fn invalid_input(e: serde_json::Error) -> AtriError {
+    AtriError::IO(io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+/// 以json格式的[`HistoryQuery`]查询, 返回json格式的记录列表
+pub extern "C" fn history_query(query: RustStr) -> FFIFuture<FFIResult<RustString>> {
+    let query = serde_json::from_str::<HistoryQuery>(query.as_ref());
+    FFIFuture::from(async move {
+        let result: AtriResult<RustString> = async {
+            let store = history_store().ok_or(AtriError::NotSupported)?;
+            let records = store.query(query.map_err(invalid_input)?).await?;
+            let json = serde_json::to_string(&records).expect("Serializing error");
+            Ok(json.into())
+        }
+        .await;
+
+        FFIResult::from(result)
+    })
+}
+
+/// 根据序号查找群消息, 未找到时返回`null`
+pub extern "C" fn history_find_group_message(
+    group_id: i64,
+    seq: i32,
+) -> FFIFuture<FFIResult<RustString>> {
+    FFIFuture::from(async move {
+        let result: AtriResult<RustString> = async {
+            let store = history_store().ok_or(AtriError::NotSupported)?;
+            let record = store
+                .find_by_seq(HistoryContact::Group(group_id), seq)
+                .await?;
+            let json = serde_json::to_string(&record).expect("Serializing error");
+            Ok(json.into())
+        }
+        .await;
+
+        FFIResult::from(result)
+    })
+}
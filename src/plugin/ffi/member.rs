@@ -5,6 +5,7 @@ use crate::plugin::ffi::group::group_to_handle;
 use atri_ffi::error::FFIResult;
 use atri_ffi::future::FFIFuture;
 use atri_ffi::{Handle, RustStr};
+use std::time::Duration;
 
 pub extern "C" fn named_member_get_id(named: *const ()) -> i64 {
     let named: &NamedMember = cast_ref(named);
@@ -21,6 +22,41 @@ pub extern "C" fn named_member_get_card_name(named: *const ()) -> RustStr {
     RustStr::from(named.card_name())
 }
 
+pub extern "C" fn named_member_get_permission(named: *const ()) -> u8 {
+    let named: &NamedMember = cast_ref(named);
+    named.permission() as u8
+}
+
+pub extern "C" fn named_member_is_admin(named: *const ()) -> bool {
+    let named: &NamedMember = cast_ref(named);
+    named.is_admin()
+}
+
+pub extern "C" fn named_member_get_join_time(named: *const ()) -> i64 {
+    let named: &NamedMember = cast_ref(named);
+    named.join_time()
+}
+
+pub extern "C" fn named_member_get_last_speak_time(named: *const ()) -> i64 {
+    let named: &NamedMember = cast_ref(named);
+    named.last_speak_time()
+}
+
+pub extern "C" fn named_member_get_special_title(named: *const ()) -> RustStr {
+    let named: &NamedMember = cast_ref(named);
+    RustStr::from(named.special_title())
+}
+
+pub extern "C" fn named_member_get_level(named: *const ()) -> u16 {
+    let named: &NamedMember = cast_ref(named);
+    named.level()
+}
+
+pub extern "C" fn named_member_get_mute_expire_time(named: *const ()) -> i64 {
+    let named: &NamedMember = cast_ref(named);
+    named.mute_expire_time()
+}
+
 pub extern "C" fn named_member_get_group(named: *const ()) -> Handle {
     let named: &NamedMember = cast_ref(named);
     unsafe { group_to_handle(named.group()) }
@@ -51,3 +87,94 @@ pub extern "C" fn named_member_change_card_name_blocking(
         FFIResult::from(result)
     })
 }
+
+pub extern "C" fn named_member_mute(named: *const (), seconds: u64) -> FFIFuture<FFIResult<()>> {
+    FFIFuture::from(async move {
+        let named: &NamedMember = cast_ref(named);
+        let result = named.mute(Duration::from_secs(seconds)).await;
+        FFIResult::from(result)
+    })
+}
+
+pub extern "C" fn named_member_mute_blocking(
+    manager: *const (),
+    named: *const (),
+    seconds: u64,
+) -> FFIResult<()> {
+    let named: &NamedMember = cast_ref(named);
+
+    future_block_on(manager, async move {
+        let result = named.mute(Duration::from_secs(seconds)).await;
+        FFIResult::from(result)
+    })
+}
+
+pub extern "C" fn named_member_unmute(named: *const ()) -> FFIFuture<FFIResult<()>> {
+    FFIFuture::from(async move {
+        let named: &NamedMember = cast_ref(named);
+        let result = named.unmute().await;
+        FFIResult::from(result)
+    })
+}
+
+pub extern "C" fn named_member_unmute_blocking(
+    manager: *const (),
+    named: *const (),
+) -> FFIResult<()> {
+    let named: &NamedMember = cast_ref(named);
+
+    future_block_on(manager, async move {
+        let result = named.unmute().await;
+        FFIResult::from(result)
+    })
+}
+
+pub extern "C" fn named_member_set_admin(
+    named: *const (),
+    admin: bool,
+) -> FFIFuture<FFIResult<()>> {
+    FFIFuture::from(async move {
+        let named: &NamedMember = cast_ref(named);
+        let result = named.set_admin(admin).await;
+        FFIResult::from(result)
+    })
+}
+
+pub extern "C" fn named_member_set_admin_blocking(
+    manager: *const (),
+    named: *const (),
+    admin: bool,
+) -> FFIResult<()> {
+    let named: &NamedMember = cast_ref(named);
+
+    future_block_on(manager, async move {
+        let result = named.set_admin(admin).await;
+        FFIResult::from(result)
+    })
+}
+
+pub extern "C" fn named_member_set_special_title(
+    named: *const (),
+    title: RustStr,
+) -> FFIFuture<FFIResult<()>> {
+    let title = title.as_ref().to_owned();
+    FFIFuture::from(async move {
+        let named: &NamedMember = cast_ref(named);
+        let result = named.set_special_title(title).await;
+        FFIResult::from(result)
+    })
+}
+
+pub extern "C" fn named_member_set_special_title_blocking(
+    manager: *const (),
+    named: *const (),
+    title: RustStr,
+) -> FFIResult<()> {
+    let named: &NamedMember = cast_ref(named);
+    let title = title.as_ref().to_owned();
+
+    future_block_on(manager, async move {
+        let result = named.set_special_title(title).await;
+        FFIResult::from(result)
+    })
+}
@@ -1,33 +1,224 @@
-use super::cast_ref;
+use super::{cast_ref, cast_ref_phandle};
+use crate::message::forward::{ForwardCard, ForwardMessage};
 use crate::message::image::Image;
-use crate::message::MessageChain;
+use crate::message::market_face::{Dice, FingerGuessing};
+use crate::message::music::MusicShare;
+use crate::message::poke::Poke;
+use crate::message::voice::Voice;
+use crate::message::{MessageChain, MessageElement};
+use crate::Client;
 use atri_ffi::error::FFIResult;
 use atri_ffi::ffi::ForFFI;
-use atri_ffi::message::FFIMessageChain;
-use atri_ffi::{Managed, RustStr, RustString};
+use atri_ffi::future::FFIFuture;
+use atri_ffi::message::forward::FFIForwardNode;
+use atri_ffi::message::{FFIMessageChain, FFIMessageElement};
+use atri_ffi::{Handle, RustStr, RustString, RustVec};
+use ricq::msg::elem::RQElem;
 
 pub extern "C" fn message_chain_to_json(chain: FFIMessageChain) -> RustString {
     let chain = MessageChain::from_ffi(chain);
     chain.to_json().into()
 }
 
+/// 包含未知元素, 可由`message_chain_from_json`还原
+pub extern "C" fn message_chain_to_json_lossless(chain: FFIMessageChain) -> FFIResult<RustString> {
+    let chain = MessageChain::from_ffi(chain);
+    chain.to_json_lossless().map(RustString::from).into()
+}
+
 pub extern "C" fn message_chain_from_json(json: RustStr) -> FFIResult<FFIMessageChain> {
     MessageChain::from_json(json.as_ref())
         .map(MessageChain::into_ffi)
         .into()
 }
 
+pub extern "C" fn message_chain_to_code(chain: FFIMessageChain) -> RustString {
+    let chain = MessageChain::from_ffi(chain);
+    chain.to_code().into()
+}
+
+pub extern "C" fn message_chain_from_code(code: RustStr) -> FFIResult<FFIMessageChain> {
+    MessageChain::from_code(code.as_ref())
+        .map(MessageChain::into_ffi)
+        .into()
+}
+
+pub extern "C" fn message_chain_to_cq_code(chain: FFIMessageChain) -> RustString {
+    let chain = MessageChain::from_ffi(chain);
+    chain.to_cq_code().into()
+}
+
+pub extern "C" fn message_chain_from_cq_code(code: RustStr) -> FFIResult<FFIMessageChain> {
+    MessageChain::from_cq_code(code.as_ref())
+        .map(MessageChain::into_ffi)
+        .into()
+}
+
 pub extern "C" fn image_get_id(img: *const ()) -> RustStr {
     let img: &Image = cast_ref(img);
     RustStr::from(img.id())
 }
 
-pub extern "C" fn _image_to_flash(img: Managed) {
-    let img: Image = unsafe { img.into_value() };
-    img.flash();
+pub extern "C" fn image_to_flash(img: *const ()) -> FFIMessageElement {
+    let img: &Image = cast_ref(img);
+    img.clone().into_flash().into_ffi()
 }
 
 pub extern "C" fn image_get_url(img: *const ()) -> RustString {
     let img: &Image = cast_ref(img);
     RustString::from(img.url())
 }
+
+/// 从未知消息元素中读取转发卡片的资源id, 不是转发卡片时返回空字符串
+pub extern "C" fn forward_get_res_id(elem: *const ()) -> RustString {
+    let elem: &RQElem = cast_ref(elem);
+    let res_id = match elem {
+        RQElem::RichMsg(rich) => ForwardCard::from_rich_msg(rich).map(|card| card.res_id),
+        _ => None,
+    };
+
+    RustString::from(res_id.unwrap_or_default())
+}
+
+pub extern "C" fn forward_download(
+    client: Handle,
+    res_id: RustStr,
+) -> FFIFuture<FFIResult<RustVec<FFIForwardNode>>> {
+    let client: &Client = cast_ref_phandle(&client);
+    let card = ForwardCard {
+        res_id: res_id.as_ref().to_owned(),
+        total: 0,
+        preview: vec![],
+    };
+
+    FFIFuture::from(async move {
+        card.download(client)
+            .await
+            .map(ForwardMessage::into_ffi)
+            .into()
+    })
+}
+
+pub extern "C" fn voice_get_name(voice: *const ()) -> RustString {
+    let voice: &Voice = cast_ref(voice);
+    RustString::from(voice.name())
+}
+
+pub extern "C" fn voice_get_url(
+    voice: *const (),
+    client: Handle,
+) -> FFIFuture<FFIResult<RustString>> {
+    let voice: Voice = cast_ref::<Voice>(voice).clone();
+    let client: &Client = cast_ref_phandle(&client);
+    FFIFuture::from(async move { voice.url(client).await.map(RustString::from).into() })
+}
+
+/// 从未知消息元素中读取商城表情的名称, 不是商城表情时返回空字符串
+pub extern "C" fn market_face_get_name(elem: *const ()) -> RustStr {
+    let elem: &RQElem = cast_ref(elem);
+    match elem {
+        RQElem::MarketFace(face) => RustStr::from(face.name.as_str()),
+        _ => RustStr::from(""),
+    }
+}
+
+/// 点数超出范围时取最近的有效点数
+pub extern "C" fn dice_new(value: i32) -> FFIMessageElement {
+    let dice = Dice {
+        value: value.clamp(1, 6),
+    };
+    MessageElement::Dice(dice).into_ffi()
+}
+
+/// 从未知消息元素中读取骰子的点数, 不是骰子时返回0
+pub extern "C" fn dice_get_value(elem: *const ()) -> i32 {
+    let elem: &RQElem = cast_ref(elem);
+    match elem {
+        RQElem::Dice(dice) => dice.value,
+        _ => 0,
+    }
+}
+
+/// 0: 石头, 1: 剪刀, 其他: 布
+pub extern "C" fn finger_guessing_new(value: u8) -> FFIMessageElement {
+    let value = match value {
+        0 => FingerGuessing::Rock,
+        1 => FingerGuessing::Scissors,
+        _ => FingerGuessing::Paper,
+    };
+    MessageElement::FingerGuessing(value).into_ffi()
+}
+
+/// 从未知消息元素中读取猜拳的结果, 0: 石头, 1: 剪刀, 2: 布, 不是猜拳时返回255
+pub extern "C" fn finger_guessing_get_value(elem: *const ()) -> u8 {
+    let elem: &RQElem = cast_ref(elem);
+    match elem {
+        RQElem::FingerGuessing(value) => match FingerGuessing::from(value.clone()) {
+            FingerGuessing::Rock => 0,
+            FingerGuessing::Scissors => 1,
+            FingerGuessing::Paper => 2,
+        },
+        _ => u8::MAX,
+    }
+}
+
+pub extern "C" fn poke_new(name: RustStr, poke_type: i32, id: i32) -> FFIMessageElement {
+    MessageElement::Poke(Poke::new(name.as_ref(), poke_type, id)).into_ffi()
+}
+
+/// 从未知消息元素中读取戳一戳的名称, 不是戳一戳时返回空字符串
+pub extern "C" fn poke_get_name(elem: *const ()) -> RustString {
+    let elem: &RQElem = cast_ref(elem);
+    let name = match MessageElement::from(elem.clone()) {
+        MessageElement::Poke(poke) => poke.name,
+        _ => String::new(),
+    };
+
+    RustString::from(name)
+}
+
+pub extern "C" fn light_app_new(content: RustStr) -> FFIMessageElement {
+    MessageElement::LightApp(content.as_ref().to_owned()).into_ffi()
+}
+
+/// 从未知消息元素中读取json卡片的内容, 不是json卡片时返回空字符串
+pub extern "C" fn light_app_get_content(elem: *const ()) -> RustStr {
+    let elem: &RQElem = cast_ref(elem);
+    match elem {
+        RQElem::LightApp(app) => RustStr::from(app.content.as_str()),
+        _ => RustStr::from(""),
+    }
+}
+
+pub extern "C" fn xml_new(service_id: i32, content: RustStr) -> FFIMessageElement {
+    MessageElement::Xml {
+        service_id,
+        content: content.as_ref().to_owned(),
+    }
+    .into_ffi()
+}
+
+/// 从未知消息元素中读取xml卡片的服务id, 不是xml卡片时返回0
+pub extern "C" fn xml_get_service_id(elem: *const ()) -> i32 {
+    let elem: &RQElem = cast_ref(elem);
+    match elem {
+        RQElem::RichMsg(rich) => rich.service_id,
+        _ => 0,
+    }
+}
+
+/// 从未知消息元素中读取xml卡片的内容, 不是xml卡片时返回空字符串
+pub extern "C" fn xml_get_content(elem: *const ()) -> RustStr {
+    let elem: &RQElem = cast_ref(elem);
+    match elem {
+        RQElem::RichMsg(rich) => RustStr::from(rich.template1.as_str()),
+        _ => RustStr::from(""),
+    }
+}
+
+/// 从json构造音乐分享卡片, 格式同[`MusicShare`]的序列化结果
+pub extern "C" fn music_share_from_json(json: RustStr) -> FFIResult<FFIMessageElement> {
+    serde_json::from_str::<MusicShare>(json.as_ref())
+        .map(|music| MessageElement::from(music).into_ffi())
+        .into()
+}
@@ -5,11 +5,14 @@ pub mod env;
 pub mod event;
 pub mod friend;
 pub mod group;
+pub mod history;
 pub mod listener;
 pub mod log;
 pub mod member;
 pub mod message;
+pub mod render;
 pub mod rt;
+pub mod scheduler;
 pub mod string;
 
 fn cast_ref<'a, T>(ptr: *const ()) -> &'a T {
@@ -0,0 +1,26 @@
+use crate::message::render::{builtin_renderer, register_element_renderer, PlainTextRenderer};
+use crate::message::{MessageChain, MessageElement};
+use atri_ffi::closure::FFIFn;
+use atri_ffi::ffi::ForFFI;
+use atri_ffi::message::{FFIMessageChain, FFIMessageElement};
+use atri_ffi::{Managed, RustStr, RustString};
+
+/// 以内置格式渲染, 未知的格式按纯文本渲染
+pub extern "C" fn message_chain_render(chain: FFIMessageChain, format: RustStr) -> RustString {
+    let chain = MessageChain::from_ffi(chain);
+    let renderer = builtin_renderer(format.as_ref()).unwrap_or(&PlainTextRenderer);
+    renderer.render(&chain).into()
+}
+
+/// 注册未知元素的渲染方式, 回调返回空字符串表示不处理
+pub extern "C" fn register_unknown_element_renderer(
+    format: RustStr,
+    f: FFIFn<FFIMessageElement, RustString>,
+) -> Managed {
+    let guard = register_element_renderer(format.as_ref(), move |elem: &MessageElement| {
+        let s = String::from(f.invoke(elem.clone().into_ffi()));
+        (!s.is_empty()).then_some(s)
+    });
+
+    Managed::from_value(guard)
+}
@@ -0,0 +1,96 @@
+use super::cast_ref;
+use crate::service::plugin::PluginManager;
+use crate::service::scheduler::{scheduler, MissedRunPolicy, Schedule};
+use atri_ffi::closure::FFIFn;
+use atri_ffi::error::FFIResult;
+use atri_ffi::future::FFIFuture;
+use atri_ffi::RustStr;
+use std::time::{Duration, UNIX_EPOCH};
+
+/// 在插件的运行时中开始调度, 名称不为空时保存下次运行时间, 并以插件名为前缀
+fn start_task(
+    manager: *const (),
+    plugin: usize,
+    schedule: Schedule,
+    name: &str,
+    missed: u8,
+    f: FFIFn<(), FFIFuture<()>>,
+) -> u64 {
+    let manager: &PluginManager = cast_ref(manager);
+    let _guard = manager.async_runtime().enter();
+
+    let mut builder = scheduler()
+        .task(schedule)
+        .owner(plugin)
+        .missed_run_policy(MissedRunPolicy::from(missed));
+
+    if !name.is_empty() {
+        let plugin_name = manager
+            .plugins()
+            .into_iter()
+            .find(|p| p.handle() == plugin)
+            .map(|p| p.name().to_owned())
+            .unwrap_or_default();
+
+        builder = builder.name(format!("{plugin_name}::{name}")).persist(true);
+    }
+
+    builder.start(move || f.invoke(())).id()
+}
+
+pub extern "C" fn scheduler_add_cron(
+    manager: *const (),
+    plugin: usize,
+    name: RustStr,
+    expr: RustStr,
+    missed: u8,
+    f: FFIFn<(), FFIFuture<()>>,
+) -> FFIResult<u64> {
+    let result = Schedule::cron(expr.as_ref())
+        .map(|schedule| start_task(manager, plugin, schedule, name.as_ref(), missed, f));
+
+    FFIResult::from(result)
+}
+
+pub extern "C" fn scheduler_add_interval(
+    manager: *const (),
+    plugin: usize,
+    name: RustStr,
+    millis: u64,
+    missed: u8,
+    f: FFIFn<(), FFIFuture<()>>,
+) -> FFIResult<u64> {
+    let result = Schedule::interval(Duration::from_millis(millis))
+        .map(|schedule| start_task(manager, plugin, schedule, name.as_ref(), missed, f));
+
+    FFIResult::from(result)
+}
+
+pub extern "C" fn scheduler_add_delay(
+    manager: *const (),
+    plugin: usize,
+    millis: u64,
+    f: FFIFn<(), FFIFuture<()>>,
+) -> u64 {
+    start_task(
+        manager,
+        plugin,
+        Schedule::Once(Duration::from_millis(millis)),
+        "",
+        0,
+        f,
+    )
+}
+
+pub extern "C" fn scheduler_cancel(id: u64) -> bool {
+    scheduler().cancel(id)
+}
+
+/// 下次运行时间(Unix毫秒), 任务不存在时为-1
+pub extern "C" fn scheduler_next_run(id: u64) -> i64 {
+    scheduler()
+        .next_run(id)
+        .and_then(|at| at.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(-1)
+}
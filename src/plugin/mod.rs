@@ -6,26 +6,42 @@ use crate::plugin::ffi::group::{group_clone, group_drop, group_upload_image_ex};
 use crate::plugin::ffi::rt::{plugin_manager_block_on, plugin_manager_spawn};
 use crate::plugin::ffi::string::{c_str_cvt, rust_str_cvt, rust_string_drop};
 use ffi::client::{
-    client_find_friend, client_find_group, client_get_friends, client_get_groups, client_get_id,
+    client_find_friend, client_find_group, client_get_all_friends, client_get_all_groups,
+    client_get_friend_categories, client_get_friends, client_get_groups, client_get_id,
     client_get_list, client_get_nickname, find_client,
 };
 use ffi::env::env_get_workspace;
 use ffi::event::{
     event_intercept, event_is_intercepted, friend_message_event_get_friend,
-    friend_message_event_get_message, group_message_event_get_group,
-    group_message_event_get_message, group_message_event_get_sender,
+    friend_message_event_get_message, friend_message_event_get_voice,
+    group_file_upload_event_get_download_url, group_file_upload_event_get_file,
+    group_file_upload_event_get_group, group_file_upload_event_get_uploader,
+    group_message_event_get_group, group_message_event_get_message, group_message_event_get_sender,
+    group_message_event_get_voice,
 };
 use ffi::friend::{
-    friend_get_client, friend_get_id, friend_get_nickname, friend_send_message,
-    friend_send_message_blocking, friend_upload_image, friend_upload_image_blocking,
+    friend_find_any, friend_get_category_id, friend_get_client, friend_get_id, friend_get_nickname,
+    friend_get_profile, friend_get_profile_blocking, friend_get_remark, friend_recall_message,
+    friend_recall_message_blocking, friend_send_forward_message,
+    friend_send_forward_message_blocking, friend_send_message, friend_send_message_blocking,
+    friend_send_voice, friend_send_voice_blocking, friend_upload_image,
+    friend_upload_image_blocking, friend_upload_image_from_path,
+    friend_upload_image_from_path_blocking, friend_upload_voice, friend_upload_voice_blocking,
 };
 use ffi::group::{
-    group_change_name, group_change_name_blocking, group_find_member, group_get_client,
-    group_get_id, group_get_members, group_get_name, group_invite, group_invite_blocking,
-    group_quit, group_quit_blocking, group_send_forward_message,
-    group_send_forward_message_blocking, group_send_message, group_send_message_blocking,
-    group_upload_image, group_upload_image_blocking,
+    group_change_name, group_change_name_blocking, group_delete_announcement,
+    group_delete_announcement_blocking, group_find_any, group_find_member, group_get_announcements,
+    group_get_announcements_blocking, group_get_client, group_get_id, group_get_members,
+    group_get_name, group_invalidate_members, group_invite, group_invite_blocking, group_mute_all,
+    group_mute_all_blocking, group_publish_announcement, group_publish_announcement_blocking,
+    group_quit, group_quit_blocking, group_remove_essence, group_remove_essence_blocking,
+    group_send_forward_message, group_send_forward_message_blocking, group_send_message,
+    group_send_message_blocking, group_send_voice, group_send_voice_blocking, group_set_essence,
+    group_set_essence_blocking, group_upload_image, group_upload_image_blocking,
+    group_upload_image_from_path, group_upload_image_from_path_blocking, group_upload_voice,
+    group_upload_voice_blocking,
 };
+use ffi::history::{history_find_group_message, history_query};
 use ffi::listener::{
     listener_next_event_with_priority, listener_next_event_with_priority_blocking, new_listener,
     new_listener_c_func, new_listener_closure,
@@ -34,9 +50,26 @@ use ffi::log::log;
 use ffi::member::{
     named_member_change_card_name, named_member_change_card_name_blocking,
     named_member_get_card_name, named_member_get_group, named_member_get_id,
-    named_member_get_nickname,
+    named_member_get_join_time, named_member_get_last_speak_time, named_member_get_level,
+    named_member_get_mute_expire_time, named_member_get_nickname, named_member_get_permission,
+    named_member_get_special_title, named_member_is_admin, named_member_mute,
+    named_member_mute_blocking, named_member_set_admin, named_member_set_admin_blocking,
+    named_member_set_special_title, named_member_set_special_title_blocking, named_member_unmute,
+    named_member_unmute_blocking,
+};
+use ffi::message::{
+    dice_get_value, dice_new, finger_guessing_get_value, finger_guessing_new, forward_download,
+    forward_get_res_id, image_get_id, image_get_url, image_to_flash, light_app_get_content,
+    light_app_new, market_face_get_name, message_chain_from_code, message_chain_from_cq_code,
+    message_chain_from_json, message_chain_to_code, message_chain_to_cq_code,
+    message_chain_to_json, message_chain_to_json_lossless, music_share_from_json, poke_get_name,
+    poke_new, voice_get_name, voice_get_url, xml_get_content, xml_get_service_id, xml_new,
+};
+use ffi::render::{message_chain_render, register_unknown_element_renderer};
+use ffi::scheduler::{
+    scheduler_add_cron, scheduler_add_delay, scheduler_add_interval, scheduler_cancel,
+    scheduler_next_run,
 };
-use ffi::message::{image_get_id, image_get_url, message_chain_from_json, message_chain_to_json};
 use tracing::error;
 
 pub extern "C" fn plugin_get_function(sig: u16) -> *const () {
@@ -82,6 +115,9 @@ pub extern "C" fn plugin_get_function(sig: u16) -> *const () {
         305 => client_find_friend,
         306 => client_get_groups,
         307 => client_get_friends,
+        308 => client_get_all_groups,
+        309 => client_get_all_friends,
+        310 => client_get_friend_categories,
 
         // client handle
         320 => client_clone,
@@ -101,6 +137,14 @@ pub extern "C" fn plugin_get_function(sig: u16) -> *const () {
         409 => group_change_name,
         410 => group_send_forward_message,
         411 => group_invite,
+        412 => group_find_any,
+        413 => group_invalidate_members,
+        414 => group_mute_all,
+        415 => group_set_essence,
+        416 => group_remove_essence,
+        417 => group_get_announcements,
+        418 => group_publish_announcement,
+        419 => group_delete_announcement,
 
         //group handle
         420 => group_clone,
@@ -113,16 +157,41 @@ pub extern "C" fn plugin_get_function(sig: u16) -> *const () {
         459 => group_change_name_blocking,
         460 => group_send_forward_message_blocking,
         461 => group_invite_blocking,
+        464 => group_mute_all_blocking,
+        465 => group_set_essence_blocking,
+        466 => group_remove_essence_blocking,
+        467 => group_get_announcements_blocking,
+        468 => group_publish_announcement_blocking,
+        469 => group_delete_announcement_blocking,
 
         // extension
         480 => group_upload_image_ex,
 
+        // group
+        440 => group_upload_voice,
+        441 => group_send_voice,
+        442 => group_upload_image_from_path,
+
+        // blocking api
+        490 => group_upload_voice_blocking,
+        491 => group_send_voice_blocking,
+        492 => group_upload_image_from_path_blocking,
+
         // friend
         500 => friend_get_id,
         501 => friend_get_nickname,
         502 => friend_get_client,
         503 => friend_send_message,
         504 => friend_upload_image,
+        505 => friend_find_any,
+        506 => friend_get_remark,
+        507 => friend_recall_message,
+        508 => friend_get_category_id,
+        509 => friend_get_profile,
+        510 => friend_send_forward_message,
+        511 => friend_upload_voice,
+        512 => friend_send_voice,
+        513 => friend_upload_image_from_path,
 
         // friend handle
         520 => friend_clone,
@@ -131,6 +200,12 @@ pub extern "C" fn plugin_get_function(sig: u16) -> *const () {
         // blocking api
         553 => friend_send_message_blocking,
         554 => friend_upload_image_blocking,
+        557 => friend_recall_message_blocking,
+        559 => friend_get_profile_blocking,
+        560 => friend_send_forward_message_blocking,
+        561 => friend_upload_voice_blocking,
+        562 => friend_send_voice_blocking,
+        563 => friend_upload_image_from_path_blocking,
 
         // extension
         580 => friend_upload_image_ex,
@@ -141,24 +216,74 @@ pub extern "C" fn plugin_get_function(sig: u16) -> *const () {
         602 => named_member_get_card_name,
         603 => named_member_get_group,
         604 => named_member_change_card_name,
+        605 => named_member_get_permission,
+        606 => named_member_is_admin,
+        607 => named_member_get_join_time,
+        608 => named_member_get_last_speak_time,
+        609 => named_member_get_special_title,
+        610 => named_member_get_level,
+        611 => named_member_get_mute_expire_time,
+        612 => named_member_mute,
+        613 => named_member_unmute,
+        614 => named_member_set_admin,
+        615 => named_member_set_special_title,
 
         // blocking api
         654 => named_member_change_card_name_blocking,
+        662 => named_member_mute_blocking,
+        663 => named_member_unmute_blocking,
+        664 => named_member_set_admin_blocking,
+        665 => named_member_set_special_title_blocking,
 
 
         // group message event
         10000 => group_message_event_get_group,
         10001 => group_message_event_get_message,
         10002 => group_message_event_get_sender,
+        10003 => group_message_event_get_voice,
 
         // friend message event
         10100 => friend_message_event_get_friend,
         10101 => friend_message_event_get_message,
+        10102 => friend_message_event_get_voice,
+
+        // group file upload event
+        10200 => group_file_upload_event_get_group,
+        10201 => group_file_upload_event_get_uploader,
+        10202 => group_file_upload_event_get_file,
+        10203 => group_file_upload_event_get_download_url,
 
         2000 => image_get_id,
-        // flash => 2001
+        2001 => image_to_flash,
         2002 => image_get_url,
 
+        // forward
+        2100 => forward_get_res_id,
+        2101 => forward_download,
+
+        // voice
+        2200 => voice_get_name,
+        2201 => voice_get_url,
+
+        // market face
+        2400 => market_face_get_name,
+        2410 => dice_new,
+        2411 => dice_get_value,
+        2420 => finger_guessing_new,
+        2421 => finger_guessing_get_value,
+
+        // poke
+        2430 => poke_new,
+        2431 => poke_get_name,
+
+        // rich message
+        2500 => light_app_new,
+        2501 => light_app_get_content,
+        2510 => xml_new,
+        2511 => xml_get_service_id,
+        2512 => xml_get_content,
+        2520 => music_share_from_json,
+
         // log
         20000 => log,
 
@@ -168,6 +293,26 @@ pub extern "C" fn plugin_get_function(sig: u16) -> *const () {
         // serialize
         30100 => message_chain_to_json,
         30101 => message_chain_from_json,
+        30102 => message_chain_to_json_lossless,
+        30103 => message_chain_to_code,
+        30104 => message_chain_from_code,
+        30105 => message_chain_to_cq_code,
+        30106 => message_chain_from_cq_code,
+
+        // history
+        30200 => history_query,
+        30201 => history_find_group_message,
+
+        // render
+        30300 => message_chain_render,
+        30301 => register_unknown_element_renderer,
+
+        // scheduler
+        30400 => scheduler_add_cron,
+        30401 => scheduler_add_interval,
+        30402 => scheduler_add_delay,
+        30403 => scheduler_cancel,
+        30404 => scheduler_next_run,
 
         // ffi
         30500 => rust_str_cvt,
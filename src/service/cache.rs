@@ -0,0 +1,79 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use tracing::{info, warn};
+
+use crate::client::WeakClient;
+use crate::config::cache::{CacheConfig, DEFAULT_CONFIG};
+use crate::config::service::ServiceConfig;
+use crate::Client;
+
+static CACHE_CONFIG: OnceLock<CacheConfig> = OnceLock::new();
+
+pub fn init_cache_service() {
+    cache_config();
+}
+
+pub fn cache_config() -> &'static CacheConfig {
+    CACHE_CONFIG.get_or_init(|| ServiceConfig::<CacheConfig>::new("cache", DEFAULT_CONFIG).read())
+}
+
+/// 登录后准备客户端的联系人缓存
+///
+/// 若存在未过期的缓存快照则直接使用, 并在后台刷新;
+/// 否则同步刷新好友列表与群列表. 之后每隔[`CacheConfig::max_staleness`]在后台刷新一次
+pub async fn prepare_contacts(client: &Client) {
+    let conf = cache_config();
+
+    let fresh = if conf.persist {
+        match client.load_contact_snapshot().await {
+            Some(age) if age < conf.max_staleness() => {
+                info!("{}已加载联系人缓存", client);
+                true
+            }
+            _ => false,
+        }
+    } else {
+        false
+    };
+
+    let mut delay = Duration::ZERO;
+    if !fresh {
+        refresh_contacts(client).await;
+        delay = conf.max_staleness();
+    }
+
+    let weak = WeakClient::new(client);
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(delay).await;
+            delay = cache_config().max_staleness();
+
+            let Some(client) = weak.upgrade() else {
+                return;
+            };
+
+            if client.is_online() {
+                refresh_contacts(&client).await;
+            }
+        }
+    });
+}
+
+/// 刷新好友列表与群列表, 并保存缓存快照
+pub async fn refresh_contacts(client: &Client) {
+    if let Err(e) = client.refresh_friend_list().await {
+        warn!("{}刷新好友列表失败: {:?}", client, e);
+    }
+    if let Err(e) = client.refresh_group_list().await {
+        warn!("{}刷新群列表失败: {:?}", client, e);
+    }
+
+    if cache_config().persist {
+        let c = client.clone();
+        let result = tokio::task::spawn_blocking(move || c.save_contact_snapshot()).await;
+        if let Ok(Err(e)) = result {
+            warn!("{}保存联系人缓存失败: {}", client, e);
+        }
+    }
+}
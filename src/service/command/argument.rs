@@ -18,38 +18,100 @@ impl CommandArg for Client {
     fn from_str(arg: &str) -> CommandResult<Self> {
         let id = i64::from_str_radix(arg, 10)?;
 
-        Client::find(id).ok_or_else(|| CommandError::execute_error(format!("无法找到客户端: {id}")))
+        find_client(id)
     }
 }
 
 impl CommandArg for Friend {
+    /// 接受`Friend`或`Client:Friend`
     fn from_str(arg: &str) -> CommandResult<Self> {
-        let id = i64::from_str_radix(arg, 10)?;
+        let (client, id) = parse_contact(arg)?;
+
+        if let Some(client) = client {
+            let client = find_client(client)?;
 
-        if let [client] = &Client::list()[..] {
             return client
                 .find_friend(id)
-                .ok_or_else(|| CommandError::execute_error(format!("无法找到好友: {id}")));
+                .ok_or_else(|| CommandError::execute_error(format!("{client}无法找到好友: {id}")));
         }
 
-        Err(CommandError::execute_error(
-            "无法从多个客户端中找到好友, 请指定客户端, 例 Client:Friend",
-        ))
+        Friend::find_any(id)
+            .ok_or_else(|| CommandError::execute_error(format!("无法找到好友: {id}")))
     }
 }
 
 impl CommandArg for Group {
+    /// 接受`Group`或`Client:Group`
     fn from_str(arg: &str) -> CommandResult<Self> {
-        let id = i64::from_str_radix(arg, 10)?;
+        let (client, id) = parse_contact(arg)?;
+
+        if let Some(client) = client {
+            let client = find_client(client)?;
 
-        if let [client] = &Client::list()[..] {
             return client
                 .find_group(id)
-                .ok_or_else(|| CommandError::execute_error(format!("无法找到群: {id}")));
+                .ok_or_else(|| CommandError::execute_error(format!("{client}无法找到群: {id}")));
         }
 
-        Err(CommandError::execute_error(
-            "无法从多个客户端中找到群, 请指定客户端, 例 Client:Group",
-        ))
+        Group::find_any(id).ok_or_else(|| CommandError::execute_error(format!("无法找到群: {id}")))
+    }
+}
+
+fn find_client(id: i64) -> CommandResult<Client> {
+    Client::find(id).ok_or_else(|| CommandError::execute_error(format!("无法找到客户端: {id}")))
+}
+
+/// 解析`Contact`或`Client:Contact`, 返回(客户端账号, 联系人账号)
+fn parse_contact(arg: &str) -> CommandResult<(Option<i64>, i64)> {
+    match arg.split_once(':') {
+        Some((client, contact)) => Ok((
+            Some(i64::from_str_radix(client, 10)?),
+            i64::from_str_radix(contact, 10)?,
+        )),
+        None => Ok((None, i64::from_str_radix(arg, 10)?)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_contact, CommandArg};
+    use crate::contact::group::Group;
+    use crate::service::command::CommandError;
+
+    #[test]
+    fn client_contact() {
+        assert_eq!(parse_contact("114514").unwrap(), (None, 114514));
+        assert_eq!(
+            parse_contact("1919810:114514").unwrap(),
+            (Some(1919810), 114514)
+        );
+    }
+
+    #[test]
+    fn missing_client() {
+        let Err(CommandError::ExecuteError(msg)) =
+            <Group as CommandArg>::from_str("1919810:114514")
+        else {
+            panic!("client should not be found");
+        };
+        assert!(msg.contains("1919810"));
+    }
+
+    #[test]
+    fn malformed() {
+        for arg in [
+            "",
+            "abc",
+            ":114514",
+            "1919810:",
+            "a:114514",
+            "1919810:b",
+            "1:2:3",
+        ] {
+            assert!(
+                matches!(parse_contact(arg), Err(CommandError::IllegalArgument)),
+                "{arg}"
+            );
+        }
     }
 }
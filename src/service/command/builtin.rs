@@ -1,8 +1,11 @@
-use crate::service::command::{CommandError, CommandResult, PLUGIN_COMMAND};
+use crate::client::crypto;
+use crate::config;
+use crate::service::command::{CommandError, CommandResult, ENCRYPT_COMMAND, PLUGIN_COMMAND};
 use crate::service::plugin::PluginManager;
+use regex::{Captures, Regex};
 use std::collections::hash_map::Entry;
-use std::mem;
-use tracing::info;
+use std::{fs, mem};
+use tracing::{info, warn};
 
 pub fn handle_plugin_command(
     plugin_command: &str,
@@ -61,3 +64,58 @@ pub fn handle_plugin_command(
 
     Ok(())
 }
+
+/// 使用已配置的密钥加密所有客户端的Token, 设备信息, 以及登录配置中的密码
+pub fn handle_encrypt_command(encrypt_command: &str) -> CommandResult<()> {
+    if !encrypt_command[ENCRYPT_COMMAND.len()..].trim().is_empty() {
+        return Err(CommandError::IllegalArgument);
+    }
+
+    let key = crypto::storage_key()
+        .ok_or_else(|| CommandError::execute_error("未配置密钥, 请在登录配置中配置'encryption'"))?;
+
+    let mut migrated = 0;
+    if let Ok(dir) = fs::read_dir(config::clients_dir_path()) {
+        for entry in dir.flatten() {
+            let path = entry.path();
+            if !path.is_dir() {
+                continue;
+            }
+
+            match crypto::migrate_client_dir(&path, key) {
+                Ok(n) => migrated += n,
+                Err(e) => warn!("加密{:?}下的登录信息失败: {}", path, e),
+            }
+        }
+    }
+
+    let login_conf = config::service_config_dir_path().join("login.toml");
+    if let Ok(s) = fs::read_to_string(&login_conf) {
+        let regex = Regex::new(r#"(?m)^(\s*password\s*=\s*)(?:'([^']*)'|"([^"\\]*)")"#)
+            .expect("Cannot parse regex");
+
+        let mut passwords = 0;
+        let replaced = regex.replace_all(&s, |caps: &Captures| {
+            let pwd = caps
+                .get(2)
+                .or_else(|| caps.get(3))
+                .map_or("", |m| m.as_str());
+            if crypto::is_sealed_password(pwd) {
+                return caps[0].to_owned();
+            }
+
+            passwords += 1;
+            format!("{}'{}'", &caps[1], key.seal_password(pwd))
+        });
+
+        if passwords != 0 {
+            fs::write(&login_conf, replaced.as_bytes())
+                .map_err(|e| CommandError::execute_error(e.to_string()))?;
+            migrated += passwords;
+        }
+    }
+
+    info!("已加密{}项登录信息", migrated);
+
+    Ok(())
+}
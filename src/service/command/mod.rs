@@ -89,3 +89,4 @@ impl From<std::num::ParseIntError> for CommandError {
 }
 
 pub const PLUGIN_COMMAND: &str = "plugin";
+pub const ENCRYPT_COMMAND: &str = "encrypt";
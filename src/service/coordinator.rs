@@ -0,0 +1,336 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use ricq::RQError;
+
+use crate::config::coordination::{CoordinationConfig, Policy, DEFAULT_CONFIG};
+use crate::config::service::ServiceConfig;
+use crate::contact::group::Group;
+use crate::{global_status, Client};
+
+/// 去重时记录的最近消息数
+const RECENT_CAPACITY: usize = 2048;
+
+/// 群消息以外的群事件的去重记录在最后一次出现后保留的时间
+const EVENT_WINDOW: Duration = Duration::from_secs(5);
+
+/// 群消息的唯一标识: (群号, seq, rand)
+pub type MessageKey = (i64, i32, i32);
+
+/// 群消息以外的群事件的标识: (事件类型, 群号, 事件参数)
+///
+/// 这些事件没有序号, 以其内容区分, 同一内容的事件按各客户端收到的次数编号
+pub type EventKey = (&'static str, i64, i64, i64);
+
+/// 多客户端协调器
+///
+/// 多个客户端处于同一群时, 每个客户端都会收到同一条消息,
+/// 协调器保证每条消息只被分发一次, 并由策略选出的客户端处理
+pub struct Coordinator {
+    config: CoordinationConfig,
+    recent: Mutex<Recent>,
+    recent_events: Mutex<HashMap<EventKey, SeenEvent>>,
+    round_robin: DashMap<i64, AtomicUsize>,
+    rate_limited: DashMap<i64, Instant>,
+}
+
+#[derive(Default)]
+struct Recent {
+    set: HashSet<MessageKey>,
+    queue: VecDeque<MessageKey>,
+}
+
+/// 同一内容的群事件的去重记录
+struct SeenEvent {
+    /// 已分发的次数
+    claimed: usize,
+    /// 每个客户端收到的次数
+    received: HashMap<i64, usize>,
+    last: Instant,
+}
+
+impl Coordinator {
+    pub fn new(config: CoordinationConfig) -> Self {
+        Self {
+            config,
+            recent: Mutex::new(Recent::default()),
+            recent_events: Mutex::new(HashMap::new()),
+            round_robin: DashMap::new(),
+            rate_limited: DashMap::new(),
+        }
+    }
+
+    /// 群使用的策略
+    pub fn policy(&self, group_id: i64) -> Policy {
+        self.config
+            .group(group_id)
+            .and_then(|g| g.policy)
+            .unwrap_or(self.config.default_policy)
+    }
+
+    /// 将消息路由到负责处理的客户端
+    ///
+    /// 返回`None`表示该消息已被分发或无可用客户端, 应丢弃;
+    /// 否则返回负责处理的客户端所持有的群.
+    /// 消息没有序号(`key`为`None`)时无法去重, 由收到消息的客户端处理
+    pub fn route(&self, client: &Client, group_id: i64, key: Option<MessageKey>) -> Option<Group> {
+        if self.policy(group_id) == Policy::Broadcast {
+            return client.find_group(group_id);
+        }
+
+        let Some(key) = key else {
+            return client.find_group(group_id);
+        };
+
+        if !self.claim(key) {
+            return None;
+        }
+
+        self.select(group_id)
+            .and_then(|selected| selected.find_group(group_id))
+    }
+
+    /// 同[`Coordinator::route`], 用于群消息以外的群事件
+    pub fn route_event(&self, client: &Client, group_id: i64, key: EventKey) -> Option<Group> {
+        if !self.claim_event(client.id(), group_id, key) {
+            return None;
+        }
+
+        if self.policy(group_id) == Policy::Broadcast {
+            return client.find_group(group_id);
+        }
+
+        self.select(group_id)
+            .and_then(|selected| selected.find_group(group_id))
+    }
+
+    /// 群消息以外的群事件首次被分发时返回`true`, 策略为[`Policy::Broadcast`]时总是返回`true`
+    ///
+    /// 客户端第n次收到某一内容的事件时, 视为该事件的第n次发生,
+    /// 每次发生只分发一次. 因此短时间内真实重复的事件(如连续戳一戳)不会被当作重复丢弃
+    pub fn claim_event(&self, client_id: i64, group_id: i64, key: EventKey) -> bool {
+        if self.policy(group_id) == Policy::Broadcast {
+            return true;
+        }
+
+        let now = Instant::now();
+        let mut events = self
+            .recent_events
+            .lock()
+            .expect("Cannot lock recent events");
+        events.retain(|_, seen| now.duration_since(seen.last) < EVENT_WINDOW);
+
+        let seen = events.entry(key).or_insert_with(|| SeenEvent {
+            claimed: 0,
+            received: HashMap::new(),
+            last: now,
+        });
+        seen.last = now;
+
+        let received = seen.received.entry(client_id).or_default();
+        *received += 1;
+        if *received > seen.claimed {
+            seen.claimed = *received;
+            return true;
+        }
+
+        false
+    }
+
+    /// 选出负责处理群事件的在线客户端
+    pub fn select(&self, group_id: i64) -> Option<Client> {
+        let candidates: Vec<Client> = global_status()
+            .clients()
+            .into_iter()
+            .filter(|c| c.is_online() && c.find_group(group_id).is_some())
+            .collect();
+
+        let ids: Vec<i64> = candidates.iter().map(Client::id).collect();
+        let id = self.select_id(group_id, &ids)?;
+
+        candidates.into_iter().find(|c| c.id() == id)
+    }
+
+    /// 按优先顺序排在最前的加入了此群的在线客户端, 不受策略影响
+    pub fn primary(&self, group_id: i64) -> Option<Client> {
+        let candidates: Vec<Client> = Client::sorted_list()
+            .into_iter()
+            .filter(|c| c.is_online() && c.find_group(group_id).is_some())
+            .collect();
+
+        let ids: Vec<i64> = candidates.iter().map(Client::id).collect();
+        let id = *self.order(group_id, &ids).first()?;
+
+        candidates.into_iter().find(|c| c.id() == id)
+    }
+
+    /// 客户端被限流时调用, 用于[`Policy::LeastRateLimited`]
+    pub fn mark_rate_limited(&self, client_id: i64) {
+        self.rate_limited.insert(client_id, Instant::now());
+    }
+
+    /// 发送失败是否由限流导致
+    ///
+    /// 消息被限流时服务器不会返回回执, 表现为等待回执超时
+    pub fn is_rate_limited(err: &RQError) -> bool {
+        matches!(err, RQError::Timeout)
+    }
+
+    fn select_id(&self, group_id: i64, candidates: &[i64]) -> Option<i64> {
+        let ordered = self.order(group_id, candidates);
+
+        match self.policy(group_id) {
+            Policy::Broadcast | Policy::PrimaryBackup => ordered.first().copied(),
+            Policy::RoundRobin => {
+                if ordered.is_empty() {
+                    return None;
+                }
+
+                let counter = self
+                    .round_robin
+                    .entry(group_id)
+                    .or_insert_with(|| AtomicUsize::new(0));
+                let index = counter.fetch_add(1, Ordering::Relaxed) % ordered.len();
+
+                Some(ordered[index])
+            }
+            Policy::LeastRateLimited => ordered
+                .iter()
+                .copied()
+                .min_by_key(|id| self.rate_limited.get(id).map(|t| *t)),
+        }
+    }
+
+    /// 按配置的优先顺序排序, 未配置的客户端按账号排序排在其后
+    fn order(&self, group_id: i64, candidates: &[i64]) -> Vec<i64> {
+        let preferred = self
+            .config
+            .group(group_id)
+            .map(|g| &g.clients[..])
+            .unwrap_or(&[]);
+
+        let mut ordered: Vec<i64> = preferred
+            .iter()
+            .copied()
+            .filter(|id| candidates.contains(id))
+            .collect();
+
+        let mut rest: Vec<i64> = candidates
+            .iter()
+            .copied()
+            .filter(|id| !preferred.contains(id))
+            .collect();
+        rest.sort_unstable();
+        ordered.extend(rest);
+
+        ordered
+    }
+
+    /// 首次见到该消息时返回`true`
+    fn claim(&self, key: MessageKey) -> bool {
+        let mut recent = self.recent.lock().expect("Cannot lock recent messages");
+        if !recent.set.insert(key) {
+            return false;
+        }
+
+        recent.queue.push_back(key);
+        if recent.queue.len() > RECENT_CAPACITY {
+            if let Some(old) = recent.queue.pop_front() {
+                recent.set.remove(&old);
+            }
+        }
+
+        true
+    }
+}
+
+static COORDINATOR: OnceLock<Coordinator> = OnceLock::new();
+
+pub fn init_coordinator_service() {
+    coordinator();
+}
+
+pub fn coordinator() -> &'static Coordinator {
+    COORDINATOR.get_or_init(|| {
+        let config =
+            ServiceConfig::<CoordinationConfig>::new("coordination", DEFAULT_CONFIG).read();
+        Coordinator::new(config)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Coordinator;
+    use crate::config::coordination::{CoordinationConfig, GroupCoordination, Policy};
+
+    fn coordinator(policy: Policy, clients: Vec<i64>) -> Coordinator {
+        Coordinator::new(CoordinationConfig {
+            default_policy: Policy::PrimaryBackup,
+            groups: vec![GroupCoordination {
+                id: 1,
+                policy: Some(policy),
+                clients,
+            }],
+        })
+    }
+
+    #[test]
+    fn primary_backup() {
+        let c = coordinator(Policy::PrimaryBackup, vec![30, 10]);
+        assert_eq!(c.select_id(1, &[10, 20, 30]), Some(30));
+        // primary offline
+        assert_eq!(c.select_id(1, &[20, 10]), Some(10));
+        assert_eq!(c.select_id(1, &[20]), Some(20));
+        assert_eq!(c.select_id(1, &[]), None);
+        // default policy for other groups
+        assert_eq!(c.select_id(2, &[30, 20]), Some(20));
+    }
+
+    #[test]
+    fn round_robin() {
+        let c = coordinator(Policy::RoundRobin, vec![]);
+        let picked: Vec<_> = (0..4).filter_map(|_| c.select_id(1, &[20, 10])).collect();
+        assert_eq!(picked, [10, 20, 10, 20]);
+    }
+
+    #[test]
+    fn least_rate_limited() {
+        let c = coordinator(Policy::LeastRateLimited, vec![]);
+        assert_eq!(c.select_id(1, &[10, 20]), Some(10));
+        c.mark_rate_limited(10);
+        assert_eq!(c.select_id(1, &[10, 20]), Some(20));
+        c.mark_rate_limited(20);
+        assert_eq!(c.select_id(1, &[10, 20]), Some(10));
+    }
+
+    #[test]
+    fn claim_event_once() {
+        let c = coordinator(Policy::PrimaryBackup, vec![]);
+        let poke = ("poke", 1, 10, 20);
+        assert!(c.claim_event(100, 1, poke));
+        assert!(!c.claim_event(200, 1, poke));
+        assert!(c.claim_event(100, 1, ("poke", 1, 20, 10)));
+
+        // repeated pokes, client 200 falls behind
+        assert!(c.claim_event(100, 1, poke));
+        assert!(c.claim_event(100, 1, poke));
+        assert!(!c.claim_event(200, 1, poke));
+        assert!(!c.claim_event(200, 1, poke));
+        assert!(c.claim_event(200, 1, poke));
+
+        let c = coordinator(Policy::Broadcast, vec![]);
+        assert!(c.claim_event(100, 1, poke));
+        assert!(c.claim_event(200, 1, poke));
+    }
+
+    #[test]
+    fn claim_once() {
+        let c = coordinator(Policy::PrimaryBackup, vec![]);
+        assert!(c.claim((1, 100, 5)));
+        assert!(!c.claim((1, 100, 5)));
+        assert!(c.claim((1, 101, 5)));
+    }
+}
@@ -0,0 +1,513 @@
+//! 消息记录
+//!
+//! 启用后将收到的群消息与好友消息保存至`service/history/messages.db`,
+//! 消息以[`MessageChain::to_json_lossless`]的格式保存
+
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, Row};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::config::history::{HistoryConfig, DEFAULT_CONFIG};
+use crate::config::service::ServiceConfig;
+use crate::config::service_config_dir_path;
+use crate::error::{AtriError, AtriResult};
+use crate::event::Event;
+use crate::message::MessageChain;
+
+/// 数据库文件名
+pub const DATABASE_FILE: &str = "messages.db";
+
+/// 清理过期消息的间隔
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+static HISTORY_CONFIG: OnceLock<HistoryConfig> = OnceLock::new();
+static HISTORY_STORE: OnceLock<Option<HistoryStore>> = OnceLock::new();
+
+pub fn init_history_service() {
+    history_config();
+}
+
+pub fn history_config() -> &'static HistoryConfig {
+    HISTORY_CONFIG
+        .get_or_init(|| ServiceConfig::<HistoryConfig>::new("history", DEFAULT_CONFIG).read())
+}
+
+/// 全局的消息记录, 未启用或数据库打开失败时为`None`
+pub fn history_store() -> Option<&'static HistoryStore> {
+    HISTORY_STORE
+        .get_or_init(|| {
+            if !history_config().enabled {
+                return None;
+            }
+
+            let dir = service_config_dir_path().join("history");
+            match HistoryStore::open(dir.join(DATABASE_FILE)) {
+                Ok(store) => Some(store),
+                Err(e) => {
+                    error!("打开消息记录数据库失败: {}", e);
+                    None
+                }
+            }
+        })
+        .as_ref()
+}
+
+/// 启动定期清理过期消息的任务, 需在运行时中调用
+pub fn start_retention_task() {
+    let (Some(store), Some(retention)) = (history_store(), history_config().retention()) else {
+        return;
+    };
+
+    tokio::spawn(async move {
+        loop {
+            let before = unix_now() - retention.as_secs() as i64;
+            match store.purge(before).await {
+                Ok(0) => {}
+                Ok(n) => info!("已清理{}条过期消息记录", n),
+                Err(e) => warn!("清理消息记录失败: {}", e),
+            }
+
+            tokio::time::sleep(CLEANUP_INTERVAL).await;
+        }
+    });
+}
+
+/// 记录消息事件, 未启用时不做任何事
+pub fn record_event(event: &Event) {
+    let Some(store) = history_store() else {
+        return;
+    };
+    let conf = history_config();
+
+    let record = match event {
+        Event::GroupMessage(e) if conf.group => HistoryRecord {
+            bot: e.client().id(),
+            contact: HistoryContact::Group(e.group().id()),
+            sender: e.sender().id(),
+            chain: e.message().clone(),
+        },
+        Event::FriendMessage(e) if conf.friend => HistoryRecord {
+            bot: e.client().id(),
+            contact: HistoryContact::Friend(e.friend().id()),
+            sender: e.friend().id(),
+            chain: e.message().clone(),
+        },
+        _ => return,
+    };
+
+    tokio::spawn(async move {
+        if let Err(e) = store.insert(record).await {
+            warn!("保存消息记录失败: {}", e);
+        }
+    });
+}
+
+/// 消息的来源
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryContact {
+    Group(i64),
+    Friend(i64),
+}
+
+impl HistoryContact {
+    fn kind(&self) -> i64 {
+        match self {
+            Self::Group(_) => 0,
+            Self::Friend(_) => 1,
+        }
+    }
+
+    fn id(&self) -> i64 {
+        match self {
+            Self::Group(id) | Self::Friend(id) => *id,
+        }
+    }
+}
+
+/// 一条消息记录
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HistoryRecord {
+    /// 接收消息的客户端
+    pub bot: i64,
+    pub contact: HistoryContact,
+    pub sender: i64,
+    pub chain: MessageChain,
+}
+
+impl HistoryRecord {
+    /// 消息的首个序号
+    pub fn seq(&self) -> i32 {
+        self.chain
+            .metadata()
+            .seqs
+            .first()
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// 发送时间(Unix秒)
+    pub fn time(&self) -> i64 {
+        self.chain.metadata().time as i64
+    }
+}
+
+/// 消息记录的查询条件, 未设置的条件不作限制, 结果按时间倒序排列
+///
+/// ```ignore
+/// let records = history_store()?
+///     .query(HistoryQuery::new().group(114514).sender(1919810).keyword("早上好"))
+///     .await?;
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct HistoryQuery {
+    pub bot: Option<i64>,
+    pub contact: Option<HistoryContact>,
+    pub sender: Option<i64>,
+    /// 起始时间(Unix秒, 包含)
+    pub since: Option<i64>,
+    /// 结束时间(Unix秒, 不包含)
+    pub until: Option<i64>,
+    /// 消息文本包含的关键词
+    pub keyword: Option<String>,
+    pub limit: usize,
+}
+
+impl Default for HistoryQuery {
+    fn default() -> Self {
+        Self {
+            bot: None,
+            contact: None,
+            sender: None,
+            since: None,
+            until: None,
+            keyword: None,
+            limit: 50,
+        }
+    }
+}
+
+impl HistoryQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn bot(mut self, bot: i64) -> Self {
+        self.bot = Some(bot);
+        self
+    }
+
+    pub fn group(mut self, group_id: i64) -> Self {
+        self.contact = Some(HistoryContact::Group(group_id));
+        self
+    }
+
+    pub fn friend(mut self, friend_id: i64) -> Self {
+        self.contact = Some(HistoryContact::Friend(friend_id));
+        self
+    }
+
+    pub fn sender(mut self, sender: i64) -> Self {
+        self.sender = Some(sender);
+        self
+    }
+
+    pub fn between(mut self, since: i64, until: i64) -> Self {
+        self.since = Some(since);
+        self.until = Some(until);
+        self
+    }
+
+    pub fn keyword<S: Into<String>>(mut self, keyword: S) -> Self {
+        self.keyword = Some(keyword.into());
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    fn to_sql(&self) -> (String, Vec<Value>) {
+        let mut sql =
+            String::from("SELECT bot, kind, contact, sender, chain FROM messages WHERE 1 = 1");
+        let mut params: Vec<Value> = vec![];
+
+        let mut cond = |sql_cond: &str, value: Value| {
+            sql.push_str(" AND ");
+            sql.push_str(sql_cond);
+            params.push(value);
+        };
+
+        if let Some(bot) = self.bot {
+            cond("bot = ?", bot.into());
+        }
+        if let Some(contact) = self.contact {
+            cond("kind = ?", contact.kind().into());
+            cond("contact = ?", contact.id().into());
+        }
+        if let Some(sender) = self.sender {
+            cond("sender = ?", sender.into());
+        }
+        if let Some(since) = self.since {
+            cond("time >= ?", since.into());
+        }
+        if let Some(until) = self.until {
+            cond("time < ?", until.into());
+        }
+        if let Some(keyword) = &self.keyword {
+            cond("instr(text, ?) > 0", keyword.clone().into());
+        }
+
+        sql.push_str(" ORDER BY time DESC, id DESC LIMIT ?");
+        params.push((self.limit as i64).into());
+
+        (sql, params)
+    }
+}
+
+/// 消息记录数据库
+#[derive(Clone)]
+pub struct HistoryStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl HistoryStore {
+    pub fn open<P: AsRef<Path>>(path: P) -> AtriResult<Self> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let conn = Connection::open(path).map_err(db_error)?;
+        Self::init(conn)
+    }
+
+    /// 使用内存数据库, 不会保存至磁盘
+    pub fn open_in_memory() -> AtriResult<Self> {
+        let conn = Connection::open_in_memory().map_err(db_error)?;
+        Self::init(conn)
+    }
+
+    fn init(conn: Connection) -> AtriResult<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS messages (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                bot INTEGER NOT NULL,
+                kind INTEGER NOT NULL,
+                contact INTEGER NOT NULL,
+                sender INTEGER NOT NULL,
+                seq INTEGER NOT NULL,
+                time INTEGER NOT NULL,
+                text TEXT NOT NULL,
+                chain TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS messages_contact ON messages (kind, contact, time);
+            CREATE INDEX IF NOT EXISTS messages_sender ON messages (sender, time);
+            CREATE INDEX IF NOT EXISTS messages_seq ON messages (kind, contact, seq);",
+        )
+        .map_err(db_error)?;
+
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    async fn with_conn<F, T>(&self, f: F) -> AtriResult<T>
+    where
+        F: FnOnce(&Connection) -> rusqlite::Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap_or_else(|e| e.into_inner());
+            f(&conn)
+        })
+        .await
+        .map_err(|e| AtriError::IO(io::Error::new(io::ErrorKind::Other, e)))?
+        .map_err(db_error)
+    }
+
+    pub async fn insert(&self, record: HistoryRecord) -> AtriResult<()> {
+        let seq = record.seq();
+        let time = record.time();
+        let text = record.chain.plain_text();
+        let chain = record.chain.to_json_lossless().map_err(|_| {
+            AtriError::InvalidMessage("message chain contains an element that cannot be encoded")
+        })?;
+
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO messages (bot, kind, contact, sender, seq, time, text, chain)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    record.bot,
+                    record.contact.kind(),
+                    record.contact.id(),
+                    record.sender,
+                    seq,
+                    time,
+                    text,
+                    chain
+                ],
+            )
+            .map(|_| ())
+        })
+        .await
+    }
+
+    pub async fn query(&self, query: HistoryQuery) -> AtriResult<Vec<HistoryRecord>> {
+        self.with_conn(move |conn| {
+            let (sql, params) = query.to_sql();
+            let mut stmt = conn.prepare(&sql)?;
+            let rows = stmt.query_map(params_from_iter(params), read_record)?;
+
+            Ok(rows.filter_map(|r| r.ok().flatten()).collect())
+        })
+        .await
+    }
+
+    /// 根据序号查找群消息, 用于解析回复
+    pub async fn find_group_message(
+        &self,
+        group_id: i64,
+        seq: i32,
+    ) -> AtriResult<Option<HistoryRecord>> {
+        self.find_by_seq(HistoryContact::Group(group_id), seq).await
+    }
+
+    pub async fn find_by_seq(
+        &self,
+        contact: HistoryContact,
+        seq: i32,
+    ) -> AtriResult<Option<HistoryRecord>> {
+        self.with_conn(move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT bot, kind, contact, sender, chain FROM messages
+                WHERE kind = ?1 AND contact = ?2 AND seq = ?3 ORDER BY id DESC LIMIT 1",
+            )?;
+            let mut rows =
+                stmt.query_map(params![contact.kind(), contact.id(), seq], read_record)?;
+
+            Ok(rows.next().transpose()?.flatten())
+        })
+        .await
+    }
+
+    /// 删除`before`(Unix秒)之前的消息, 返回删除的数量
+    pub async fn purge(&self, before: i64) -> AtriResult<usize> {
+        self.with_conn(move |conn| conn.execute("DELETE FROM messages WHERE time < ?1", [before]))
+            .await
+    }
+}
+
+/// 读取一行记录, 消息无法解析时为`None`
+fn read_record(row: &Row<'_>) -> rusqlite::Result<Option<HistoryRecord>> {
+    let bot: i64 = row.get(0)?;
+    let kind: i64 = row.get(1)?;
+    let contact: i64 = row.get(2)?;
+    let sender: i64 = row.get(3)?;
+    let chain: String = row.get(4)?;
+
+    let chain = match MessageChain::from_json(&chain) {
+        Ok(chain) => chain,
+        Err(e) => {
+            warn!("无法解析消息记录: {}", e);
+            return Ok(None);
+        }
+    };
+
+    let contact = match kind {
+        0 => HistoryContact::Group(contact),
+        _ => HistoryContact::Friend(contact),
+    };
+
+    Ok(Some(HistoryRecord {
+        bot,
+        contact,
+        sender,
+        chain,
+    }))
+}
+
+fn db_error(e: rusqlite::Error) -> AtriError {
+    AtriError::IO(io::Error::new(io::ErrorKind::Other, e))
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HistoryContact, HistoryQuery, HistoryRecord, HistoryStore};
+    use crate::message::{MessageChain, MessageElement};
+
+    fn record(group: i64, sender: i64, seq: i32, time: i32, text: &str) -> HistoryRecord {
+        let mut chain = MessageChain::from(vec![MessageElement::Text(text.into())]);
+        chain.metadata_mut().seqs = vec![seq];
+        chain.metadata_mut().time = time;
+        chain.metadata_mut().sender = sender;
+
+        HistoryRecord {
+            bot: 1,
+            contact: HistoryContact::Group(group),
+            sender,
+            chain,
+        }
+    }
+
+    #[test]
+    fn query() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let store = HistoryStore::open_in_memory().unwrap();
+            store
+                .insert(record(10, 100, 1, 1000, "早上好"))
+                .await
+                .unwrap();
+            store
+                .insert(record(10, 101, 2, 2000, "晚上好"))
+                .await
+                .unwrap();
+            store
+                .insert(record(11, 100, 1, 3000, "早上好啊"))
+                .await
+                .unwrap();
+
+            let by_group = store.query(HistoryQuery::new().group(10)).await.unwrap();
+            assert_eq!(by_group.len(), 2);
+            assert_eq!(by_group[0].seq(), 2);
+
+            let by_keyword = store
+                .query(HistoryQuery::new().sender(100).keyword("早上"))
+                .await
+                .unwrap();
+            assert_eq!(by_keyword.len(), 2);
+
+            let by_time = store
+                .query(HistoryQuery::new().between(1500, 3000))
+                .await
+                .unwrap();
+            assert_eq!(by_time.len(), 1);
+            assert_eq!(by_time[0].chain.plain_text(), "晚上好");
+
+            let found = store.find_group_message(11, 1).await.unwrap().unwrap();
+            assert_eq!(found.time(), 3000);
+            assert!(store.find_group_message(11, 2).await.unwrap().is_none());
+
+            assert_eq!(store.purge(2500).await.unwrap(), 2);
+            assert_eq!(store.query(HistoryQuery::new()).await.unwrap().len(), 1);
+        });
+    }
+}
@@ -9,9 +9,12 @@ use tokio::fs;
 use tokio::io::AsyncWriteExt;
 use tracing::{error, info, warn};
 
+use crate::client::crypto;
+use crate::client::proxy::Proxy;
 use crate::client::ClientConfiguration;
 use crate::config::login::{LoginConfig, DEFAULT_CONFIG};
 use crate::error::AtriResult;
+use crate::service::cache::prepare_contacts;
 use crate::{config, global_status, Client};
 
 pub async fn login_clients() -> Result<(), RQError> {
@@ -55,6 +58,12 @@ pub async fn login_clients() -> Result<(), RQError> {
         set_auto_reconnect(true);
     }
 
+    if let Err(e) = crypto::init_storage_key(&login_conf.encryption) {
+        // 避免以明文保存登录信息
+        error!("读取登录信息密钥失败: {}, 将不会登录客户端", e);
+        return Err(io::Error::new(io::ErrorKind::Other, e.to_string()).into());
+    }
+
     let clients_path = config::clients_dir_path();
     if !clients_path.is_dir() {
         fs::create_dir(&clients_path).await?;
@@ -66,7 +75,26 @@ pub async fn login_clients() -> Result<(), RQError> {
         }
 
         let account = client.account;
-        let pwd = client.password;
+        let pwd = match client
+            .password
+            .as_deref()
+            .map(crypto::open_password)
+            .transpose()
+        {
+            Ok(pwd) => pwd,
+            Err(e) => {
+                error!("Client({})无法解密密码: {}, 跳过登陆", account, e);
+                continue;
+            }
+        };
+
+        let proxy = match client.proxy.as_deref().map(str::parse::<Proxy>).transpose() {
+            Ok(proxy) => proxy,
+            Err(e) => {
+                error!("Client({})代理配置错误: {}, 跳过登陆", account, e);
+                continue;
+            }
+        };
 
         let client_device = clients_path.join(account.to_string()).join("device.json");
         if !client_device.is_file() {
@@ -84,6 +112,9 @@ pub async fn login_clients() -> Result<(), RQError> {
                         .protocol
                         .unwrap_or(login_conf.default_protocol)
                         .as_version(),
+                    proxy,
+                    address_family: client.address_family,
+                    server_override: client.server_override,
                 },
             )
             .await
@@ -91,12 +122,7 @@ pub async fn login_clients() -> Result<(), RQError> {
                 Ok(client) => {
                     global_status().add_client(client.clone());
                     info!("{}登陆成功", client);
-                    if let Err(e) = client.refresh_friend_list().await {
-                        warn!("{}刷新好友列表失败: {:?}", client, e);
-                    }
-                    if let Err(e) = client.refresh_group_list().await {
-                        warn!("{}刷新群列表失败: {:?}", client, e);
-                    }
+                    prepare_contacts(&client).await;
                     Ok(client)
                 }
                 Err(e) => {
@@ -124,7 +150,7 @@ async fn login_client(
     password: &Option<String>,
     conf: ClientConfiguration,
 ) -> AtriResult<Client> {
-    let client = Client::new(account, conf).await;
+    let client = Client::new(account, conf).await?;
     client.start().await?;
 
     info!("Client({})登陆中", account);
@@ -144,7 +170,11 @@ async fn login_client(
                             let tokenp = client.work_dir().join("token.json");
 
                             if let Ok(mut f) = fs::File::create(&tokenp).await {
-                                let token = client.request_client().gen_token().await;
+                                let token = client.gen_token().await;
+                                let token = match crypto::storage_key() {
+                                    Some(key) => token.encrypt(key),
+                                    None => token,
+                                };
                                 let s = serde_json::to_string_pretty(&token)
                                     .expect("Cannot serialize token");
                                 let _ = f.write_all(s.as_bytes()).await;
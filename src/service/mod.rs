@@ -9,11 +9,15 @@ use std::{fs, io};
 use serde::{Deserialize, Serialize};
 use tracing::error;
 
+pub mod cache;
 pub mod command;
+pub mod coordinator;
+pub mod history;
 pub mod listener;
 pub mod log;
 pub mod login;
 pub mod plugin;
+pub mod scheduler;
 
 fn get_service_path() -> &'static PathBuf {
     static PATH: OnceLock<PathBuf> = OnceLock::new();
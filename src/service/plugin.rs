@@ -355,6 +355,8 @@ impl Plugin {
             _ => return false,
         }
 
+        crate::service::scheduler::scheduler().cancel_owner(self.handle);
+
         if self.should_drop {
             let ptr = self.instance.swap(null_mut(), Ordering::Acquire);
             (self.vtb.disable)(ptr);
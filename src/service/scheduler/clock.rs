@@ -0,0 +1,68 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use tokio::sync::watch;
+
+/// 调度器使用的时钟
+pub trait Clock: Send + Sync + 'static {
+    fn now(&self) -> SystemTime;
+
+    /// 等待至`at`, `at`已过去时立即返回
+    fn sleep_until(&self, at: SystemTime) -> Pin<Box<dyn Future<Output = ()> + Send>>;
+}
+
+/// 系统时钟
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn sleep_until(&self, at: SystemTime) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        let duration = at.duration_since(SystemTime::now()).unwrap_or_default();
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// 手动推进的时钟, 用于测试
+#[derive(Clone)]
+pub struct MockClock {
+    now: Arc<watch::Sender<SystemTime>>,
+}
+
+impl MockClock {
+    pub fn new(start: SystemTime) -> Self {
+        let (tx, _) = watch::channel(start);
+        Self { now: Arc::new(tx) }
+    }
+
+    pub fn set(&self, now: SystemTime) {
+        self.now.send_replace(now);
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.now.send_modify(|now| *now += duration);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> SystemTime {
+        *self.now.borrow()
+    }
+
+    fn sleep_until(&self, at: SystemTime) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        let mut rx = self.now.subscribe();
+        Box::pin(async move {
+            while *rx.borrow_and_update() < at {
+                if rx.changed().await.is_err() {
+                    // 时钟已被丢弃, 不会再到达
+                    std::future::pending::<()>().await;
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,327 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use time::{Date, Month, OffsetDateTime, UtcOffset};
+
+/// 查找下次运行时间的最大范围, 覆盖如`0 0 29 2 *`的表达式
+const SEARCH_LIMIT: i64 = 9 * 366 * 24 * 60 * 60;
+
+/// cron表达式
+///
+/// 支持5个字段(分 时 日 月 周)或6个字段(秒 分 时 日 月 周),
+/// 每个字段支持`*`, `?`, `a`, `a-b`, `*/n`, `a-b/n`, `a/n`及以`,`分隔的列表.
+/// 周的取值为0-7, 0与7均为周日. 日与周均被限制时, 满足其一即可
+///
+/// 另支持`@yearly`, `@monthly`, `@weekly`, `@daily`和`@hourly`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    source: String,
+    seconds: u64,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(expr: &str) -> Result<Self, CronError> {
+        let source = expr.trim();
+        let expanded = match source {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            or => or,
+        };
+
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let (seconds, rest) = match fields.len() {
+            5 => ("0", &fields[..]),
+            6 => (fields[0], &fields[1..]),
+            n => return Err(CronError::new(format!("expected 5 or 6 fields, found {n}"))),
+        };
+
+        let mut weekdays = parse_field(rest[4], 0, 7, "weekday")?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+
+        Ok(Self {
+            source: source.to_owned(),
+            seconds: parse_field(seconds, 0, 59, "second")?,
+            minutes: parse_field(rest[0], 0, 59, "minute")?,
+            hours: parse_field(rest[1], 0, 23, "hour")?,
+            days: parse_field(rest[2], 1, 31, "day")?,
+            months: parse_field(rest[3], 1, 12, "month")?,
+            weekdays,
+            days_restricted: !is_wildcard(rest[2]),
+            weekdays_restricted: !is_wildcard(rest[4]),
+        })
+    }
+
+    /// 原始表达式
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// `after`之后(不含)的下次运行时间, 以`offset`时区计算
+    pub fn next_after(&self, after: SystemTime, offset: UtcOffset) -> Option<SystemTime> {
+        let offset = offset.whole_seconds() as i64;
+        let after = after.duration_since(UNIX_EPOCH).ok()?.as_secs() as i64;
+
+        // 以本地时间的秒数计算, 按UTC解析得到本地时间的各字段
+        let mut t = after + offset + 1;
+        let limit = t + SEARCH_LIMIT;
+
+        while t <= limit {
+            let dt = OffsetDateTime::from_unix_timestamp(t).ok()?;
+
+            if !has(self.months, dt.month() as u8) {
+                t = next_month(dt)?;
+                continue;
+            }
+
+            if !self.day_matches(dt) {
+                t = floor(t, 86400) + 86400;
+                continue;
+            }
+
+            if !has(self.hours, dt.hour()) {
+                t = floor(t, 3600) + 3600;
+                continue;
+            }
+
+            if !has(self.minutes, dt.minute()) {
+                t = floor(t, 60) + 60;
+                continue;
+            }
+
+            if !has(self.seconds, dt.second()) {
+                t += 1;
+                continue;
+            }
+
+            return Some(UNIX_EPOCH + Duration::from_secs((t - offset) as u64));
+        }
+
+        None
+    }
+
+    fn day_matches(&self, dt: OffsetDateTime) -> bool {
+        let day = has(self.days, dt.day());
+        let weekday = has(self.weekdays, dt.weekday().number_days_from_sunday());
+
+        if self.days_restricted && self.weekdays_restricted {
+            day || weekday
+        } else {
+            day && weekday
+        }
+    }
+}
+
+impl FromStr for CronSchedule {
+    type Err = CronError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl Display for CronSchedule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+fn has(bits: u64, value: u8) -> bool {
+    bits & (1 << value) != 0
+}
+
+fn floor(t: i64, unit: i64) -> i64 {
+    t - t.rem_euclid(unit)
+}
+
+fn next_month(dt: OffsetDateTime) -> Option<i64> {
+    let (year, month) = match dt.month() {
+        Month::December => (dt.year() + 1, Month::January),
+        or => (dt.year(), or.next()),
+    };
+
+    let date = Date::from_calendar_date(year, month, 1).ok()?;
+    Some(date.midnight().assume_utc().unix_timestamp())
+}
+
+fn is_wildcard(field: &str) -> bool {
+    field == "*" || field == "?"
+}
+
+fn parse_field(field: &str, min: u8, max: u8, name: &str) -> Result<u64, CronError> {
+    let invalid = || CronError::new(format!("invalid {name} field: {field}"));
+    let parse = |s: &str| -> Result<u8, CronError> {
+        let value = s.parse::<u8>().map_err(|_| invalid())?;
+        if value < min || value > max {
+            return Err(CronError::new(format!(
+                "{name} {value} out of range {min}-{max}"
+            )));
+        }
+
+        Ok(value)
+    };
+
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step = step.parse::<u8>().map_err(|_| invalid())?;
+                if step == 0 {
+                    return Err(invalid());
+                }
+                (range, Some(step))
+            }
+            None => (part, None),
+        };
+
+        let (start, end) = match range {
+            "*" | "?" => (min, max),
+            range => match range.split_once('-') {
+                Some((start, end)) => (parse(start)?, parse(end)?),
+                None if step.is_some() => (parse(range)?, max),
+                None => {
+                    let value = parse(range)?;
+                    (value, value)
+                }
+            },
+        };
+
+        if start > end {
+            return Err(invalid());
+        }
+
+        let step = step.unwrap_or(1) as usize;
+        for value in (start..=end).step_by(step) {
+            bits |= 1 << value;
+        }
+    }
+
+    Ok(bits)
+}
+
+#[derive(Debug)]
+pub struct CronError {
+    message: String,
+}
+
+impl CronError {
+    fn new(message: String) -> Self {
+        Self { message }
+    }
+}
+
+impl Display for CronError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("cron expression error: ")?;
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for CronError {}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use time::macros::datetime;
+    use time::UtcOffset;
+
+    use super::CronSchedule;
+
+    fn at(dt: time::OffsetDateTime) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(dt.unix_timestamp() as u64)
+    }
+
+    fn next(expr: &str, from: time::OffsetDateTime, offset: UtcOffset) -> SystemTime {
+        CronSchedule::parse(expr)
+            .unwrap()
+            .next_after(at(from), offset)
+            .unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        assert!(CronSchedule::parse("0 8 * * *").is_ok());
+        assert!(CronSchedule::parse("*/15 0-5,12 1 */2 1-5").is_ok());
+        assert!(CronSchedule::parse("30 0 8 * * 7").is_ok());
+        assert!(CronSchedule::parse("@daily").is_ok());
+
+        assert!(CronSchedule::parse("0 8 * *").is_err());
+        assert!(CronSchedule::parse("60 8 * * *").is_err());
+        assert!(CronSchedule::parse("0 8 * * 8").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+        assert!(CronSchedule::parse("5-1 * * * *").is_err());
+    }
+
+    #[test]
+    fn next_after() {
+        let utc = UtcOffset::UTC;
+
+        assert_eq!(
+            next("0 8 * * *", datetime!(2023-01-01 07:59:59 UTC), utc),
+            at(datetime!(2023-01-01 08:00:00 UTC))
+        );
+        assert_eq!(
+            next("0 8 * * *", datetime!(2023-01-01 08:00:00 UTC), utc),
+            at(datetime!(2023-01-02 08:00:00 UTC))
+        );
+        assert_eq!(
+            next("*/15 * * * *", datetime!(2023-01-01 10:07:00 UTC), utc),
+            at(datetime!(2023-01-01 10:15:00 UTC))
+        );
+        assert_eq!(
+            next("30 * * * * *", datetime!(2023-01-01 10:07:30 UTC), utc),
+            at(datetime!(2023-01-01 10:08:30 UTC))
+        );
+        assert_eq!(
+            next("@yearly", datetime!(2023-12-31 23:59:59 UTC), utc),
+            at(datetime!(2024-01-01 00:00:00 UTC))
+        );
+        assert_eq!(
+            next("0 0 29 2 *", datetime!(2023-03-01 00:00:00 UTC), utc),
+            at(datetime!(2024-02-29 00:00:00 UTC))
+        );
+    }
+
+    #[test]
+    fn weekday() {
+        let utc = UtcOffset::UTC;
+
+        // 2023-01-01为周日
+        assert_eq!(
+            next("0 9 * * 1-5", datetime!(2023-01-01 00:00:00 UTC), utc),
+            at(datetime!(2023-01-02 09:00:00 UTC))
+        );
+        assert_eq!(
+            next("0 0 * * 7", datetime!(2023-01-02 00:00:00 UTC), utc),
+            at(datetime!(2023-01-08 00:00:00 UTC))
+        );
+        // 日与周均被限制时满足其一即可
+        assert_eq!(
+            next("0 0 15 * 3", datetime!(2023-01-01 00:00:00 UTC), utc),
+            at(datetime!(2023-01-04 00:00:00 UTC))
+        );
+    }
+
+    #[test]
+    fn offset() {
+        let offset = UtcOffset::from_hms(8, 0, 0).unwrap();
+        assert_eq!(
+            next("0 8 * * *", datetime!(2023-01-01 00:00:00 UTC), offset),
+            at(datetime!(2023-01-02 00:00:00 UTC))
+        );
+    }
+}
@@ -0,0 +1,693 @@
+//! 定时任务
+//!
+//! 支持cron表达式, 固定间隔与一次性延迟, 任务由[`Scheduler`]统一调度.
+//! 插件注册的任务在插件被禁用时自动取消
+//!
+//! ```ignore
+//! scheduler()
+//!     .task(Schedule::cron("0 8 * * *")?)
+//!     .name("daily_report")
+//!     .persist(true)
+//!     .start(|| async {
+//!         // 发送日报
+//!     });
+//! ```
+
+pub mod clock;
+pub mod cron;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+use std::io;
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use dashmap::DashMap;
+use futures::FutureExt;
+use serde::{Deserialize, Serialize};
+use time::UtcOffset;
+use tokio::task::AbortHandle;
+use tracing::{error, warn};
+
+use crate::config::scheduler::{SchedulerConfig, DEFAULT_CONFIG};
+use crate::config::service::ServiceConfig;
+use crate::config::service_config_dir_path;
+use clock::{Clock, SystemClock};
+use cron::{CronError, CronSchedule};
+
+/// 保存下次运行时间的文件名
+pub const NEXT_RUNS_FILE: &str = "next_runs.json";
+
+/// 固定间隔任务的最小间隔
+pub const MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+static SCHEDULER_CONFIG: OnceLock<SchedulerConfig> = OnceLock::new();
+static SCHEDULER: OnceLock<Scheduler> = OnceLock::new();
+
+/// 需在创建运行时之前调用, 以便获取系统时区
+pub fn init_scheduler_service() {
+    scheduler();
+}
+
+pub fn scheduler_config() -> &'static SchedulerConfig {
+    SCHEDULER_CONFIG
+        .get_or_init(|| ServiceConfig::<SchedulerConfig>::new("scheduler", DEFAULT_CONFIG).read())
+}
+
+/// 全局的调度器
+pub fn scheduler() -> &'static Scheduler {
+    SCHEDULER.get_or_init(|| {
+        let conf = scheduler_config();
+
+        let offset = match conf.utc_offset {
+            Some(minutes) => UtcOffset::from_whole_seconds(minutes * 60).unwrap_or_else(|e| {
+                error!("定时任务时区设置错误: {}, 将使用UTC", e);
+                UtcOffset::UTC
+            }),
+            None => UtcOffset::current_local_offset().unwrap_or_else(|e| {
+                warn!("无法获取系统时区: {}, 定时任务将使用默认时区UTC+8", e);
+                UtcOffset::from_hms(8, 0, 0).unwrap()
+            }),
+        };
+
+        if !conf.persist {
+            return Scheduler::new(SystemClock, offset);
+        }
+
+        let dir = service_config_dir_path().join("scheduler");
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            error!("无法创建定时任务目录: {}", e);
+        }
+
+        Scheduler::with_persistence(SystemClock, offset, dir.join(NEXT_RUNS_FILE))
+    })
+}
+
+/// 任务的运行计划
+#[derive(Debug, Clone)]
+pub enum Schedule {
+    Cron(CronSchedule),
+    /// 每隔固定时间运行, 应使用[`Schedule::interval`]创建, 小于[`MIN_INTERVAL`]的间隔按其计算
+    Interval(Duration),
+    /// 延迟后运行一次
+    Once(Duration),
+}
+
+impl Schedule {
+    pub fn cron(expr: &str) -> Result<Self, CronError> {
+        CronSchedule::parse(expr).map(Self::Cron)
+    }
+
+    /// 间隔小于[`MIN_INTERVAL`]时返回错误
+    pub fn interval(period: Duration) -> Result<Self, ScheduleError> {
+        if period < MIN_INTERVAL {
+            return Err(ScheduleError::IntervalTooShort(period));
+        }
+
+        Ok(Self::Interval(period))
+    }
+
+    /// 计算下次运行时间, `fired`表示任务是否已运行过
+    fn next_run(&self, after: SystemTime, fired: bool, offset: UtcOffset) -> Option<SystemTime> {
+        match self {
+            Self::Cron(cron) => cron.next_after(after, offset),
+            Self::Interval(d) => Some(after + (*d).max(MIN_INTERVAL)),
+            Self::Once(d) => (!fired).then_some(after + *d),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ScheduleError {
+    Cron(CronError),
+    IntervalTooShort(Duration),
+}
+
+impl Display for ScheduleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Cron(e) => Display::fmt(e, f),
+            Self::IntervalTooShort(d) => write!(
+                f,
+                "interval {}ms is shorter than {}ms",
+                d.as_millis(),
+                MIN_INTERVAL.as_millis()
+            ),
+        }
+    }
+}
+
+impl Error for ScheduleError {}
+
+impl From<CronError> for ScheduleError {
+    fn from(err: CronError) -> Self {
+        Self::Cron(err)
+    }
+}
+
+/// 重启后对错过的运行的处理方式, 仅对保存了下次运行时间的任务有效
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissedRunPolicy {
+    /// 跳过, 等待下次运行
+    #[default]
+    Skip,
+    /// 立即补运行一次
+    RunOnce,
+}
+
+impl From<u8> for MissedRunPolicy {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Self::RunOnce,
+            _ => Self::Skip,
+        }
+    }
+}
+
+type Job = Arc<dyn Fn() -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
+
+struct TaskEntry {
+    name: Option<String>,
+    owner: Option<usize>,
+    next_run: Arc<Mutex<Option<SystemTime>>>,
+    abort: AbortHandle,
+}
+
+#[derive(Clone)]
+pub struct Scheduler(Arc<imp::Scheduler>);
+
+impl Scheduler {
+    /// 使用`clock`计时, cron表达式以`offset`时区计算
+    pub fn new<C: Clock>(clock: C, offset: UtcOffset) -> Self {
+        Self::build(clock, offset, None)
+    }
+
+    /// 同[`Scheduler::new`], 并将启用了保存的任务的下次运行时间保存至`path`
+    pub fn with_persistence<C: Clock, P: AsRef<Path>>(
+        clock: C,
+        offset: UtcOffset,
+        path: P,
+    ) -> Self {
+        Self::build(clock, offset, Some(NextRunStore::load(path.as_ref())))
+    }
+
+    fn build<C: Clock>(clock: C, offset: UtcOffset, store: Option<NextRunStore>) -> Self {
+        Self(Arc::new(imp::Scheduler {
+            clock: Arc::new(clock),
+            offset,
+            store,
+            tasks: DashMap::new(),
+            next_id: AtomicU64::new(1),
+        }))
+    }
+
+    pub fn clock(&self) -> &dyn Clock {
+        &*self.0.clock
+    }
+
+    pub fn offset(&self) -> UtcOffset {
+        self.0.offset
+    }
+
+    pub fn task(&self, schedule: Schedule) -> TaskBuilder {
+        TaskBuilder {
+            scheduler: self.clone(),
+            schedule,
+            name: None,
+            owner: None,
+            persist: false,
+            missed: MissedRunPolicy::default(),
+        }
+    }
+
+    /// 取消任务, 已保存的下次运行时间不会被删除
+    pub fn cancel(&self, id: u64) -> bool {
+        match self.0.tasks.remove(&id) {
+            Some((_, entry)) => {
+                entry.abort.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 取消插件注册的所有任务, 返回取消的数量
+    pub fn cancel_owner(&self, owner: usize) -> usize {
+        let ids: Vec<u64> = self
+            .0
+            .tasks
+            .iter()
+            .filter(|e| e.owner == Some(owner))
+            .map(|e| *e.key())
+            .collect();
+
+        ids.into_iter().filter(|id| self.cancel(*id)).count()
+    }
+
+    pub fn next_run(&self, id: u64) -> Option<SystemTime> {
+        let entry = self.0.tasks.get(&id)?;
+        let next_run = *entry.next_run.lock().unwrap_or_else(|e| e.into_inner());
+        next_run
+    }
+
+    pub fn task_name(&self, id: u64) -> Option<String> {
+        self.0.tasks.get(&id)?.name.clone()
+    }
+
+    pub fn contains(&self, id: u64) -> bool {
+        self.0.tasks.contains_key(&id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.tasks.is_empty()
+    }
+
+    fn start(&self, builder: TaskBuilder, job: Job) -> TaskHandle {
+        let TaskBuilder {
+            schedule,
+            name,
+            owner,
+            persist,
+            missed,
+            ..
+        } = builder;
+
+        let id = self.0.next_id.fetch_add(1, Ordering::Relaxed);
+        let next_run = Arc::new(Mutex::new(None));
+        let key = name.clone().filter(|_| persist);
+        let (ready_tx, ready_rx) = tokio::sync::oneshot::channel::<()>();
+
+        let scheduler = self.clone();
+        let shared_next_run = next_run.clone();
+        let handle = tokio::spawn(async move {
+            // 等待任务登记完成
+            let _ = ready_rx.await;
+            scheduler
+                .run(id, schedule, missed, key, shared_next_run, job)
+                .await;
+        });
+
+        self.0.tasks.insert(
+            id,
+            TaskEntry {
+                name,
+                owner,
+                next_run,
+                abort: handle.abort_handle(),
+            },
+        );
+        let _ = ready_tx.send(());
+
+        TaskHandle {
+            id,
+            scheduler: self.clone(),
+        }
+    }
+
+    async fn run(
+        &self,
+        id: u64,
+        schedule: Schedule,
+        missed: MissedRunPolicy,
+        key: Option<String>,
+        next_run: Arc<Mutex<Option<SystemTime>>>,
+        job: Job,
+    ) {
+        let inner = &self.0;
+        let now = inner.clock.now();
+        let store = inner.store.as_ref().filter(|_| key.is_some());
+
+        let mut next = match store.and_then(|s| s.get(key.as_deref()?)) {
+            Some(at) if at >= now => Some(at),
+            Some(_) if missed == MissedRunPolicy::RunOnce => Some(now),
+            Some(_) => match schedule {
+                Schedule::Once(_) => None,
+                _ => schedule.next_run(now, true, inner.offset),
+            },
+            None => schedule.next_run(now, false, inner.offset),
+        };
+
+        while let Some(at) = next {
+            *next_run.lock().unwrap_or_else(|e| e.into_inner()) = Some(at);
+            if let (Some(store), Some(key)) = (store, &key) {
+                store.set(key, Some(at)).await;
+            }
+
+            inner.clock.sleep_until(at).await;
+
+            if let Err(e) = AssertUnwindSafe(job()).catch_unwind().await {
+                error!("定时任务#{}运行时发生错误: {:?}", id, e);
+            }
+
+            let now = inner.clock.now();
+            // 落后多个周期时不补运行
+            next = schedule.next_run(at, true, inner.offset).and_then(|n| {
+                if n < now {
+                    schedule.next_run(now, true, inner.offset)
+                } else {
+                    Some(n)
+                }
+            });
+        }
+
+        if let (Some(store), Some(key)) = (store, &key) {
+            store.set(key, None).await;
+        }
+        inner.tasks.remove(&id);
+    }
+}
+
+/// 定时任务构建器
+pub struct TaskBuilder {
+    scheduler: Scheduler,
+    schedule: Schedule,
+    name: Option<String>,
+    owner: Option<usize>,
+    persist: bool,
+    missed: MissedRunPolicy,
+}
+
+impl TaskBuilder {
+    /// 任务名称, 保存下次运行时间时作为键, 需全局唯一
+    pub fn name<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// 任务所属的插件, 插件被禁用时任务将被取消
+    pub fn owner(mut self, plugin_handle: usize) -> Self {
+        self.owner = Some(plugin_handle);
+        self
+    }
+
+    /// 是否保存下次运行时间, 需同时设置名称
+    pub fn persist(mut self, persist: bool) -> Self {
+        self.persist = persist;
+        self
+    }
+
+    pub fn missed_run_policy(mut self, policy: MissedRunPolicy) -> Self {
+        self.missed = policy;
+        self
+    }
+
+    /// 开始调度, 需在tokio运行时中调用. 任务的每次运行不会重叠
+    pub fn start<F, Fu>(self, job: F) -> TaskHandle
+    where
+        F: Fn() -> Fu + Send + Sync + 'static,
+        Fu: Future<Output = ()> + Send + 'static,
+    {
+        let scheduler = self.scheduler.clone();
+        let job: Job =
+            Arc::new(move || -> Pin<Box<dyn Future<Output = ()> + Send>> { Box::pin(job()) });
+        scheduler.start(self, job)
+    }
+}
+
+#[derive(Clone)]
+pub struct TaskHandle {
+    id: u64,
+    scheduler: Scheduler,
+}
+
+impl TaskHandle {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn next_run(&self) -> Option<SystemTime> {
+        self.scheduler.next_run(self.id)
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.scheduler.contains(self.id)
+    }
+
+    pub fn cancel(&self) -> bool {
+        self.scheduler.cancel(self.id)
+    }
+}
+
+/// 任务的下次运行时间(Unix毫秒)
+struct NextRunStore {
+    path: PathBuf,
+    runs: Mutex<HashMap<String, u64>>,
+    /// 保证文件的写入按顺序进行
+    write: tokio::sync::Mutex<()>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct NextRunFile {
+    runs: HashMap<String, u64>,
+}
+
+impl NextRunStore {
+    fn load(path: &Path) -> Self {
+        let file: NextRunFile = std::fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+
+        Self {
+            path: path.to_owned(),
+            runs: Mutex::new(file.runs),
+            write: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<SystemTime> {
+        let runs = self.runs.lock().unwrap_or_else(|e| e.into_inner());
+        runs.get(key)
+            .map(|millis| UNIX_EPOCH + Duration::from_millis(*millis))
+    }
+
+    async fn set(&self, key: &str, at: Option<SystemTime>) {
+        {
+            let mut runs = self.runs.lock().unwrap_or_else(|e| e.into_inner());
+            match at.and_then(|at| at.duration_since(UNIX_EPOCH).ok()) {
+                Some(d) => runs.insert(key.to_owned(), d.as_millis() as u64),
+                None => runs.remove(key),
+            };
+        }
+
+        if let Err(e) = self.save().await {
+            warn!("保存定时任务运行时间失败: {}", e);
+        }
+    }
+
+    async fn save(&self) -> io::Result<()> {
+        let _write = self.write.lock().await;
+
+        // 获取写锁后再读取, 保证最后写入的是最新的内容
+        let file = NextRunFile {
+            runs: self.runs.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+        };
+        let json = serde_json::to_vec(&file)?;
+        tokio::fs::write(&self.path, json).await
+    }
+}
+
+mod imp {
+    use std::sync::atomic::AtomicU64;
+    use std::sync::Arc;
+
+    use dashmap::DashMap;
+    use time::UtcOffset;
+
+    use super::clock::Clock;
+    use super::{NextRunStore, TaskEntry};
+
+    pub struct Scheduler {
+        pub clock: Arc<dyn Clock>,
+        pub offset: UtcOffset,
+        pub store: Option<NextRunStore>,
+        pub tasks: DashMap<u64, TaskEntry>,
+        pub next_id: AtomicU64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use time::UtcOffset;
+
+    use super::clock::MockClock;
+    use super::{MissedRunPolicy, Schedule, Scheduler};
+
+    fn block_on<F: std::future::Future>(f: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(f)
+    }
+
+    async fn settle() {
+        for _ in 0..8 {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    fn counter() -> (
+        Arc<AtomicUsize>,
+        impl Fn() -> std::future::Ready<()> + Send + Sync,
+    ) {
+        let count = Arc::new(AtomicUsize::new(0));
+        let c = count.clone();
+        (count, move || {
+            c.fetch_add(1, Ordering::SeqCst);
+            std::future::ready(())
+        })
+    }
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    #[test]
+    fn interval() {
+        block_on(async {
+            let clock = MockClock::new(UNIX_EPOCH + secs(1000));
+            let scheduler = Scheduler::new(clock.clone(), UtcOffset::UTC);
+            let (count, job) = counter();
+
+            assert!(Schedule::interval(Duration::ZERO).is_err());
+            assert!(Schedule::interval(Duration::from_millis(999)).is_err());
+
+            let task = scheduler
+                .task(Schedule::interval(secs(10)).unwrap())
+                .start(job);
+            settle().await;
+            assert_eq!(task.next_run(), Some(UNIX_EPOCH + secs(1010)));
+
+            clock.advance(secs(9));
+            settle().await;
+            assert_eq!(count.load(Ordering::SeqCst), 0);
+
+            clock.advance(secs(1));
+            settle().await;
+            assert_eq!(count.load(Ordering::SeqCst), 1);
+            assert_eq!(task.next_run(), Some(UNIX_EPOCH + secs(1020)));
+
+            // 落后多个周期时不补运行
+            clock.advance(secs(35));
+            settle().await;
+            assert_eq!(count.load(Ordering::SeqCst), 2);
+            assert_eq!(task.next_run(), Some(UNIX_EPOCH + secs(1055)));
+
+            assert!(task.cancel());
+            assert!(!task.is_active());
+            clock.advance(secs(100));
+            settle().await;
+            assert_eq!(count.load(Ordering::SeqCst), 2);
+        });
+    }
+
+    #[test]
+    fn once_and_cron() {
+        block_on(async {
+            let clock = MockClock::new(UNIX_EPOCH);
+            let scheduler = Scheduler::new(clock.clone(), UtcOffset::UTC);
+
+            let (once_count, once_job) = counter();
+            let once = scheduler.task(Schedule::Once(secs(5))).start(once_job);
+
+            let (cron_count, cron_job) = counter();
+            let cron = scheduler
+                .task(Schedule::cron("0 * * * * *").unwrap())
+                .start(cron_job);
+            settle().await;
+            assert_eq!(cron.next_run(), Some(UNIX_EPOCH + secs(60)));
+
+            clock.advance(secs(5));
+            settle().await;
+            assert_eq!(once_count.load(Ordering::SeqCst), 1);
+            assert!(!once.is_active());
+
+            clock.advance(secs(55));
+            settle().await;
+            assert_eq!(cron_count.load(Ordering::SeqCst), 1);
+            assert_eq!(cron.next_run(), Some(UNIX_EPOCH + secs(120)));
+        });
+    }
+
+    #[test]
+    fn owner() {
+        block_on(async {
+            let clock = MockClock::new(UNIX_EPOCH);
+            let scheduler = Scheduler::new(clock.clone(), UtcOffset::UTC);
+
+            for owner in [1, 1, 2] {
+                let (_, job) = counter();
+                scheduler
+                    .task(Schedule::Interval(secs(1)))
+                    .owner(owner)
+                    .start(job);
+            }
+            settle().await;
+
+            assert_eq!(scheduler.cancel_owner(1), 2);
+            assert_eq!(scheduler.len(), 1);
+        });
+    }
+
+    #[test]
+    fn missed_run() {
+        let path =
+            std::env::temp_dir().join(format!("atri_scheduler_test_{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        block_on(async {
+            let clock = MockClock::new(UNIX_EPOCH + secs(1000));
+            let scheduler = Scheduler::with_persistence(clock, UtcOffset::UTC, &path);
+            let (_, job) = counter();
+
+            scheduler
+                .task(Schedule::Interval(secs(100)))
+                .name("report")
+                .persist(true)
+                .start(job);
+            settle().await;
+        });
+
+        // 重启时已错过保存的运行时间
+        for (policy, expected) in [(MissedRunPolicy::Skip, 0), (MissedRunPolicy::RunOnce, 1)] {
+            block_on(async {
+                let clock = MockClock::new(UNIX_EPOCH + secs(1500));
+                let scheduler = Scheduler::with_persistence(clock, UtcOffset::UTC, &path);
+                let (count, job) = counter();
+
+                let task = scheduler
+                    .task(Schedule::Interval(secs(100)))
+                    .name("report")
+                    .persist(true)
+                    .missed_run_policy(policy)
+                    .start(job);
+                settle().await;
+
+                assert_eq!(count.load(Ordering::SeqCst), expected);
+                assert_eq!(task.next_run(), Some(UNIX_EPOCH + secs(1600)));
+                task.cancel();
+            });
+
+            // 恢复为错过的状态
+            let store = super::NextRunStore::load(&path);
+            store.set("report", Some(UNIX_EPOCH + secs(1100)));
+        }
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -7,7 +7,8 @@ mod sys;
 
 pub use sys::handle_standard_output;
 
-use crate::service::command::{builtin::handle_plugin_command, PLUGIN_COMMAND};
+use crate::service::command::builtin::{handle_encrypt_command, handle_plugin_command};
+use crate::service::command::{ENCRYPT_COMMAND, PLUGIN_COMMAND};
 use crate::terminal::buffer::{INPUT_BUFFER, INPUT_CACHE};
 use crate::PluginManager;
 use crossterm::cursor::MoveToColumn;
@@ -162,7 +163,11 @@ pub fn start_read_input(manager: &mut PluginManager) -> Result<(), Box<dyn Error
                             continue;
                         }
                         "help" | "?" | "h" => {
-                            static INFOS: &[&str] = &["help: 显示本帮助", "exit: 退出程序"];
+                            static INFOS: &[&str] = &[
+                                "help: 显示本帮助",
+                                "exit: 退出程序",
+                                "encrypt: 加密已保存的登录信息",
+                            ];
 
                             let mut s = String::from('\n');
                             for &info in INFOS {
@@ -180,6 +185,11 @@ pub fn start_read_input(manager: &mut PluginManager) -> Result<(), Box<dyn Error
                                 error!("{}", e);
                             }
                         }
+                        encrypt if encrypt.starts_with(ENCRYPT_COMMAND) => {
+                            if let Err(e) = handle_encrypt_command(encrypt) {
+                                error!("{}", e);
+                            }
+                        }
                         or => {
                             info!("未知的命令 '{}', 使用 'help' 显示帮助信息", or);
                         }